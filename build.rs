@@ -2,8 +2,13 @@ extern crate bindgen;
 extern crate cc;
 
 use std::env;
-use std::path::PathBuf;
-use std::process::Command;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The include lookup of the runtime shader compiler, so both find the same files.
+#[path = "src/app/graphics/shader_include.rs"]
+mod shader_include;
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
@@ -18,8 +23,22 @@ fn main() {
         .allowlist_var("GLFW_NO_API")
         .allowlist_var("GLFW_RESIZABLE")
         .allowlist_var("GLFW_FALSE")
+        .allowlist_var("GLFW_TRUE")
+        .allowlist_var("GLFW_PRESS")
+        .allowlist_var("GLFW_KEY_F12")
+        .allowlist_var("GLFW_KEY_T")
         .allowlist_type("GLFWwindow")
         .allowlist_type("GLFWmonitor")
+        .allowlist_type("GLFWframebuffersizefun")
+        .allowlist_type("GLFWkeyfun")
+        .allowlist_var("GLFW_OPENGL_API")
+        .allowlist_var("GLFW_CONTEXT_VERSION_MAJOR")
+        .allowlist_var("GLFW_CONTEXT_VERSION_MINOR")
+        .allowlist_var("GLFW_OPENGL_PROFILE")
+        .allowlist_var("GLFW_OPENGL_CORE_PROFILE")
+        .allowlist_var("GLFW_OPENGL_FORWARD_COMPAT")
+        .allowlist_var("GLFW_SAMPLES")
+        .allowlist_var("GLFW_SRGB_CAPABLE")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .generate()
         .expect("Unable to generate glfw bindings");
@@ -28,6 +47,104 @@ fn main() {
         .expect("Couldn't write glfw bindings!");
     println!("cargo:rustc-link-lib=glfw");
 
+    let gl_include_dir: &str = "/usr/include/GL";
+    let gl_header_file: String = format!("{}/glcorearb.h", gl_include_dir);
+    // Only types and constants: the functions are loaded at runtime through GLFW, see src/gl.
+    let bindings_gl = bindgen::Builder::default()
+        .header(gl_header_file)
+        .allowlist_type("GLenum")
+        .allowlist_type("GLboolean")
+        .allowlist_type("GLbitfield")
+        .allowlist_type("GLint")
+        .allowlist_type("GLuint")
+        .allowlist_type("GLsizei")
+        .allowlist_type("GLfloat")
+        .allowlist_type("GLdouble")
+        .allowlist_type("GLchar")
+        .allowlist_type("GLubyte")
+        .allowlist_type("GLintptr")
+        .allowlist_type("GLsizeiptr")
+        .allowlist_var("GL_VERSION")
+        .allowlist_var("GL_RENDERER")
+        .allowlist_var("GL_UPPER_LEFT")
+        .allowlist_var("GL_ZERO_TO_ONE")
+        .allowlist_var("GL_DEPTH_TEST")
+        .allowlist_var("GL_LESS")
+        .allowlist_var("GL_FRAMEBUFFER_SRGB")
+        .allowlist_var("GL_COLOR_BUFFER_BIT")
+        .allowlist_var("GL_DEPTH_BUFFER_BIT")
+        .allowlist_var("GL_VERTEX_SHADER")
+        .allowlist_var("GL_FRAGMENT_SHADER")
+        .allowlist_var("GL_COMPILE_STATUS")
+        .allowlist_var("GL_LINK_STATUS")
+        .allowlist_var("GL_INFO_LOG_LENGTH")
+        .allowlist_var("GL_UNIFORM_BUFFER")
+        .allowlist_var("GL_DYNAMIC_STORAGE_BIT")
+        .allowlist_var("GL_FLOAT")
+        .allowlist_var("GL_FALSE")
+        .allowlist_var("GL_TEXTURE_2D")
+        .allowlist_var("GL_SRGB8_ALPHA8")
+        .allowlist_var("GL_RGBA")
+        .allowlist_var("GL_UNSIGNED_BYTE")
+        .allowlist_var("GL_TEXTURE_MIN_FILTER")
+        .allowlist_var("GL_TEXTURE_MAG_FILTER")
+        .allowlist_var("GL_LINEAR")
+        .allowlist_var("GL_LINEAR_MIPMAP_LINEAR")
+        .allowlist_var("GL_TEXTURE_WRAP_S")
+        .allowlist_var("GL_TEXTURE_WRAP_T")
+        .allowlist_var("GL_REPEAT")
+        .allowlist_var("GL_TRIANGLES")
+        .allowlist_var("GL_UNSIGNED_INT")
+        .allowlist_type("PFNGLCLIPCONTROLPROC")
+        .allowlist_type("PFNGLENABLEPROC")
+        .allowlist_type("PFNGLVIEWPORTPROC")
+        .allowlist_type("PFNGLCLEARCOLORPROC")
+        .allowlist_type("PFNGLCLEARDEPTHPROC")
+        .allowlist_type("PFNGLCLEARPROC")
+        .allowlist_type("PFNGLDEPTHFUNCPROC")
+        .allowlist_type("PFNGLGETSTRINGPROC")
+        .allowlist_type("PFNGLCREATESHADERPROC")
+        .allowlist_type("PFNGLSHADERSOURCEPROC")
+        .allowlist_type("PFNGLCOMPILESHADERPROC")
+        .allowlist_type("PFNGLGETSHADERIVPROC")
+        .allowlist_type("PFNGLGETSHADERINFOLOGPROC")
+        .allowlist_type("PFNGLDELETESHADERPROC")
+        .allowlist_type("PFNGLCREATEPROGRAMPROC")
+        .allowlist_type("PFNGLATTACHSHADERPROC")
+        .allowlist_type("PFNGLLINKPROGRAMPROC")
+        .allowlist_type("PFNGLGETPROGRAMIVPROC")
+        .allowlist_type("PFNGLGETPROGRAMINFOLOGPROC")
+        .allowlist_type("PFNGLDELETEPROGRAMPROC")
+        .allowlist_type("PFNGLUSEPROGRAMPROC")
+        .allowlist_type("PFNGLCREATEBUFFERSPROC")
+        .allowlist_type("PFNGLNAMEDBUFFERSTORAGEPROC")
+        .allowlist_type("PFNGLNAMEDBUFFERSUBDATAPROC")
+        .allowlist_type("PFNGLDELETEBUFFERSPROC")
+        .allowlist_type("PFNGLBINDBUFFERBASEPROC")
+        .allowlist_type("PFNGLCREATEVERTEXARRAYSPROC")
+        .allowlist_type("PFNGLVERTEXARRAYVERTEXBUFFERPROC")
+        .allowlist_type("PFNGLVERTEXARRAYELEMENTBUFFERPROC")
+        .allowlist_type("PFNGLENABLEVERTEXARRAYATTRIBPROC")
+        .allowlist_type("PFNGLVERTEXARRAYATTRIBFORMATPROC")
+        .allowlist_type("PFNGLVERTEXARRAYATTRIBBINDINGPROC")
+        .allowlist_type("PFNGLBINDVERTEXARRAYPROC")
+        .allowlist_type("PFNGLDELETEVERTEXARRAYSPROC")
+        .allowlist_type("PFNGLCREATETEXTURESPROC")
+        .allowlist_type("PFNGLTEXTURESTORAGE2DPROC")
+        .allowlist_type("PFNGLTEXTURESUBIMAGE2DPROC")
+        .allowlist_type("PFNGLGENERATETEXTUREMIPMAPPROC")
+        .allowlist_type("PFNGLTEXTUREPARAMETERIPROC")
+        .allowlist_type("PFNGLBINDTEXTUREUNITPROC")
+        .allowlist_type("PFNGLDELETETEXTURESPROC")
+        .allowlist_type("PFNGLDRAWELEMENTSPROC")
+        .allowlist_type("PFNGLFINISHPROC")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .expect("Unable to generate gl bindings");
+    bindings_gl
+        .write_to_file(out_path.join("bindings_gl.rs"))
+        .expect("Couldn't write gl bindings!");
+
     let vulkan_include_dir: &str = "/usr/include/vulkan";
     let vulkan_header_file: String = format!("{}/vulkan.h", vulkan_include_dir);
 
@@ -125,6 +242,9 @@ fn main() {
         .allowlist_item("VkDynamicState")
         .allowlist_item("VkPipelineDynamicStateCreateInfo")
         .allowlist_item("VkPipelineLayoutCreateInfo")
+        .allowlist_item("VkPushConstantRange")
+        .allowlist_item("VkSpecializationInfo")
+        .allowlist_item("VkSpecializationMapEntry")
         .allowlist_item("VkPipelineLayout")
         .allowlist_item("VkGraphicsPipelineCreateInfo")
         .allowlist_item("VkPipeline")
@@ -151,6 +271,67 @@ fn main() {
         .allowlist_item("VkPipelineStageFlags")
         .allowlist_item("VkPresentInfoKHR")
         .allowlist_item("VkCommandPoolCreateFlagBits")
+        .allowlist_item("VkVertexInputBindingDescription")
+        .allowlist_item("VkVertexInputAttributeDescription")
+        .allowlist_item("VkVertexInputRate")
+        .allowlist_item("VkBuffer")
+        .allowlist_item("VkBufferCreateInfo")
+        .allowlist_item("VkBufferUsageFlagBits")
+        .allowlist_item("VkBufferUsageFlags")
+        .allowlist_item("VkDeviceSize")
+        .allowlist_item("VkDeviceMemory")
+        .allowlist_item("VkMemoryRequirements")
+        .allowlist_item("VkMemoryAllocateInfo")
+        .allowlist_item("VkMemoryMapFlags")
+        .allowlist_item("VkMemoryPropertyFlagBits")
+        .allowlist_item("VkMemoryPropertyFlags")
+        .allowlist_item("VkPhysicalDeviceMemoryProperties")
+        .allowlist_item("VkMemoryHeapFlagBits")
+        .allowlist_item("VkPhysicalDeviceType")
+        .allowlist_item("VkIndexType")
+        .allowlist_item("VkBufferCopy")
+        .allowlist_item("VkBufferImageCopy")
+        .allowlist_item("VkImageSubresourceLayers")
+        .allowlist_item("VkOffset3D")
+        .allowlist_item("VkExtent3D")
+        .allowlist_item("VkMemoryBarrier")
+        .allowlist_item("VkBufferMemoryBarrier")
+        .allowlist_item("VkImageMemoryBarrier")
+        .allowlist_item("VkDependencyFlags")
+        .allowlist_item("VkCommandBufferUsageFlagBits")
+        .allowlist_item("VK_QUEUE_FAMILY_IGNORED")
+        .allowlist_item("VkDescriptorType")
+        .allowlist_item("VkDescriptorSetLayout")
+        .allowlist_item("VkDescriptorSetLayoutBinding")
+        .allowlist_item("VkDescriptorSetLayoutCreateInfo")
+        .allowlist_item("VkDescriptorPool")
+        .allowlist_item("VkDescriptorPoolSize")
+        .allowlist_item("VkDescriptorPoolCreateInfo")
+        .allowlist_item("VkDescriptorSet")
+        .allowlist_item("VkDescriptorSetAllocateInfo")
+        .allowlist_item("VkDescriptorBufferInfo")
+        .allowlist_item("VkDescriptorImageInfo")
+        .allowlist_item("VkWriteDescriptorSet")
+        .allowlist_item("VkCopyDescriptorSet")
+        .allowlist_item("VkImageCreateInfo")
+        .allowlist_item("VkImageType")
+        .allowlist_item("VkImageTiling")
+        .allowlist_item("VkSampler")
+        .allowlist_item("VkSamplerCreateInfo")
+        .allowlist_item("VkFilter")
+        .allowlist_item("VkSamplerAddressMode")
+        .allowlist_item("VkSamplerMipmapMode")
+        .allowlist_item("VkBorderColor")
+        .allowlist_item("VkCompareOp")
+        .allowlist_item("VkImageAspectFlags")
+        .allowlist_item("VkImageUsageFlags")
+        .allowlist_item("VkFormatProperties")
+        .allowlist_item("VkFormatFeatureFlagBits")
+        .allowlist_item("VkFormatFeatureFlags")
+        .allowlist_item("VkPipelineDepthStencilStateCreateInfo")
+        .allowlist_item("VkStencilOpState")
+        .allowlist_item("VkClearDepthStencilValue")
+        .allowlist_item("VkImageBlit")
         .default_enum_style(bindgen::EnumVariation::Rust {
             non_exhaustive: true,
         })
@@ -162,29 +343,323 @@ fn main() {
         .expect("Couldn't write vulkan bindings!");
     println!("cargo:rustc-link-lib=vulkan");
 
-    let mut build_hello_shaders = Command::new("glslc");
-    build_hello_shaders
-        .arg("src/shaders/shader.frag")
-        .arg("-o")
-        .arg("src/shaders/shader.frag.spv");
-    let output = build_hello_shaders
-        .output()
-        .expect("Failed to compile fragment shader");
-
-    if !output.status.success() {
-        panic!("Shader compilation failed: {:?}", output);
+    let stb_include_dir: &str = "/usr/include/stb";
+    let stb_header_file: String = format!("{}/stb_image.h", stb_include_dir);
+    let bindings_stb = bindgen::Builder::default()
+        .header(stb_header_file)
+        .allowlist_item("STBI_rgb_alpha")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .expect("Unable to generate stb bindings");
+    bindings_stb
+        .write_to_file(out_path.join("bindings_stb.rs"))
+        .expect("Couldn't write stb bindings!");
+    println!("cargo:rustc-link-lib=stb");
+
+    let shaderc_include_dir: &str = "/usr/include/shaderc";
+    let shaderc_header_file: String = format!("{}/shaderc.h", shaderc_include_dir);
+    // Shaders are compiled to SPIR-V at runtime for hot reload, see src/app/graphics/shader.rs.
+    let bindings_shaderc = bindgen::Builder::default()
+        .header(shaderc_header_file)
+        .allowlist_type("shaderc_compiler_t")
+        .allowlist_type("shaderc_compile_options_t")
+        .allowlist_type("shaderc_compilation_result_t")
+        .allowlist_type("shaderc_shader_kind")
+        .allowlist_type("shaderc_source_language")
+        .allowlist_type("shaderc_target_env")
+        .allowlist_type("shaderc_env_version")
+        .allowlist_type("shaderc_compilation_status")
+        .allowlist_type("shaderc_include_type")
+        .allowlist_type("shaderc_include_result")
+        .allowlist_type("shaderc_include_resolve_fn")
+        .allowlist_type("shaderc_include_result_release_fn")
+        .default_enum_style(bindgen::EnumVariation::Rust {
+            non_exhaustive: true,
+        })
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .expect("Unable to generate shaderc bindings");
+    bindings_shaderc
+        .write_to_file(out_path.join("bindings_shaderc.rs"))
+        .expect("Couldn't write shaderc bindings!");
+    println!("cargo:rustc-link-lib=shaderc_shared");
+
+    compile_shaders(&out_path);
+}
+
+// build.rs can not use the shaderc bindings it generates, so the functions the shader build needs
+// are declared by hand. The numbers are from shaderc.h.
+mod shaderc {
+    use std::ffi::{c_char, c_int, c_void};
+
+    pub const VERTEX_SHADER: c_int = 0;
+    pub const FRAGMENT_SHADER: c_int = 1;
+    pub const COMPUTE_SHADER: c_int = 2;
+    pub const SOURCE_LANGUAGE_GLSL: c_int = 0;
+    pub const SOURCE_LANGUAGE_HLSL: c_int = 1;
+    pub const TARGET_ENV_VULKAN: c_int = 0;
+    pub const ENV_VERSION_VULKAN_1_0: u32 = 1 << 22;
+    pub const INCLUDE_TYPE_RELATIVE: c_int = 0;
+    pub const COMPILATION_STATUS_SUCCESS: c_int = 0;
+
+    #[repr(C)]
+    pub struct IncludeResult {
+        pub source_name: *const c_char,
+        pub source_name_length: usize,
+        pub content: *const c_char,
+        pub content_length: usize,
+        pub user_data: *mut c_void,
+    }
+
+    pub type ResolveFn = unsafe extern "C" fn(
+        *mut c_void,
+        *const c_char,
+        c_int,
+        *const c_char,
+        usize,
+    ) -> *mut IncludeResult;
+    pub type ReleaseFn = unsafe extern "C" fn(*mut c_void, *mut IncludeResult);
+
+    #[link(name = "shaderc_shared")]
+    unsafe extern "C" {
+        pub unsafe fn shaderc_compiler_initialize() -> *mut c_void;
+        pub unsafe fn shaderc_compiler_release(compiler: *mut c_void);
+        pub unsafe fn shaderc_compile_options_initialize() -> *mut c_void;
+        pub unsafe fn shaderc_compile_options_release(options: *mut c_void);
+        pub unsafe fn shaderc_compile_options_set_source_language(
+            options: *mut c_void,
+            lang: c_int,
+        );
+        pub unsafe fn shaderc_compile_options_set_target_env(
+            options: *mut c_void,
+            target: c_int,
+            version: u32,
+        );
+        pub unsafe fn shaderc_compile_options_set_include_callbacks(
+            options: *mut c_void,
+            resolver: Option<ResolveFn>,
+            result_releaser: Option<ReleaseFn>,
+            user_data: *mut c_void,
+        );
+        pub unsafe fn shaderc_compile_into_spv(
+            compiler: *const c_void,
+            source_text: *const c_char,
+            source_text_size: usize,
+            shader_kind: c_int,
+            input_file_name: *const c_char,
+            entry_point_name: *const c_char,
+            additional_options: *const c_void,
+        ) -> *mut c_void;
+        pub unsafe fn shaderc_result_release(result: *mut c_void);
+        pub unsafe fn shaderc_result_get_length(result: *const c_void) -> usize;
+        pub unsafe fn shaderc_result_get_bytes(result: *const c_void) -> *const c_char;
+        pub unsafe fn shaderc_result_get_error_message(result: *const c_void) -> *const c_char;
+        pub unsafe fn shaderc_result_get_compilation_status(result: *const c_void) -> c_int;
+    }
+}
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+// Every shader in src/shaders is compiled to OUT_DIR/shaders/<name>.spv, and OUT_DIR/shaders.rs
+// lists them for src/app/graphics/shader_registry.rs to embed. A shader that does not compile
+// fails the build.
+fn compile_shaders(out_path: &Path) {
+    let shader_dir: PathBuf =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/shaders");
+    println!("cargo:rerun-if-changed={}", shader_dir.display());
+
+    let mut sources: Vec<PathBuf> = Vec::new();
+    find_shaders(&shader_dir, &mut sources);
+    sources.sort();
+
+    let compiler: *mut c_void = unsafe { shaderc::shaderc_compiler_initialize() };
+    assert!(!compiler.is_null(), "Unable to initialize shaderc");
+
+    let mut registry: String = String::from(
+        "// Generated by build.rs from the shaders in src/shaders.\n\
+         static EMBEDDED_SHADERS: &[(&str, &Aligned<[u8]>)] = &[\n",
+    );
+    for source in &sources {
+        // Names use forward slashes on every platform, e.g. "shader.vert" or "post/blur.frag".
+        let name: String = source
+            .strip_prefix(&shader_dir)
+            .unwrap()
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let code: Vec<u8> = compile_shader(compiler, &shader_dir, source)
+            .unwrap_or_else(|message| panic!("{}", message));
+
+        let spirv_path: PathBuf = out_path.join("shaders").join(format!("{}.spv", name));
+        fs::create_dir_all(spirv_path.parent().unwrap()).expect("Couldn't create the shader dir!");
+        fs::write(&spirv_path, &code).expect("Couldn't write SPIR-V!");
+        registry.push_str(&format!(
+            "    ({:?}, &Aligned(*include_bytes!({:?}))),\n",
+            name,
+            spirv_path.display().to_string()
+        ));
+    }
+    registry.push_str("];\n");
+    unsafe { shaderc::shaderc_compiler_release(compiler) };
+
+    fs::write(out_path.join("shaders.rs"), registry).expect("Couldn't write the shader registry!");
+}
+
+fn find_shaders(dir: &Path, sources: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("Couldn't read the shader dir!") {
+        let path: PathBuf = entry.expect("Couldn't read the shader dir!").path();
+        if path.is_dir() {
+            find_shaders(&path, sources);
+        } else if shader_kind(&path).is_some() {
+            sources.push(path);
+        }
     }
+}
+
+// Named like ShaderStage::from_path expects: .vert, .frag or .comp, followed by .hlsl for HLSL.
+// Anything else, such as an included .glsl file, is not a shader of its own.
+fn shader_kind(path: &Path) -> Option<(c_int, c_int)> {
+    let file_name: &str = path.file_name()?.to_str()?;
+    let (file_name, language): (&str, c_int) = match file_name.strip_suffix(".hlsl") {
+        Some(file_name) => (file_name, shaderc::SOURCE_LANGUAGE_HLSL),
+        None => (file_name, shaderc::SOURCE_LANGUAGE_GLSL),
+    };
+    let kind: c_int = match Path::new(file_name).extension()?.to_str()? {
+        "vert" => shaderc::VERTEX_SHADER,
+        "frag" => shaderc::FRAGMENT_SHADER,
+        "comp" => shaderc::COMPUTE_SHADER,
+        _ => return None,
+    };
+
+    Some((kind, language))
+}
+
+fn compile_shader(
+    compiler: *mut c_void,
+    shader_dir: &Path,
+    path: &Path,
+) -> Result<Vec<u8>, String> {
+    let (kind, language): (c_int, c_int) = shader_kind(path).unwrap();
+    let source: String = fs::read_to_string(path)
+        .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+    let input_file_name: CString = CString::new(path.display().to_string()).unwrap();
+    let entry_point_name: CString = CString::new("main").unwrap();
+    let include_dirs: Vec<PathBuf> = vec![shader_dir.to_path_buf()];
+
+    let (status, code, message): (c_int, Vec<u8>, String) = unsafe {
+        let options: *mut c_void = shaderc::shaderc_compile_options_initialize();
+        shaderc::shaderc_compile_options_set_source_language(options, language);
+        shaderc::shaderc_compile_options_set_target_env(
+            options,
+            shaderc::TARGET_ENV_VULKAN,
+            shaderc::ENV_VERSION_VULKAN_1_0,
+        );
+        shaderc::shaderc_compile_options_set_include_callbacks(
+            options,
+            Some(resolve_include_callback),
+            Some(release_include_callback),
+            &include_dirs as *const Vec<PathBuf> as *mut c_void,
+        );
+        let result: *mut c_void = shaderc::shaderc_compile_into_spv(
+            compiler,
+            source.as_ptr() as *const c_char,
+            source.len(),
+            kind,
+            input_file_name.as_ptr(),
+            entry_point_name.as_ptr(),
+            options,
+        );
+        shaderc::shaderc_compile_options_release(options);
+        if result.is_null() {
+            return Err(format!(
+                "shaderc ran out of memory compiling {}",
+                path.display()
+            ));
+        }
+
+        let bytes: *const c_char = shaderc::shaderc_result_get_bytes(result);
+        let length: usize = shaderc::shaderc_result_get_length(result);
+        let code: Vec<u8> = if bytes.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(bytes as *const u8, length).to_vec()
+        };
+        let message: *const c_char = shaderc::shaderc_result_get_error_message(result);
+        let message: String = if message.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        };
+        let status: c_int = shaderc::shaderc_result_get_compilation_status(result);
+        shaderc::shaderc_result_release(result);
+
+        (status, code, message)
+    };
+
+    if status != shaderc::COMPILATION_STATUS_SUCCESS {
+        return Err(format!(
+            "Failed to compile {}\n{}",
+            path.display(),
+            message.trim_end()
+        ));
+    }
+    // shaderc writes the words in the byte order of the build machine, as the registry reads them.
+    if code.len() < 4
+        || !code.len().is_multiple_of(4)
+        || u32::from_ne_bytes([code[0], code[1], code[2], code[3]]) != SPIRV_MAGIC
+    {
+        return Err(format!(
+            "shaderc returned invalid SPIR-V for {}",
+            path.display()
+        ));
+    }
+
+    Ok(code)
+}
+
+unsafe extern "C" fn resolve_include_callback(
+    user_data: *mut c_void,
+    requested_source: *const c_char,
+    include_type: c_int,
+    requesting_source: *const c_char,
+    _include_depth: usize,
+) -> *mut shaderc::IncludeResult {
+    let include_dirs: &Vec<PathBuf> = unsafe { &*(user_data as *const Vec<PathBuf>) };
+    let requested: String = unsafe { CStr::from_ptr(requested_source) }
+        .to_string_lossy()
+        .into_owned();
+    let requesting: String = unsafe { CStr::from_ptr(requesting_source) }
+        .to_string_lossy()
+        .into_owned();
+
+    let relative: bool = include_type == shaderc::INCLUDE_TYPE_RELATIVE;
+    let strings: Box<(String, String)> = Box::new(shader_include::include_source(
+        &requested,
+        relative,
+        &requesting,
+        include_dirs,
+    ));
+
+    // The strings live in the result's user_data until shaderc releases it.
+    let result: Box<shaderc::IncludeResult> = Box::new(shaderc::IncludeResult {
+        source_name: strings.0.as_ptr() as *const c_char,
+        source_name_length: strings.0.len(),
+        content: strings.1.as_ptr() as *const c_char,
+        content_length: strings.1.len(),
+        user_data: Box::into_raw(strings) as *mut c_void,
+    });
+
+    Box::into_raw(result)
+}
 
-    build_hello_shaders = Command::new("glslc");
-    build_hello_shaders
-        .arg("src/shaders/shader.vert")
-        .arg("-o")
-        .arg("src/shaders/shader.vert.spv");
-    let output = build_hello_shaders
-        .output()
-        .expect("Failed to compile vertex shader");
-
-    if !output.status.success() {
-        panic!("Shader compilation failed: {:?}", output);
+unsafe extern "C" fn release_include_callback(
+    _user_data: *mut c_void,
+    result: *mut shaderc::IncludeResult,
+) {
+    if result.is_null() {
+        return;
     }
+    let result: Box<shaderc::IncludeResult> = unsafe { Box::from_raw(result) };
+    drop(unsafe { Box::from_raw(result.user_data as *mut (String, String)) });
 }
@@ -0,0 +1,257 @@
+use std::fmt;
+
+use super::config::Config;
+use super::graphics::{GpuSelection, GraphicsType};
+
+pub const USAGE: &str = "\
+Usage: oito-caneco [OPTIONS]
+
+Options:
+    --backend <vulkan|opengl|null>  Graphics backend [default: vulkan]
+    --width <PIXELS>                Window width [default: 800]
+    --height <PIXELS>               Window height [default: 600]
+    --vsync                         Wait for the vertical blank before presenting
+    --validation                    Enable the Vulkan validation layers [default in debug builds]
+    --gpu <INDEX|NAME|VENDOR:DEVICE>
+                                    Use this GPU, by index, part of its name or hexadecimal IDs
+    --frames <N>                    Stop after N frames
+    --headless                      Render offscreen and write every frame to frames/*.png
+    -h, --help                      Print this help
+
+Options override the settings in oito-caneco.toml, looked up in the working directory and then
+in $XDG_CONFIG_HOME/oito-caneco/config.toml, and in OITO_CANECO_* environment variables.";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Config),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(&'static str),
+    // A switch such as --vsync was given a value.
+    UnexpectedValue(&'static str),
+    InvalidValue {
+        option: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => write!(f, "unknown option {}", option),
+            CliError::MissingValue(option) => write!(f, "{} needs a value", option),
+            CliError::UnexpectedValue(option) => write!(f, "{} does not take a value", option),
+            CliError::InvalidValue {
+                option,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value \"{}\" for {}: expected {}",
+                value, option, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+// Applies the arguments, without the program name, on top of `config`. Values can follow their
+// option as the next argument or after an equals sign: `--width 1280` or `--width=1280`.
+pub fn parse<I>(args: I, mut config: Config) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline_value): (&str, Option<String>) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+
+        match name {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--backend" => {
+                let value: String = value("--backend", inline_value, &mut args)?;
+                config.backend = match value.as_str() {
+                    "vulkan" => GraphicsType::Vulkan,
+                    "opengl" => GraphicsType::OpenGL,
+                    "null" => GraphicsType::Null,
+                    _ => {
+                        return Err(CliError::InvalidValue {
+                            option: "--backend",
+                            value,
+                            expected: "vulkan, opengl or null",
+                        });
+                    }
+                };
+            }
+            "--width" => {
+                let value: String = value("--width", inline_value, &mut args)?;
+                config.width = size("--width", value)?;
+            }
+            "--height" => {
+                let value: String = value("--height", inline_value, &mut args)?;
+                config.height = size("--height", value)?;
+            }
+            "--vsync" => {
+                switch("--vsync", inline_value)?;
+                config.settings.vsync = true;
+            }
+            "--validation" => {
+                switch("--validation", inline_value)?;
+                config.settings.validation = true;
+            }
+            "--gpu" => {
+                let value: String = value("--gpu", inline_value, &mut args)?;
+                config.settings.gpu = Some(gpu(value)?);
+            }
+            "--frames" => {
+                let value: String = value("--frames", inline_value, &mut args)?;
+                config.settings.frame_limit =
+                    Some(value.parse().map_err(|_| CliError::InvalidValue {
+                        option: "--frames",
+                        value,
+                        expected: "a number of frames",
+                    })?);
+            }
+            "--headless" => {
+                switch("--headless", inline_value)?;
+                config.settings.headless = true;
+            }
+            _ => return Err(CliError::UnknownOption(arg)),
+        }
+    }
+
+    Ok(Command::Run(config))
+}
+
+fn value(
+    option: &'static str,
+    inline_value: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, CliError> {
+    inline_value
+        .or_else(|| args.next())
+        .ok_or(CliError::MissingValue(option))
+}
+
+fn switch(option: &'static str, inline_value: Option<String>) -> Result<(), CliError> {
+    match inline_value {
+        Some(_) => Err(CliError::UnexpectedValue(option)),
+        None => Ok(()),
+    }
+}
+
+fn size(option: &'static str, value: String) -> Result<usize, CliError> {
+    match value.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(CliError::InvalidValue {
+            option,
+            value,
+            expected: "a positive number of pixels",
+        }),
+    }
+}
+
+fn gpu(value: String) -> Result<GpuSelection, CliError> {
+    GpuSelection::parse(&value).ok_or(CliError::InvalidValue {
+        option: "--gpu",
+        value,
+        expected: "a GPU index, name or vendor:device ID",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Command, CliError> {
+        parse(args.iter().map(|arg| arg.to_string()), Config::default())
+    }
+
+    fn config(args: &[&str]) -> Config {
+        match run(args) {
+            Ok(Command::Run(config)) => config,
+            other => panic!("expected a config, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_arguments_keep_the_defaults() {
+        assert_eq!(config(&[]), Config::default());
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let config: Config = config(&[
+            "--backend",
+            "opengl",
+            "--width",
+            "1280",
+            "--height=720",
+            "--vsync",
+            "--validation",
+            "--gpu",
+            "1",
+            "--frames",
+            "10",
+            "--headless",
+        ]);
+
+        assert_eq!(config.backend, GraphicsType::OpenGL);
+        assert_eq!((config.width, config.height), (1280, 720));
+        assert!(config.settings.vsync);
+        assert!(config.settings.validation);
+        assert_eq!(config.settings.gpu, Some(GpuSelection::Index(1)));
+        assert_eq!(config.settings.frame_limit, Some(10));
+        assert!(config.settings.headless);
+    }
+
+    #[test]
+    fn gpu_names_are_kept_as_given() {
+        assert_eq!(
+            config(&["--gpu=GeForce RTX"]).settings.gpu,
+            Some(GpuSelection::Name(String::from("GeForce RTX")))
+        );
+        assert_eq!(
+            config(&["--gpu", "0x10de:2504"]).settings.gpu,
+            Some(GpuSelection::Id {
+                vendor_id: 0x10de,
+                device_id: 0x2504
+            })
+        );
+    }
+
+    #[test]
+    fn help_wins_over_other_options() {
+        assert_eq!(run(&["--width", "1280", "--help"]), Ok(Command::Help));
+        assert_eq!(run(&["-h"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn reports_bad_arguments() {
+        assert_eq!(
+            run(&["--fullscreen"]),
+            Err(CliError::UnknownOption(String::from("--fullscreen")))
+        );
+        assert_eq!(run(&["--width"]), Err(CliError::MissingValue("--width")));
+        assert_eq!(
+            run(&["--vsync=false"]),
+            Err(CliError::UnexpectedValue("--vsync"))
+        );
+        assert_eq!(
+            run(&["--backend", "metal"]).unwrap_err().to_string(),
+            "invalid value \"metal\" for --backend: expected vulkan, opengl or null"
+        );
+        assert_eq!(
+            run(&["--height", "0"]).unwrap_err().to_string(),
+            "invalid value \"0\" for --height: expected a positive number of pixels"
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::utils::toml::{self, TomlEntry, TomlError, TomlValue};
+
+use super::graphics::{GpuSelection, GraphicsSettings, GraphicsType};
+
+// Looked up in the working directory first, then as config.toml in the user's config directory.
+pub const FILE_NAME: &str = "oito-caneco.toml";
+const CONFIG_DIR_NAME: &str = "oito-caneco";
+// Every key can also be set as OITO_CANECO_<TABLE>_<KEY>, e.g. OITO_CANECO_WINDOW_WIDTH=1280.
+const ENV_PREFIX: &str = "OITO_CANECO_";
+
+const KEYS: &[&str] = &[
+    "window.width",
+    "window.height",
+    "renderer.backend",
+    "renderer.model",
+    "renderer.vsync",
+    "renderer.msaa_samples",
+    "renderer.sample_shading",
+    "renderer.ktx2_textures",
+    "renderer.gpu",
+    "renderer.frames",
+    "renderer.headless",
+    "renderer.device_extensions",
+    "debug.validation",
+    "debug.validation_layers",
+    "debug.shader_hot_reload",
+    "debug.shader_override_dir",
+];
+
+// Everything the app is started with. Defaults are overridden by the config file, then by the
+// environment, then by the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub backend: GraphicsType,
+    pub width: usize,
+    pub height: usize,
+    pub settings: GraphicsSettings,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Parse {
+        path: String,
+        error: TomlError,
+    },
+    // `origin` says where the value came from: a file and line, or an environment variable.
+    Invalid {
+        origin: String,
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            ConfigError::Parse { path, error } => write!(f, "{}: {}", path, error),
+            ConfigError::Invalid {
+                origin,
+                key,
+                message,
+            } => write!(f, "{}: invalid {}: {}", origin, key, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            backend: GraphicsType::Vulkan,
+            width: 800,
+            height: 600,
+            settings: GraphicsSettings {
+                msaa_samples: 4,
+                ..GraphicsSettings::default()
+            },
+        }
+    }
+}
+
+impl Config {
+    // The defaults with the config file, if there is one, and the environment applied.
+    pub fn load() -> Result<Config, ConfigError> {
+        let mut config: Config = Config::default();
+        if let Some(path) = find_file() {
+            let path: String = path.display().to_string();
+            let source: String = fs::read_to_string(&path).map_err(|source| ConfigError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            config.apply_file(&path, &source)?;
+        }
+        config.apply_env(|name| std::env::var(name).ok())?;
+
+        Ok(config)
+    }
+
+    pub fn apply_file(&mut self, path: &str, source: &str) -> Result<(), ConfigError> {
+        let entries: Vec<TomlEntry> = toml::parse(source).map_err(|error| ConfigError::Parse {
+            path: path.to_string(),
+            error,
+        })?;
+
+        for entry in entries {
+            let key: String = entry.path();
+            self.apply(&key, &entry.value)
+                .map_err(|message| ConfigError::Invalid {
+                    origin: format!("{} line {}", path, entry.line),
+                    key,
+                    message,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    // Environment values are read as TOML values; anything that does not parse is a string, and
+    // lists can be written comma separated: OITO_CANECO_DEBUG_VALIDATION_LAYERS=a,b.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        for key in KEYS {
+            let name: String = env_var_name(key);
+            let Some(text) = var(&name) else {
+                continue;
+            };
+
+            let value: TomlValue = TomlValue::parse(&text).unwrap_or(TomlValue::String(text));
+            self.apply(key, &value)
+                .map_err(|message| ConfigError::Invalid {
+                    origin: format!("environment variable {}", name),
+                    key: key.to_string(),
+                    message,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn apply(&mut self, key: &str, value: &TomlValue) -> Result<(), String> {
+        match key {
+            "window.width" => self.width = positive(value)?,
+            "window.height" => self.height = positive(value)?,
+            "renderer.backend" => {
+                self.backend = match string(value)?.as_str() {
+                    "vulkan" => GraphicsType::Vulkan,
+                    "opengl" => GraphicsType::OpenGL,
+                    "null" => GraphicsType::Null,
+                    other => {
+                        return Err(format!(
+                            "expected \"vulkan\", \"opengl\" or \"null\", found \"{}\"",
+                            other
+                        ));
+                    }
+                }
+            }
+            "renderer.model" => self.settings.model_path = string(value)?,
+            "renderer.vsync" => self.settings.vsync = boolean(value)?,
+            "renderer.msaa_samples" => {
+                self.settings.msaa_samples = match integer(value)? {
+                    samples @ (1 | 2 | 4 | 8) => samples as u32,
+                    samples => return Err(format!("expected 1, 2, 4 or 8, found {}", samples)),
+                }
+            }
+            "renderer.sample_shading" => self.settings.sample_shading = boolean(value)?,
+            "renderer.ktx2_textures" => self.settings.ktx2_textures = boolean(value)?,
+            "renderer.gpu" => {
+                let gpu: Option<GpuSelection> = match value {
+                    TomlValue::Integer(index) if *index >= 0 => {
+                        Some(GpuSelection::Index(*index as usize))
+                    }
+                    TomlValue::String(text) => GpuSelection::parse(text),
+                    _ => None,
+                };
+                self.settings.gpu = Some(
+                    gpu.ok_or_else(|| expected("a GPU index, name or vendor:device ID", value))?,
+                );
+            }
+            "renderer.frames" => {
+                self.settings.frame_limit = Some(
+                    u32::try_from(integer(value)?)
+                        .map_err(|_| String::from("expected a number of frames"))?,
+                )
+            }
+            "renderer.headless" => self.settings.headless = boolean(value)?,
+            "renderer.device_extensions" => self.settings.device_extensions = names(value)?,
+            "debug.validation" => self.settings.validation = boolean(value)?,
+            "debug.validation_layers" => self.settings.validation_layers = names(value)?,
+            "debug.shader_hot_reload" => self.settings.shader_hot_reload = boolean(value)?,
+            "debug.shader_override_dir" => self.settings.shader_override_dir = Some(string(value)?),
+            _ => return Err(format!("unknown key, expected one of {}", KEYS.join(", "))),
+        }
+
+        Ok(())
+    }
+}
+
+fn find_file() -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = vec![PathBuf::from(FILE_NAME)];
+    // XDG_CONFIG_HOME defaults to ~/.config when unset or empty.
+    let config_home: Option<PathBuf> = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    if let Some(config_home) = config_home {
+        candidates.push(config_home.join(CONFIG_DIR_NAME).join("config.toml"));
+    }
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+fn expected(what: &str, value: &TomlValue) -> String {
+    format!("expected {}, found {}", what, value.type_name())
+}
+
+fn boolean(value: &TomlValue) -> Result<bool, String> {
+    match value {
+        TomlValue::Bool(value) => Ok(*value),
+        _ => Err(expected("true or false", value)),
+    }
+}
+
+fn integer(value: &TomlValue) -> Result<i64, String> {
+    match value {
+        TomlValue::Integer(value) => Ok(*value),
+        _ => Err(expected("an integer", value)),
+    }
+}
+
+fn positive(value: &TomlValue) -> Result<usize, String> {
+    match value {
+        TomlValue::Integer(value) if *value > 0 => Ok(*value as usize),
+        _ => Err(expected("a positive integer", value)),
+    }
+}
+
+fn string(value: &TomlValue) -> Result<String, String> {
+    match value {
+        TomlValue::String(value) => Ok(value.clone()),
+        _ => Err(expected("a string", value)),
+    }
+}
+
+// Layer and extension names. The environment can only give a comma separated string.
+fn names(value: &TomlValue) -> Result<Vec<String>, String> {
+    let names: Vec<String> = match value {
+        TomlValue::Array(values) => values.iter().map(string).collect::<Result<_, _>>()?,
+        TomlValue::String(value) if value.trim().is_empty() => Vec::new(),
+        TomlValue::String(value) => value
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect(),
+        _ => return Err(expected("an array of names", value)),
+    };
+
+    // The names are handed to Vulkan as C strings.
+    if names
+        .iter()
+        .any(|name| name.is_empty() || name.contains('\0'))
+    {
+        return Err(String::from(
+            "names can not be empty or contain NUL characters",
+        ));
+    }
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_values_override_the_defaults() {
+        let mut config: Config = Config::default();
+        config
+            .apply_file(
+                FILE_NAME,
+                "[window]\n\
+                 width = 1280\n\
+                 [renderer]\n\
+                 backend = \"opengl\"\n\
+                 model = \"models/scene.glb\"\n\
+                 gpu = \"nvidia\"\n\
+                 device_extensions = [\"VK_KHR_ray_query\"]\n\
+                 [debug]\n\
+                 validation_layers = []\n\
+                 shader_override_dir = \"spirv\"\n",
+            )
+            .unwrap();
+
+        assert_eq!((config.width, config.height), (1280, 600));
+        assert_eq!(config.backend, GraphicsType::OpenGL);
+        assert_eq!(config.settings.model_path, "models/scene.glb");
+        assert_eq!(
+            config.settings.gpu,
+            Some(GpuSelection::Name(String::from("nvidia")))
+        );
+        assert_eq!(config.settings.device_extensions, ["VK_KHR_ray_query"]);
+        assert!(config.settings.validation_layers.is_empty());
+        assert_eq!(
+            config.settings.shader_override_dir.as_deref(),
+            Some("spirv")
+        );
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let mut config: Config = Config::default();
+        config
+            .apply_file(FILE_NAME, "[renderer]\nvsync = false\nmsaa_samples = 2\n")
+            .unwrap();
+        config
+            .apply_env(|name| match name {
+                "OITO_CANECO_RENDERER_VSYNC" => Some(String::from("true")),
+                "OITO_CANECO_RENDERER_GPU" => Some(String::from("1")),
+                "OITO_CANECO_DEBUG_VALIDATION_LAYERS" => Some(String::from("a, b")),
+                _ => None,
+            })
+            .unwrap();
+
+        assert!(config.settings.vsync);
+        assert_eq!(config.settings.msaa_samples, 2);
+        assert_eq!(config.settings.gpu, Some(GpuSelection::Index(1)));
+        assert_eq!(config.settings.validation_layers, ["a", "b"]);
+    }
+
+    #[test]
+    fn errors_name_the_key() {
+        let mut config: Config = Config::default();
+
+        let error: ConfigError = config
+            .apply_file(FILE_NAME, "[window]\nheight = \"tall\"\n")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "oito-caneco.toml line 2: invalid window.height: expected a positive integer, \
+             found a string"
+        );
+
+        let error: ConfigError = config
+            .apply_file(FILE_NAME, "[renderer]\nmsaa = 4\n")
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("oito-caneco.toml line 2: invalid renderer.msaa: unknown key"));
+
+        let error: ConfigError = config
+            .apply_env(|name| {
+                (name == "OITO_CANECO_RENDERER_MSAA_SAMPLES").then(|| String::from("3"))
+            })
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "environment variable OITO_CANECO_RENDERER_MSAA_SAMPLES: \
+             invalid renderer.msaa_samples: expected 1, 2, 4 or 8, found 3"
+        );
+    }
+}
@@ -1,17 +1,153 @@
+use std::fmt;
+
+use crate::gl::GlError;
 use crate::glfw;
+use crate::utils::debug_mode;
+use crate::vulkan::VulkanError;
+
+use super::mesh::MODEL_PATH;
 
 pub trait GraphicApi {
     fn init_window(&self) -> Window;
-    fn init_api(&self);
+    fn init_api(&self) -> Result<(), GraphicError>;
     fn cleanup(&self);
     fn should_close(&self) -> bool;
     fn pool_events(&self);
     fn wait_events(&self);
-    fn draw_frame(&self);
+    fn draw_frame(&self) -> Result<(), GraphicError>;
     fn wait_device_idle(&self);
 }
 
+// Rendering options the app asks for; backends fall back when the hardware can not honour them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphicsSettings {
+    // The model drawn: OBJ, glTF (.gltf) or binary glTF (.glb), picked by the file extension.
+    pub model_path: String,
+    // MSAA sample count: 1, 2, 4 or 8. Clamped to the highest count the GPU supports.
+    pub msaa_samples: u32,
+    // Shade multisampled pixels per sample instead of once per pixel.
+    pub sample_shading: bool,
+    // Load textures with pre-built mip levels from KTX2 files instead of generating them.
+    pub ktx2_textures: bool,
+    // Render into an offscreen image instead of a window and write every frame to a PNG file.
+    pub headless: bool,
+    // Stop after this many frames. None runs until the window closes, or one frame headless.
+    pub frame_limit: Option<u32>,
+    // Wait for the display to refresh before presenting, capping the frame rate.
+    pub vsync: bool,
+    // Enable the Vulkan validation layers. On by default in debug builds.
+    pub validation: bool,
+    // Instance layers enabled when validation is on.
+    pub validation_layers: Vec<String>,
+    // Device extensions required on top of the ones the renderer needs itself. GPUs without them
+    // are not used.
+    pub device_extensions: Vec<String>,
+    // Use this GPU instead of the best scoring one.
+    pub gpu: Option<GpuSelection>,
+    // Recompile the shaders when a file in src/shaders changes and swap in the new pipeline.
+    pub shader_hot_reload: bool,
+    // Load <name>.spv from this directory instead of the shaders built into the binary.
+    pub shader_override_dir: Option<String>,
+}
+
+// A GPU by its position in the driver's device list, by part of its name or by its PCI IDs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GpuSelection {
+    Index(usize),
+    Name(String),
+    Id { vendor_id: u32, device_id: u32 },
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            model_path: String::from(MODEL_PATH),
+            msaa_samples: 1,
+            sample_shading: false,
+            ktx2_textures: false,
+            headless: false,
+            frame_limit: None,
+            vsync: false,
+            validation: debug_mode(),
+            validation_layers: vec![String::from("VK_LAYER_KHRONOS_validation")],
+            device_extensions: Vec::new(),
+            gpu: None,
+            shader_hot_reload: debug_mode(),
+            shader_override_dir: None,
+        }
+    }
+}
+
 pub enum Window {
     Vulkan(*mut glfw::GLFWwindow),
     OpenGL(*mut glfw::GLFWwindow),
+    // Rendering without a window, nothing is shown on screen.
+    Headless,
+}
+
+#[derive(Debug)]
+pub enum GraphicError {
+    Vulkan(VulkanError),
+    OpenGL(GlError),
+}
+
+impl From<VulkanError> for GraphicError {
+    fn from(error: VulkanError) -> Self {
+        GraphicError::Vulkan(error)
+    }
+}
+
+impl From<GlError> for GraphicError {
+    fn from(error: GlError) -> Self {
+        GraphicError::OpenGL(error)
+    }
+}
+
+impl fmt::Display for GraphicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphicError::Vulkan(error) => write!(f, "Vulkan: {}", error),
+            GraphicError::OpenGL(error) => write!(f, "OpenGL: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for GraphicError {}
+
+impl GpuSelection {
+    // "1" is an index and "10de:2504" a hexadecimal vendor:device pair; anything else is a name.
+    pub fn parse(text: &str) -> Option<GpuSelection> {
+        let text: &str = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        if let Ok(index) = text.parse::<usize>() {
+            return Some(GpuSelection::Index(index));
+        }
+
+        let hex = |id: &str| u32::from_str_radix(id.trim_start_matches("0x"), 16).ok();
+        if let Some((vendor, device)) = text.split_once(':') {
+            if let (Some(vendor_id), Some(device_id)) = (hex(vendor), hex(device)) {
+                return Some(GpuSelection::Id {
+                    vendor_id,
+                    device_id,
+                });
+            }
+        }
+
+        Some(GpuSelection::Name(text.to_string()))
+    }
+}
+
+impl fmt::Display for GpuSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuSelection::Index(index) => write!(f, "{}", index),
+            GpuSelection::Name(name) => write!(f, "{}", name),
+            GpuSelection::Id {
+                vendor_id,
+                device_id,
+            } => write!(f, "{:04x}:{:04x}", vendor_id, device_id),
+        }
+    }
 }
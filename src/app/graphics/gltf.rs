@@ -0,0 +1,1350 @@
+use std::fs;
+use std::path::Path;
+
+use crate::utils::json::JsonValue;
+
+use super::load::LoadError;
+use super::math::{self, Mat4, Vec3};
+use super::mesh::{self, NO_MATERIAL};
+use super::vertex::MeshVertex;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+const COMPONENT_BYTE: usize = 5120;
+const COMPONENT_UNSIGNED_BYTE: usize = 5121;
+const COMPONENT_SHORT: usize = 5122;
+const COMPONENT_UNSIGNED_SHORT: usize = 5123;
+const COMPONENT_UNSIGNED_INT: usize = 5125;
+const COMPONENT_FLOAT: usize = 5126;
+
+const MODE_TRIANGLES: usize = 4;
+
+// Accessors without a buffer view take no space in the file, so their zeros are capped before
+// they are allocated.
+const MAX_ZEROED_ACCESSOR_VALUES: usize = 1 << 24;
+
+// Node hierarchy and resources of a glTF 2.0 asset, with every accessor decoded. All indices
+// between the lists are checked while loading, so they can be used without bounds checks.
+#[derive(Debug)]
+pub struct Scene {
+    pub nodes: Vec<Node>,
+    // Nodes of the default scene; the rest of it is reached through their children.
+    pub root_nodes: Vec<usize>,
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub textures: Vec<Texture>,
+    pub images: Vec<Image>,
+    // Cameras and skins are imported for callers that animate or frame the scene; the renderer
+    // only draws the meshes so far.
+    #[allow(dead_code)]
+    pub cameras: Vec<Camera>,
+    #[allow(dead_code)]
+    pub skins: Vec<Skin>,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    #[allow(dead_code)]
+    pub name: Option<String>,
+    pub children: Vec<usize>,
+    // Relative to the parent node.
+    pub transform: Mat4,
+    pub mesh: Option<usize>,
+    #[allow(dead_code)]
+    pub camera: Option<usize>,
+    #[allow(dead_code)]
+    pub skin: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Mesh {
+    pub primitives: Vec<Primitive>,
+}
+
+// A triangle list. Every attribute list is either empty or as long as `positions`.
+#[derive(Debug)]
+pub struct Primitive {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub tex_coords: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 4]>,
+    #[allow(dead_code)]
+    pub joints: Vec<[u16; 4]>,
+    #[allow(dead_code)]
+    pub weights: Vec<[f32; 4]>,
+    // Sequential when the file has no index accessor.
+    pub indices: Vec<u32>,
+    pub material: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureRef {
+    pub texture: usize,
+    // Which TEXCOORD_n set samples the texture; only TEXCOORD_0 is imported.
+    pub tex_coord: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    // Fragments with a lower alpha than the cutoff are discarded.
+    Mask(f32),
+    Blend,
+}
+
+// PBR metallic-roughness material. The renderer reads the base color so far, the remaining
+// inputs are kept for the lighting model that will use them.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Material {
+    pub name: Option<String>,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    pub occlusion_texture: Option<TextureRef>,
+    pub emissive_texture: Option<TextureRef>,
+    pub emissive_factor: Vec3,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+// Samplers are not imported; textures use the renderer's sampler.
+#[derive(Debug)]
+pub struct Texture {
+    // Missing when the image comes from an extension.
+    pub image: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Image {
+    // Path of an image file, resolved against the directory of the glTF file by `load`.
+    Uri(String),
+    // Stored in a buffer view or a data URI.
+    Embedded { mime_type: String, bytes: Vec<u8> },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Camera {
+    Perspective {
+        y_fov: f32,
+        aspect_ratio: Option<f32>,
+        z_near: f32,
+        // None for an infinite projection.
+        z_far: Option<f32>,
+    },
+    Orthographic {
+        x_mag: f32,
+        y_mag: f32,
+        z_near: f32,
+        z_far: f32,
+    },
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Skin {
+    pub joints: Vec<usize>,
+    // One per joint; identity matrices when the file has none.
+    pub inverse_bind_matrices: Vec<Mat4>,
+    pub skeleton: Option<usize>,
+}
+
+// Reads a .gltf or .glb file. External buffers and images are relative to its directory.
+pub fn load(path: &str) -> Result<Scene, LoadError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(|source| LoadError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    let directory: &Path = Path::new(path).parent().unwrap_or(Path::new(""));
+    let read_file = |uri: &str| fs::read(directory.join(uri)).map_err(|error| error.to_string());
+
+    let scene: Result<Scene, String> = if bytes.starts_with(GLB_MAGIC) {
+        parse_glb(&bytes, read_file)
+    } else {
+        match std::str::from_utf8(&bytes) {
+            Ok(json) => parse_gltf(json, None, read_file),
+            Err(_) => Err(String::from("file is neither glTF JSON nor GLB")),
+        }
+    };
+    let mut scene: Scene = scene.map_err(|reason| LoadError::invalid(path, reason))?;
+
+    for image in scene.images.iter_mut() {
+        if let Image::Uri(uri) = image {
+            *uri = directory.join(&uri).to_string_lossy().into_owned();
+        }
+    }
+
+    Ok(scene)
+}
+
+// Splits a binary glTF file into its JSON and BIN chunks.
+pub fn parse_glb(
+    bytes: &[u8],
+    read_file: impl FnMut(&str) -> Result<Vec<u8>, String>,
+) -> Result<Scene, String> {
+    let version: u32 = read_u32(bytes, 4)?;
+    if version != 2 {
+        return Err(format!("GLB version {} is not supported", version));
+    }
+    let length: usize = read_u32(bytes, 8)? as usize;
+    if length > bytes.len() {
+        return Err(format!(
+            "file is truncated, header says {} bytes but found {}",
+            length,
+            bytes.len()
+        ));
+    }
+
+    let mut json: Option<&[u8]> = None;
+    let mut bin: Option<&[u8]> = None;
+    let mut offset: usize = 12;
+    while offset + 8 <= length {
+        let chunk_length: usize = read_u32(bytes, offset)? as usize;
+        let chunk_type: u32 = read_u32(bytes, offset + 4)?;
+        let data: &[u8] = bytes
+            .get(offset + 8..(offset + 8).saturating_add(chunk_length))
+            .ok_or_else(|| format!("chunk at byte {} is truncated", offset))?;
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(data),
+            GLB_CHUNK_BIN if bin.is_none() => bin = Some(data),
+            // Chunks of unknown types are skipped, as the specification asks.
+            _ => {}
+        }
+        offset += 8 + chunk_length;
+    }
+
+    let json: &[u8] = json.ok_or_else(|| String::from("GLB file has no JSON chunk"))?;
+    let json: &str =
+        std::str::from_utf8(json).map_err(|_| String::from("GLB JSON chunk is not UTF-8"))?;
+
+    parse_gltf(json, bin, read_file)
+}
+
+// `bin` is the GLB binary chunk, used by the first buffer when it has no URI. `read_file`
+// returns the contents of a file referenced by a buffer URI.
+pub fn parse_gltf(
+    json: &str,
+    bin: Option<&[u8]>,
+    mut read_file: impl FnMut(&str) -> Result<Vec<u8>, String>,
+) -> Result<Scene, String> {
+    let root: JsonValue = JsonValue::parse(json).map_err(|error| error.to_string())?;
+
+    let version: &str = required(
+        string(
+            required(root.get("asset"), "asset", "glTF")?,
+            "version",
+            "asset",
+        )?,
+        "version",
+        "asset",
+    )?;
+    if !version.starts_with("2.") {
+        return Err(format!("glTF version {} is not supported", version));
+    }
+    if let Some(extension) = array(&root, "extensionsRequired", "glTF")?.first() {
+        return Err(format!(
+            "required extension {} is not supported",
+            extension.as_str().unwrap_or("?")
+        ));
+    }
+
+    let mut data: Vec<Vec<u8>> = Vec::new();
+    for (index, buffer) in array(&root, "buffers", "glTF")?.iter().enumerate() {
+        let context: String = format!("buffers[{}]", index);
+        let byte_length: usize = required(
+            usize_field(buffer, "byteLength", &context)?,
+            "byteLength",
+            &context,
+        )?;
+        let bytes: Vec<u8> = match string(buffer, "uri", &context)? {
+            Some(uri) if uri.starts_with("data:") => {
+                decode_data_uri(uri)
+                    .map_err(|reason| format!("{}: {}", context, reason))?
+                    .1
+            }
+            Some(uri) => read_file(&decode_uri(uri))
+                .map_err(|reason| format!("{}: can not read {}: {}", context, uri, reason))?,
+            None => match (index, bin) {
+                (0, Some(bin)) => bin.to_vec(),
+                _ => return Err(format!("{}: has no uri and no GLB binary chunk", context)),
+            },
+        };
+        if bytes.len() < byte_length {
+            return Err(format!(
+                "{}: holds {} bytes, byteLength is {}",
+                context,
+                bytes.len(),
+                byte_length
+            ));
+        }
+        data.push(bytes);
+    }
+
+    let buffers: Buffers = Buffers {
+        accessors: array(&root, "accessors", "glTF")?,
+        buffer_views: array(&root, "bufferViews", "glTF")?,
+        data,
+    };
+    let accessor_count: usize = buffers.accessors.len();
+
+    let mut images: Vec<Image> = Vec::new();
+    for (index, image) in array(&root, "images", "glTF")?.iter().enumerate() {
+        let context: String = format!("images[{}]", index);
+        let buffer_view: Option<usize> =
+            reference(image, "bufferView", buffers.buffer_views.len(), &context)?;
+        images.push(match (string(image, "uri", &context)?, buffer_view) {
+            (Some(uri), _) if uri.starts_with("data:") => {
+                let (mime_type, bytes) =
+                    decode_data_uri(uri).map_err(|reason| format!("{}: {}", context, reason))?;
+                Image::Embedded { mime_type, bytes }
+            }
+            (Some(uri), _) => Image::Uri(decode_uri(uri)),
+            (None, Some(view)) => Image::Embedded {
+                mime_type: required(string(image, "mimeType", &context)?, "mimeType", &context)?
+                    .to_string(),
+                bytes: buffers.buffer_view(view)?.0.to_vec(),
+            },
+            (None, None) => return Err(format!("{}: needs a uri or a bufferView", context)),
+        });
+    }
+
+    let mut textures: Vec<Texture> = Vec::new();
+    for (index, texture) in array(&root, "textures", "glTF")?.iter().enumerate() {
+        let context: String = format!("textures[{}]", index);
+        textures.push(Texture {
+            image: reference(texture, "source", images.len(), &context)?,
+        });
+    }
+
+    let mut materials: Vec<Material> = Vec::new();
+    let no_pbr: JsonValue = JsonValue::Object(Vec::new());
+    for (index, material) in array(&root, "materials", "glTF")?.iter().enumerate() {
+        materials.push(parse_material(
+            material,
+            &no_pbr,
+            textures.len(),
+            &format!("materials[{}]", index),
+        )?);
+    }
+
+    let mut cameras: Vec<Camera> = Vec::new();
+    for (index, camera) in array(&root, "cameras", "glTF")?.iter().enumerate() {
+        cameras.push(parse_camera(camera, &format!("cameras[{}]", index))?);
+    }
+
+    let node_count: usize = array(&root, "nodes", "glTF")?.len();
+    let mut skins: Vec<Skin> = Vec::new();
+    for (index, skin) in array(&root, "skins", "glTF")?.iter().enumerate() {
+        let context: String = format!("skins[{}]", index);
+        let joints: Vec<usize> = references(skin, "joints", node_count, &context)?;
+        let inverse_bind_matrices: Vec<Mat4> =
+            match reference(skin, "inverseBindMatrices", accessor_count, &context)? {
+                Some(accessor) => buffers
+                    .read_vectors::<16>(accessor, &context)?
+                    .iter()
+                    .map(matrix_from_columns)
+                    .collect(),
+                None => vec![math::identity(); joints.len()],
+            };
+        if inverse_bind_matrices.len() < joints.len() {
+            return Err(format!(
+                "{}: {} inverse bind matrices for {} joints",
+                context,
+                inverse_bind_matrices.len(),
+                joints.len()
+            ));
+        }
+        skins.push(Skin {
+            joints,
+            inverse_bind_matrices,
+            skeleton: reference(skin, "skeleton", node_count, &context)?,
+        });
+    }
+
+    let mut meshes: Vec<Mesh> = Vec::new();
+    for (index, mesh) in array(&root, "meshes", "glTF")?.iter().enumerate() {
+        let context: String = format!("meshes[{}]", index);
+        let mut primitives: Vec<Primitive> = Vec::new();
+        for (primitive_index, primitive) in array(mesh, "primitives", &context)?.iter().enumerate()
+        {
+            let context: String = format!("{}.primitives[{}]", context, primitive_index);
+            primitives.push(parse_primitive(
+                primitive,
+                &buffers,
+                materials.len(),
+                &context,
+            )?);
+        }
+        meshes.push(Mesh { primitives });
+    }
+
+    let mut nodes: Vec<Node> = Vec::new();
+    for (index, node) in array(&root, "nodes", "glTF")?.iter().enumerate() {
+        let context: String = format!("nodes[{}]", index);
+        let transform: Mat4 = match floats::<16>(node, "matrix", &context)? {
+            Some(columns) => matrix_from_columns(&columns),
+            None => {
+                let translation: Vec3 =
+                    floats::<3>(node, "translation", &context)?.unwrap_or([0.0; 3]);
+                let rotation: [f32; 4] =
+                    floats::<4>(node, "rotation", &context)?.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+                let scale: Vec3 = floats::<3>(node, "scale", &context)?.unwrap_or([1.0; 3]);
+                math::multiply(
+                    &math::multiply(
+                        &math::translation(translation),
+                        &math::quaternion_rotation(rotation),
+                    ),
+                    &math::scaling(scale),
+                )
+            }
+        };
+        nodes.push(Node {
+            name: string(node, "name", &context)?.map(String::from),
+            children: references(node, "children", node_count, &context)?,
+            transform,
+            mesh: reference(node, "mesh", meshes.len(), &context)?,
+            camera: reference(node, "camera", cameras.len(), &context)?,
+            skin: reference(node, "skin", skins.len(), &context)?,
+        });
+    }
+    check_hierarchy(&nodes)?;
+
+    // Without a default scene the first one is shown, and without any scene every parentless
+    // node is.
+    let scenes: &[JsonValue] = array(&root, "scenes", "glTF")?;
+    let root_nodes: Vec<usize> = match reference(&root, "scene", scenes.len(), "glTF")? {
+        Some(scene) => references(
+            &scenes[scene],
+            "nodes",
+            node_count,
+            &format!("scenes[{}]", scene),
+        )?,
+        None if !scenes.is_empty() => references(&scenes[0], "nodes", node_count, "scenes[0]")?,
+        None => (0..node_count)
+            .filter(|node| !nodes.iter().any(|parent| parent.children.contains(node)))
+            .collect(),
+    };
+
+    Ok(Scene {
+        nodes,
+        root_nodes,
+        meshes,
+        materials,
+        textures,
+        images,
+        cameras,
+        skins,
+    })
+}
+
+impl Scene {
+    // Every node of the scene with its transform relative to the scene root, parents first.
+    pub fn world_transforms(&self) -> Vec<(usize, Mat4)> {
+        let mut transforms: Vec<(usize, Mat4)> = Vec::with_capacity(self.nodes.len());
+        let mut stack: Vec<(usize, Mat4)> = self
+            .root_nodes
+            .iter()
+            .rev()
+            .map(|&node| (node, self.nodes[node].transform))
+            .collect();
+
+        while let Some((node, world)) = stack.pop() {
+            for &child in self.nodes[node].children.iter().rev() {
+                stack.push((child, math::multiply(&world, &self.nodes[child].transform)));
+            }
+            transforms.push((node, world));
+        }
+
+        transforms
+    }
+
+    // Flattens the scene into one mesh in world space, one material per glTF material. Skinned
+    // meshes are drawn in their bind pose and embedded images are not referenced.
+    pub fn to_mesh(&self) -> mesh::Mesh {
+        let mut result: mesh::Mesh = mesh::Mesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            face_materials: Vec::new(),
+            materials: self
+                .materials
+                .iter()
+                .enumerate()
+                .map(|(index, material)| mesh::Material {
+                    name: material
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("material{}", index)),
+                    diffuse: [
+                        material.base_color_factor[0],
+                        material.base_color_factor[1],
+                        material.base_color_factor[2],
+                    ],
+                    diffuse_texture: material
+                        .base_color_texture
+                        .and_then(|texture| self.textures[texture.texture].image)
+                        .and_then(|image| match &self.images[image] {
+                            Image::Uri(path) => Some(path.clone()),
+                            Image::Embedded { .. } => None,
+                        }),
+                })
+                .collect(),
+        };
+
+        for (node, world) in self.world_transforms() {
+            let Some(mesh) = self.nodes[node].mesh else {
+                continue;
+            };
+            let normal_matrix: Mat4 = math::inverse(&world)
+                .map(|inverse| math::transpose(&inverse))
+                .unwrap_or(world);
+            // A mirroring transform turns counter-clockwise triangles clockwise.
+            let mirrored: bool = math::dot(
+                math::cross(
+                    [world[0][0], world[0][1], world[0][2]],
+                    [world[1][0], world[1][1], world[1][2]],
+                ),
+                [world[2][0], world[2][1], world[2][2]],
+            ) < 0.0;
+
+            for primitive in &self.meshes[mesh].primitives {
+                let (material, material_color): (u32, [f32; 4]) = match primitive.material {
+                    Some(material) => (material as u32, self.materials[material].base_color_factor),
+                    None => (NO_MATERIAL, [1.0; 4]),
+                };
+                let vertex = |index: usize, normal: Vec3| {
+                    let color: [f32; 4] = primitive.colors.get(index).copied().unwrap_or([1.0; 4]);
+                    MeshVertex {
+                        position: math::transform_point(&world, primitive.positions[index]),
+                        normal,
+                        color: [
+                            material_color[0] * color[0],
+                            material_color[1] * color[1],
+                            material_color[2] * color[2],
+                        ],
+                        tex_coord: primitive.tex_coords.get(index).copied().unwrap_or([0.0; 2]),
+                    }
+                };
+
+                let base: u32 = result.vertices.len() as u32;
+                if !primitive.normals.is_empty() {
+                    for (index, normal) in primitive.normals.iter().enumerate() {
+                        let normal: Vec3 =
+                            math::normalize(math::transform_vector(&normal_matrix, *normal));
+                        result.vertices.push(vertex(index, normal));
+                    }
+                }
+                for triangle in primitive.indices.chunks_exact(3) {
+                    let corners: [u32; 3] = if mirrored {
+                        [triangle[0], triangle[2], triangle[1]]
+                    } else {
+                        [triangle[0], triangle[1], triangle[2]]
+                    };
+                    if primitive.normals.is_empty() {
+                        // glTF asks for flat shading without normals, so corners are not shared.
+                        let positions: [Vec3; 3] = corners.map(|corner| {
+                            math::transform_point(&world, primitive.positions[corner as usize])
+                        });
+                        let face_normal: Vec3 = math::normalize(math::cross(
+                            math::subtract(positions[1], positions[0]),
+                            math::subtract(positions[2], positions[0]),
+                        ));
+                        for corner in corners {
+                            result.indices.push(result.vertices.len() as u32);
+                            result.vertices.push(vertex(corner as usize, face_normal));
+                        }
+                    } else {
+                        result.indices.extend(corners.map(|corner| base + corner));
+                    }
+                    result.face_materials.push(material);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+fn parse_material(
+    material: &JsonValue,
+    no_pbr: &JsonValue,
+    texture_count: usize,
+    context: &str,
+) -> Result<Material, String> {
+    let pbr: &JsonValue = material.get("pbrMetallicRoughness").unwrap_or(no_pbr);
+    let pbr_context: String = format!("{}.pbrMetallicRoughness", context);
+
+    let alpha_mode: AlphaMode = match string(material, "alphaMode", context)? {
+        None | Some("OPAQUE") => AlphaMode::Opaque,
+        Some("MASK") => AlphaMode::Mask(float(material, "alphaCutoff", context)?.unwrap_or(0.5)),
+        Some("BLEND") => AlphaMode::Blend,
+        Some(other) => return Err(format!("{}: unknown alphaMode {}", context, other)),
+    };
+
+    Ok(Material {
+        name: string(material, "name", context)?.map(String::from),
+        base_color_factor: floats::<4>(pbr, "baseColorFactor", &pbr_context)?.unwrap_or([1.0; 4]),
+        base_color_texture: texture_ref(pbr, "baseColorTexture", texture_count, &pbr_context)?,
+        metallic_factor: float(pbr, "metallicFactor", &pbr_context)?.unwrap_or(1.0),
+        roughness_factor: float(pbr, "roughnessFactor", &pbr_context)?.unwrap_or(1.0),
+        metallic_roughness_texture: texture_ref(
+            pbr,
+            "metallicRoughnessTexture",
+            texture_count,
+            &pbr_context,
+        )?,
+        normal_texture: texture_ref(material, "normalTexture", texture_count, context)?,
+        occlusion_texture: texture_ref(material, "occlusionTexture", texture_count, context)?,
+        emissive_texture: texture_ref(material, "emissiveTexture", texture_count, context)?,
+        emissive_factor: floats::<3>(material, "emissiveFactor", context)?.unwrap_or([0.0; 3]),
+        alpha_mode,
+        double_sided: match material.get("doubleSided") {
+            None => false,
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| format!("{}.doubleSided must be a boolean", context))?,
+        },
+    })
+}
+
+fn parse_camera(camera: &JsonValue, context: &str) -> Result<Camera, String> {
+    match string(camera, "type", context)? {
+        Some("perspective") => {
+            let context: String = format!("{}.perspective", context);
+            let projection: &JsonValue =
+                required(camera.get("perspective"), "perspective", &context)?;
+            Ok(Camera::Perspective {
+                y_fov: required(float(projection, "yfov", &context)?, "yfov", &context)?,
+                aspect_ratio: float(projection, "aspectRatio", &context)?,
+                z_near: required(float(projection, "znear", &context)?, "znear", &context)?,
+                z_far: float(projection, "zfar", &context)?,
+            })
+        }
+        Some("orthographic") => {
+            let context: String = format!("{}.orthographic", context);
+            let projection: &JsonValue =
+                required(camera.get("orthographic"), "orthographic", &context)?;
+            Ok(Camera::Orthographic {
+                x_mag: required(float(projection, "xmag", &context)?, "xmag", &context)?,
+                y_mag: required(float(projection, "ymag", &context)?, "ymag", &context)?,
+                z_near: required(float(projection, "znear", &context)?, "znear", &context)?,
+                z_far: required(float(projection, "zfar", &context)?, "zfar", &context)?,
+            })
+        }
+        _ => Err(format!(
+            "{}.type must be perspective or orthographic",
+            context
+        )),
+    }
+}
+
+fn parse_primitive(
+    primitive: &JsonValue,
+    buffers: &Buffers,
+    material_count: usize,
+    context: &str,
+) -> Result<Primitive, String> {
+    let mode: usize = usize_field(primitive, "mode", context)?.unwrap_or(MODE_TRIANGLES);
+    if mode != MODE_TRIANGLES {
+        return Err(format!(
+            "{}: only triangle lists are supported, mode is {}",
+            context, mode
+        ));
+    }
+
+    let attributes_context: String = format!("{}.attributes", context);
+    let attributes: &JsonValue = required(primitive.get("attributes"), "attributes", context)?;
+    let accessor_count: usize = buffers.accessors.len();
+    let attribute = |name: &str| reference(attributes, name, accessor_count, &attributes_context);
+
+    let position_accessor: usize =
+        required(attribute("POSITION")?, "POSITION", &attributes_context)?;
+    let positions: Vec<Vec3> = buffers.read_vectors::<3>(position_accessor, &attributes_context)?;
+    let vertex_count: usize = positions.len();
+
+    let normals: Vec<Vec3> = match attribute("NORMAL")? {
+        Some(accessor) => buffers.read_vectors::<3>(accessor, &attributes_context)?,
+        None => Vec::new(),
+    };
+    let tex_coords: Vec<[f32; 2]> = match attribute("TEXCOORD_0")? {
+        Some(accessor) => buffers.read_vectors::<2>(accessor, &attributes_context)?,
+        None => Vec::new(),
+    };
+    // Vertex colors may leave out alpha.
+    let colors: Vec<[f32; 4]> = match attribute("COLOR_0")? {
+        Some(accessor) => match buffers.read_accessor(accessor)? {
+            (3, values) => values
+                .chunks_exact(3)
+                .map(|rgb| [rgb[0] as f32, rgb[1] as f32, rgb[2] as f32, 1.0])
+                .collect(),
+            (4, values) => values
+                .chunks_exact(4)
+                .map(|rgba| {
+                    [
+                        rgba[0] as f32,
+                        rgba[1] as f32,
+                        rgba[2] as f32,
+                        rgba[3] as f32,
+                    ]
+                })
+                .collect(),
+            (components, _) => {
+                return Err(format!(
+                    "{}.COLOR_0: expected 3 or 4 components, found {}",
+                    attributes_context, components
+                ))
+            }
+        },
+        None => Vec::new(),
+    };
+    let joints: Vec<[u16; 4]> = match attribute("JOINTS_0")? {
+        Some(accessor) => buffers
+            .read_vectors::<4>(accessor, &attributes_context)?
+            .iter()
+            .map(|joints| joints.map(|joint| joint as u16))
+            .collect(),
+        None => Vec::new(),
+    };
+    let weights: Vec<[f32; 4]> = match attribute("WEIGHTS_0")? {
+        Some(accessor) => buffers.read_vectors::<4>(accessor, &attributes_context)?,
+        None => Vec::new(),
+    };
+
+    for (name, count) in [
+        ("NORMAL", normals.len()),
+        ("TEXCOORD_0", tex_coords.len()),
+        ("COLOR_0", colors.len()),
+        ("JOINTS_0", joints.len()),
+        ("WEIGHTS_0", weights.len()),
+    ] {
+        if count != 0 && count != vertex_count {
+            return Err(format!(
+                "{}.{}: holds {} elements, POSITION holds {}",
+                attributes_context, name, count, vertex_count
+            ));
+        }
+    }
+
+    let indices: Vec<u32> = match reference(primitive, "indices", accessor_count, context)? {
+        Some(accessor) => buffers
+            .read_vectors::<1>(accessor, context)?
+            .iter()
+            .map(|[index]| *index as u32)
+            .collect(),
+        None => (0..vertex_count as u32).collect(),
+    };
+    if !indices.len().is_multiple_of(3) {
+        return Err(format!(
+            "{}: {} indices do not form whole triangles",
+            context,
+            indices.len()
+        ));
+    }
+    if let Some(index) = indices
+        .iter()
+        .find(|index| **index as usize >= vertex_count)
+    {
+        return Err(format!(
+            "{}: index {} is out of range ({} vertices)",
+            context, index, vertex_count
+        ));
+    }
+
+    Ok(Primitive {
+        positions,
+        normals,
+        tex_coords,
+        colors,
+        joints,
+        weights,
+        indices,
+        material: reference(primitive, "material", material_count, context)?,
+    })
+}
+
+// Binary data behind the accessors, with every buffer already loaded.
+struct Buffers<'a> {
+    accessors: &'a [JsonValue],
+    buffer_views: &'a [JsonValue],
+    data: Vec<Vec<u8>>,
+}
+
+impl Buffers<'_> {
+    // Returns the bytes of a buffer view and its stride, if it has one.
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>), String> {
+        let view: &JsonValue = &self.buffer_views[index];
+        let context: String = format!("bufferViews[{}]", index);
+        let buffer: usize = required(
+            reference(view, "buffer", self.data.len(), &context)?,
+            "buffer",
+            &context,
+        )?;
+        let offset: usize = usize_field(view, "byteOffset", &context)?.unwrap_or(0);
+        let length: usize = required(
+            usize_field(view, "byteLength", &context)?,
+            "byteLength",
+            &context,
+        )?;
+
+        let bytes: &[u8] = self.data[buffer]
+            .get(offset..offset.saturating_add(length))
+            .ok_or_else(|| format!("{}: range is outside buffers[{}]", context, buffer))?;
+
+        Ok((bytes, usize_field(view, "byteStride", &context)?))
+    }
+
+    // Decodes an accessor into its number of components per element and all of their values.
+    // f64 holds every glTF component type exactly.
+    fn read_accessor(&self, index: usize) -> Result<(usize, Vec<f64>), String> {
+        let accessor: &JsonValue = &self.accessors[index];
+        let context: String = format!("accessors[{}]", index);
+        if accessor.get("sparse").is_some() {
+            return Err(format!("{}: sparse accessors are not supported", context));
+        }
+
+        let count: usize = required(usize_field(accessor, "count", &context)?, "count", &context)?;
+        let component_type: usize = required(
+            usize_field(accessor, "componentType", &context)?,
+            "componentType",
+            &context,
+        )?;
+        let component_size: usize = match component_type {
+            COMPONENT_BYTE | COMPONENT_UNSIGNED_BYTE => 1,
+            COMPONENT_SHORT | COMPONENT_UNSIGNED_SHORT => 2,
+            COMPONENT_UNSIGNED_INT | COMPONENT_FLOAT => 4,
+            other => return Err(format!("{}: unknown componentType {}", context, other)),
+        };
+        // Matrices of 1 or 2 byte components have padded columns, which is not handled; glTF
+        // only uses float matrices for skins.
+        let components: usize =
+            match required(string(accessor, "type", &context)?, "type", &context)? {
+                "SCALAR" => 1,
+                "VEC2" => 2,
+                "VEC3" => 3,
+                "VEC4" | "MAT2" => 4,
+                "MAT3" => 9,
+                "MAT4" => 16,
+                other => return Err(format!("{}: unknown type {}", context, other)),
+            };
+        let normalized: bool = accessor
+            .get("normalized")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(false);
+
+        // An accessor without a buffer view is all zeros.
+        let Some(view) = reference(accessor, "bufferView", self.buffer_views.len(), &context)?
+        else {
+            let value_count: usize = count
+                .checked_mul(components)
+                .filter(|value_count| *value_count <= MAX_ZEROED_ACCESSOR_VALUES)
+                .ok_or_else(|| {
+                    format!(
+                        "{}: {} elements of {} components are too many without a bufferView",
+                        context, count, components
+                    )
+                })?;
+            return Ok((components, vec![0.0; value_count]));
+        };
+        let (bytes, stride) = self.buffer_view(view)?;
+        let element_size: usize = components * component_size;
+        let stride: usize = stride.unwrap_or(element_size);
+        let offset: usize = usize_field(accessor, "byteOffset", &context)?.unwrap_or(0);
+
+        if count > 0 {
+            let end: Option<usize> = stride
+                .checked_mul(count - 1)
+                .and_then(|last| last.checked_add(offset))
+                .and_then(|last| last.checked_add(element_size));
+            if end.is_none_or(|end| end > bytes.len()) {
+                return Err(format!(
+                    "{}: {} elements of {} bytes do not fit in bufferViews[{}]",
+                    context, count, element_size, view
+                ));
+            }
+        }
+
+        let mut values: Vec<f64> = Vec::with_capacity(count * components);
+        for element in 0..count {
+            let start: usize = offset + element * stride;
+            for component in 0..components {
+                let at: usize = start + component * component_size;
+                values.push(read_component(
+                    component_type,
+                    normalized,
+                    &bytes[at..at + component_size],
+                ));
+            }
+        }
+
+        Ok((components, values))
+    }
+
+    fn read_vectors<const N: usize>(
+        &self,
+        index: usize,
+        context: &str,
+    ) -> Result<Vec<[f32; N]>, String> {
+        let (components, values) = self.read_accessor(index)?;
+        if components != N {
+            return Err(format!(
+                "{}: accessors[{}] has {} components per element, expected {}",
+                context, index, components, N
+            ));
+        }
+
+        Ok(values
+            .chunks_exact(N)
+            .map(|element| std::array::from_fn(|component| element[component] as f32))
+            .collect())
+    }
+}
+
+fn read_component(component_type: usize, normalized: bool, bytes: &[u8]) -> f64 {
+    let (value, max): (f64, f64) = match component_type {
+        COMPONENT_BYTE => (bytes[0] as i8 as f64, i8::MAX as f64),
+        COMPONENT_UNSIGNED_BYTE => (bytes[0] as f64, u8::MAX as f64),
+        COMPONENT_SHORT => (
+            i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            i16::MAX as f64,
+        ),
+        COMPONENT_UNSIGNED_SHORT => (
+            u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            u16::MAX as f64,
+        ),
+        COMPONENT_UNSIGNED_INT => (
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            u32::MAX as f64,
+        ),
+        _ => {
+            return f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64;
+        }
+    };
+
+    // Normalized signed values map both -128 and -127 to -1.
+    if normalized {
+        (value / max).max(-1.0)
+    } else {
+        value
+    }
+}
+
+// Nodes must form a forest: at most one parent each and no cycles.
+fn check_hierarchy(nodes: &[Node]) -> Result<(), String> {
+    let mut has_parent: Vec<bool> = vec![false; nodes.len()];
+    for node in nodes {
+        for &child in &node.children {
+            if has_parent[child] {
+                return Err(format!("nodes[{}] has more than one parent", child));
+            }
+            has_parent[child] = true;
+        }
+    }
+
+    // With a single parent each, the nodes a parentless node can not reach are in a cycle.
+    let mut reached: Vec<bool> = vec![false; nodes.len()];
+    let mut stack: Vec<usize> = (0..nodes.len()).filter(|node| !has_parent[*node]).collect();
+    while let Some(node) = stack.pop() {
+        reached[node] = true;
+        stack.extend(&nodes[node].children);
+    }
+    match reached.iter().position(|reached| !reached) {
+        Some(node) => Err(format!("nodes[{}] is part of a cycle", node)),
+        None => Ok(()),
+    }
+}
+
+fn matrix_from_columns(values: &[f32; 16]) -> Mat4 {
+    std::array::from_fn(|column| std::array::from_fn(|row| values[column * 4 + row]))
+}
+
+fn required<T>(value: Option<T>, key: &str, context: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("{}.{} is required", context, key))
+}
+
+fn array<'a>(object: &'a JsonValue, key: &str, context: &str) -> Result<&'a [JsonValue], String> {
+    match object.get(key) {
+        None => Ok(&[]),
+        Some(value) => value
+            .as_array()
+            .ok_or_else(|| format!("{}.{} must be an array", context, key)),
+    }
+}
+
+fn string<'a>(object: &'a JsonValue, key: &str, context: &str) -> Result<Option<&'a str>, String> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| format!("{}.{} must be a string", context, key)),
+    }
+}
+
+fn float(object: &JsonValue, key: &str, context: &str) -> Result<Option<f32>, String> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_f64()
+            .map(|value| Some(value as f32))
+            .ok_or_else(|| format!("{}.{} must be a number", context, key)),
+    }
+}
+
+fn floats<const N: usize>(
+    object: &JsonValue,
+    key: &str,
+    context: &str,
+) -> Result<Option<[f32; N]>, String> {
+    let values: &[JsonValue] = match object.get(key) {
+        None => return Ok(None),
+        Some(value) => value.as_array().unwrap_or_default(),
+    };
+    if values.len() != N || values.iter().any(|value| value.as_f64().is_none()) {
+        return Err(format!(
+            "{}.{} must be an array of {} numbers",
+            context, key, N
+        ));
+    }
+
+    Ok(Some(std::array::from_fn(|index| {
+        values[index].as_f64().unwrap_or_default() as f32
+    })))
+}
+
+fn usize_field(object: &JsonValue, key: &str, context: &str) -> Result<Option<usize>, String> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_usize()
+            .map(Some)
+            .ok_or_else(|| format!("{}.{} must be a non-negative integer", context, key)),
+    }
+}
+
+// An index into another top-level list of `count` elements.
+fn reference(
+    object: &JsonValue,
+    key: &str,
+    count: usize,
+    context: &str,
+) -> Result<Option<usize>, String> {
+    match usize_field(object, key, context)? {
+        Some(index) if index >= count => Err(format!(
+            "{}.{}: index {} is out of range ({} defined)",
+            context, key, index, count
+        )),
+        index => Ok(index),
+    }
+}
+
+fn references(
+    object: &JsonValue,
+    key: &str,
+    count: usize,
+    context: &str,
+) -> Result<Vec<usize>, String> {
+    array(object, key, context)?
+        .iter()
+        .map(|value| match value.as_usize() {
+            Some(index) if index < count => Ok(index),
+            _ => Err(format!(
+                "{}.{}: {:?} is not an index below {}",
+                context, key, value, count
+            )),
+        })
+        .collect()
+}
+
+fn texture_ref(
+    object: &JsonValue,
+    key: &str,
+    texture_count: usize,
+    context: &str,
+) -> Result<Option<TextureRef>, String> {
+    let Some(info) = object.get(key) else {
+        return Ok(None);
+    };
+    let context: String = format!("{}.{}", context, key);
+
+    Ok(Some(TextureRef {
+        texture: required(
+            reference(info, "index", texture_count, &context)?,
+            "index",
+            &context,
+        )?,
+        tex_coord: usize_field(info, "texCoord", &context)?.unwrap_or(0),
+    }))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|field| u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+        .ok_or_else(|| format!("truncated at byte {}", offset))
+}
+
+// Decodes `%XX` escapes in a relative URI, so it can be used as a file path.
+fn decode_uri(uri: &str) -> String {
+    let bytes: &[u8] = uri.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut index: usize = 0;
+    while index < bytes.len() {
+        let escaped: Option<u8> = match bytes[index] {
+            b'%' => uri
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Splits a `data:<mime type>;base64,<data>` URI into its MIME type and decoded bytes.
+fn decode_data_uri(uri: &str) -> Result<(String, Vec<u8>), String> {
+    let (header, data) = uri
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+        .ok_or_else(|| String::from("malformed data URI"))?;
+    let mime_type: &str = header
+        .strip_suffix(";base64")
+        .ok_or_else(|| String::from("only base64 data URIs are supported"))?;
+
+    Ok((mime_type.to_string(), decode_base64(data)?))
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() / 4 * 3);
+    let mut accumulator: u32 = 0;
+    let mut bits: u32 = 0;
+
+    for c in text.trim_end_matches('=').bytes() {
+        let value: u8 = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("invalid base64 character '{}'", c as char)),
+        };
+        accumulator = (accumulator << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((accumulator >> bits) as u8);
+            accumulator &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/gltf");
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    fn no_files(uri: &str) -> Result<Vec<u8>, String> {
+        Err(format!("{} not found", uri))
+    }
+
+    #[test]
+    fn gltf_with_external_buffer() {
+        let scene: Scene = load(&format!("{}/hierarchy.gltf", FIXTURES)).unwrap();
+
+        assert_eq!(scene.root_nodes, vec![0]);
+        assert_eq!(scene.nodes.len(), 3);
+        assert_eq!(scene.nodes[0].name.as_deref(), Some("root"));
+        assert_eq!(scene.nodes[0].children, vec![1, 2]);
+        assert_eq!(scene.nodes[1].mesh, Some(0));
+        assert_eq!(scene.nodes[2].camera, Some(0));
+
+        let material: &Material = &scene.materials[0];
+        assert_eq!(material.base_color_factor, [1.0, 0.5, 0.25, 1.0]);
+        assert_eq!(material.metallic_factor, 0.25);
+        assert_eq!(material.roughness_factor, 0.75);
+        assert_eq!(material.base_color_texture.map(|t| t.texture), Some(0));
+        assert_eq!(material.alpha_mode, AlphaMode::Mask(0.3));
+        assert!(material.double_sided);
+        assert_eq!(
+            scene.images[0],
+            Image::Uri(format!("{}/base color.png", FIXTURES))
+        );
+        assert_eq!(
+            scene.cameras[0],
+            Camera::Perspective {
+                y_fov: 0.8,
+                aspect_ratio: Some(1.5),
+                z_near: 0.1,
+                z_far: Some(100.0),
+            }
+        );
+
+        let primitive: &Primitive = &scene.meshes[0].primitives[0];
+        assert_eq!(primitive.indices, vec![0, 1, 2]);
+        assert_eq!(primitive.tex_coords[1], [1.0, 0.0]);
+
+        // The child is rotated a quarter turn around Y, scaled by 2 and moved by its parent.
+        let transforms: Vec<(usize, Mat4)> = scene.world_transforms();
+        assert_eq!(
+            transforms.iter().map(|(node, _)| *node).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_close(
+            &math::transform_point(&transforms[1].1, [1.0, 0.0, 0.0]),
+            &[0.0, 0.0, -4.0],
+        );
+
+        let mesh: mesh::Mesh = scene.to_mesh();
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(mesh.face_materials, vec![0]);
+        assert_close(&mesh.vertices[1].position, &[0.0, 0.0, -4.0]);
+        assert_close(&mesh.vertices[0].normal, &[1.0, 0.0, 0.0]);
+        assert_eq!(mesh.vertices[0].color, [1.0, 0.5, 0.25]);
+        assert_eq!(
+            mesh.materials[0].diffuse_texture,
+            Some(format!("{}/base color.png", FIXTURES))
+        );
+    }
+
+    #[test]
+    fn glb_with_skin_and_primitives() {
+        let scene: Scene = load(&format!("{}/skinned.glb", FIXTURES)).unwrap();
+
+        // Without scenes every parentless node is a root.
+        assert_eq!(scene.root_nodes, vec![0, 1, 3]);
+        assert_eq!(scene.nodes[0].skin, Some(0));
+
+        let primitives: &[Primitive] = &scene.meshes[0].primitives;
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(
+            primitives[0].joints,
+            vec![[0, 1, 0, 0], [1, 0, 0, 0], [0, 0, 0, 0]]
+        );
+        assert_eq!(primitives[0].weights[0], [0.75, 0.25, 0.0, 0.0]);
+        assert_eq!(primitives[0].material, Some(0));
+        // Interleaved with normalized unsigned short texture coordinates.
+        assert_eq!(primitives[1].positions[2], [0.0, 1.0, 1.0]);
+        assert_eq!(
+            primitives[1].tex_coords,
+            vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+        );
+        assert_eq!(primitives[1].indices, vec![0, 1, 2]);
+        assert_eq!(primitives[1].material, None);
+
+        let skin: &Skin = &scene.skins[0];
+        assert_eq!(skin.joints, vec![1, 2]);
+        assert_eq!(skin.skeleton, Some(1));
+        assert_eq!(skin.inverse_bind_matrices[1][3], [0.0, -0.5, 0.0, 1.0]);
+        assert_eq!(
+            scene.cameras[0],
+            Camera::Orthographic {
+                x_mag: 2.0,
+                y_mag: 1.0,
+                z_near: 0.01,
+                z_far: 10.0,
+            }
+        );
+
+        let material: &Material = &scene.materials[0];
+        assert_eq!(
+            (material.metallic_factor, material.roughness_factor),
+            (1.0, 1.0)
+        );
+
+        // Neither primitive has normals, so both get flat shaded corners of their own.
+        let mesh: mesh::Mesh = scene.to_mesh();
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(mesh.face_materials, vec![0, NO_MATERIAL]);
+        assert_close(&mesh.vertices[0].normal, &[0.0, 0.0, 1.0]);
+        assert_close(&mesh.vertices[3].color, &[1.0, 1.0, 1.0]);
+        assert_close(&mesh.vertices[0].color, &[0.2, 0.4, 0.6]);
+    }
+
+    #[test]
+    fn embedded_buffer_and_mirrored_node() {
+        // One triangle, positions (0,0,0) (1,0,0) (0,1,0), in a base64 data URI.
+        let json: &str = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 36, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"}],
+            "bufferViews": [{"buffer": 0, "byteLength": 36}],
+            "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+            "nodes": [{"mesh": 0, "scale": [-1, 1, 1]}]
+        }"#;
+        let scene: Scene = parse_gltf(json, None, no_files).unwrap();
+        assert_eq!(scene.meshes[0].primitives[0].positions[1], [1.0, 0.0, 0.0]);
+
+        // The mirrored triangle keeps facing +Z after its winding is reversed.
+        let mesh: mesh::Mesh = scene.to_mesh();
+        assert_close(&mesh.vertices[1].position, &[0.0, 1.0, 0.0]);
+        assert_close(&mesh.vertices[0].normal, &[0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn errors_name_the_offending_field() {
+        let parse = |body: &str| {
+            parse_gltf(
+                &format!(r#"{{"asset": {{"version": "2.0"}}, {}}}"#, body),
+                None,
+                no_files,
+            )
+            .err()
+            .unwrap()
+        };
+
+        assert_eq!(
+            parse(r#""nodes": [{"children": [1]}, {"children": [0]}]"#),
+            "nodes[0] is part of a cycle"
+        );
+        assert_eq!(
+            parse(r#""nodes": [{"mesh": 2}]"#),
+            "nodes[0].mesh: index 2 is out of range (0 defined)"
+        );
+        assert_eq!(
+            parse(
+                r#""buffers": [{"byteLength": 4, "uri": "data:application/octet-stream;base64,AAAAAA=="}],
+                "bufferViews": [{"buffer": 0, "byteLength": 4}],
+                "accessors": [{"bufferView": 0, "componentType": 5126, "count": 1, "type": "SCALAR"}],
+                "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}]"#
+            ),
+            "meshes[0].primitives[0].attributes: accessors[0] has 1 components per element, \
+             expected 3"
+        );
+        assert_eq!(
+            parse(r#""buffers": [{"byteLength": 4, "uri": "missing.bin"}]"#),
+            "buffers[0]: can not read missing.bin: missing.bin not found"
+        );
+        assert_eq!(
+            parse(
+                r#""accessors": [{"componentType": 5126, "count": 100000000, "type": "VEC3"}],
+                "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}]"#
+            ),
+            "accessors[0]: 100000000 elements of 3 components are too many without a bufferView"
+        );
+        assert!(parse_gltf(r#"{"asset": {"version": "1.0"}}"#, None, no_files).is_err());
+    }
+}
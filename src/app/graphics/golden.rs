@@ -0,0 +1,226 @@
+// Golden-image regression tests: named scenes are rendered headless and their last frame is
+// compared against a reference image in tests/golden. Rendering needs a Vulkan driver (lavapipe
+// is enough), so those tests are ignored by default:
+//
+//     cargo test golden -- --ignored
+//
+// Run them with UPDATE_GOLDEN=1 to write the rendered frames as the new references. On a
+// mismatch the rendered frame and a diff image are written to target/golden.
+
+use std::fs;
+use std::path::Path;
+
+use super::api::{GraphicApi, GraphicError, GraphicsSettings};
+use super::texture::ImageData;
+use super::vulkan::VulkanApi;
+
+const REFERENCE_DIR: &str = "tests/golden";
+const OUTPUT_DIR: &str = "target/golden";
+
+pub struct Scene {
+    // Also the file name of the reference image.
+    pub name: &'static str,
+    pub width: usize,
+    pub height: usize,
+    // Frames rendered before the last one is compared. Headless animation advances a fixed step
+    // per frame, so the same frame always shows the same thing.
+    pub frames: u32,
+    // Largest difference allowed in any channel of a pixel; 0 requires an exact match.
+    pub tolerance: u8,
+    pub settings: GraphicsSettings,
+}
+
+pub struct Comparison {
+    pub mismatched_pixels: usize,
+    pub max_difference: u8,
+    // Mismatched pixels in red over a dimmed grayscale copy of the reference.
+    pub diff: ImageData,
+}
+
+pub fn compare(
+    actual: &ImageData,
+    expected: &ImageData,
+    tolerance: u8,
+) -> Result<Comparison, String> {
+    if (actual.width, actual.height) != (expected.width, expected.height) {
+        return Err(format!(
+            "image is {}x{} but the reference is {}x{}",
+            actual.width, actual.height, expected.width, expected.height
+        ));
+    }
+
+    let mut mismatched_pixels: usize = 0;
+    let mut max_difference: u8 = 0;
+    let mut diff_pixels: Vec<u8> = Vec::with_capacity(expected.pixels.len());
+    for (a, e) in actual
+        .pixels
+        .chunks_exact(4)
+        .zip(expected.pixels.chunks_exact(4))
+    {
+        let difference: u8 = a
+            .iter()
+            .zip(e)
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            diff_pixels.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma: u8 = ((e[0] as u32 * 2 + e[1] as u32 * 5 + e[2] as u32) / 32) as u8;
+            diff_pixels.extend_from_slice(&[luma, luma, luma, 255]);
+        }
+    }
+
+    Ok(Comparison {
+        mismatched_pixels,
+        max_difference,
+        diff: ImageData {
+            width: expected.width,
+            height: expected.height,
+            pixels: diff_pixels,
+        },
+    })
+}
+
+pub fn render(scene: &Scene) -> Result<ImageData, GraphicError> {
+    let settings: GraphicsSettings = GraphicsSettings {
+        headless: true,
+        ..scene.settings.clone()
+    };
+    let api: VulkanApi = VulkanApi::new(scene.width, scene.height, settings);
+
+    api.init_window();
+    let result: Result<ImageData, GraphicError> = api.init_api().and_then(|_| {
+        for _ in 1..scene.frames {
+            api.render_offscreen_frame()?;
+        }
+        Ok(api.render_offscreen_frame()?)
+    });
+    api.cleanup();
+
+    result
+}
+
+pub fn check(scene: &Scene) -> Result<(), String> {
+    let actual: ImageData = render(scene).map_err(|error| error.to_string())?;
+    let reference_path: String = format!("{}/{}.png", REFERENCE_DIR, scene.name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(REFERENCE_DIR).map_err(|error| error.to_string())?;
+        return actual
+            .save_png(&reference_path)
+            .map_err(|error| error.to_string());
+    }
+    if !Path::new(&reference_path).exists() {
+        return Err(format!(
+            "{}: no reference image, run with UPDATE_GOLDEN=1 to create it",
+            reference_path
+        ));
+    }
+
+    let expected: ImageData =
+        ImageData::load(&reference_path).map_err(|error| error.to_string())?;
+    let comparison: Comparison = compare(&actual, &expected, scene.tolerance)
+        .map_err(|error| format!("{}: {}", scene.name, error))?;
+    if comparison.mismatched_pixels == 0 {
+        return Ok(());
+    }
+
+    let actual_path: String = format!("{}/{}.png", OUTPUT_DIR, scene.name);
+    let diff_path: String = format!("{}/{}.diff.png", OUTPUT_DIR, scene.name);
+    fs::create_dir_all(OUTPUT_DIR).map_err(|error| error.to_string())?;
+    actual
+        .save_png(&actual_path)
+        .map_err(|error| error.to_string())?;
+    comparison
+        .diff
+        .save_png(&diff_path)
+        .map_err(|error| error.to_string())?;
+
+    Err(format!(
+        "{}: {} pixels differ by more than {} (at most {}), see {} and {}",
+        scene.name,
+        comparison.mismatched_pixels,
+        scene.tolerance,
+        comparison.max_difference,
+        actual_path,
+        diff_path
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> ImageData {
+        ImageData {
+            width,
+            height,
+            pixels: pixel.repeat((width * height) as usize),
+        }
+    }
+
+    #[test]
+    fn compare_applies_the_tolerance_per_channel() {
+        let expected: ImageData = image(2, 2, [100, 100, 100, 255]);
+        let mut actual: ImageData = image(2, 2, [100, 100, 100, 255]);
+        actual.pixels[1] = 103;
+        actual.pixels[6] = 90;
+
+        let comparison: Comparison = compare(&actual, &expected, 3).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 1);
+        assert_eq!(comparison.max_difference, 10);
+        assert_eq!(&comparison.diff.pixels[0..4], &[25, 25, 25, 255]);
+        assert_eq!(&comparison.diff.pixels[4..8], &[255, 0, 0, 255]);
+
+        assert_eq!(
+            compare(&actual, &expected, 10).unwrap().mismatched_pixels,
+            0
+        );
+    }
+
+    #[test]
+    fn compare_rejects_different_sizes() {
+        let error: String = compare(&image(2, 1, [0; 4]), &image(1, 2, [0; 4]), 0)
+            .err()
+            .unwrap();
+        assert_eq!(error, "image is 2x1 but the reference is 1x2");
+    }
+
+    // Validation is off even in debug builds: CI drivers usually come without the layers.
+    fn scene(name: &'static str, model_path: &str, msaa_samples: u32) -> Scene {
+        Scene {
+            name,
+            width: 256,
+            height: 256,
+            frames: 3,
+            tolerance: 2,
+            settings: GraphicsSettings {
+                model_path: model_path.to_string(),
+                msaa_samples,
+                validation: false,
+                ..GraphicsSettings::default()
+            },
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a Vulkan driver"]
+    fn golden_textured_cube() {
+        if let Err(error) = check(&scene("textured_cube", "models/cube.obj", 1)) {
+            panic!("{}", error);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a Vulkan driver"]
+    fn golden_pyramid_msaa() {
+        let pyramid: Scene = scene("pyramid_msaa", "tests/fixtures/models/pyramid.obj", 4);
+        if let Err(error) = check(&pyramid) {
+            panic!("{}", error);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs;
+
+use crate::vulkan::VkFormat;
+
+use super::load::LoadError;
+use super::texture::ImageData;
+
+use VkFormat::VK_FORMAT_R8G8B8A8_SRGB;
+
+// Only uncompressed 2D RGBA8 textures are read, which is what the texture pipeline samples.
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+// Reads every mip level stored in a KTX2 file, base level first, and the level count of its
+// header. A level count of 0 asks the loader to generate the mips below the stored base level.
+pub fn load(path: &str) -> Result<(Vec<ImageData>, u32), LoadError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(|source| LoadError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+
+    parse(&bytes).map_err(|reason| LoadError::invalid(path, reason))
+}
+
+fn parse(bytes: &[u8]) -> Result<(Vec<ImageData>, u32), String> {
+    if bytes.len() < HEADER_SIZE || bytes[..12] != KTX2_IDENTIFIER {
+        return Err(String::from("not a KTX2 file"));
+    }
+
+    let vk_format: u32 = read_u32(bytes, 12)?;
+    let width: u32 = read_u32(bytes, 20)?;
+    let height: u32 = read_u32(bytes, 24)?;
+    let depth: u32 = read_u32(bytes, 28)?;
+    let layer_count: u32 = read_u32(bytes, 32)?;
+    let face_count: u32 = read_u32(bytes, 36)?;
+    let level_count: u32 = read_u32(bytes, 40)?;
+    let supercompression_scheme: u32 = read_u32(bytes, 44)?;
+
+    if vk_format != VK_FORMAT_R8G8B8A8_SRGB as u32 {
+        return Err(format!(
+            "format {} is not supported, expected VK_FORMAT_R8G8B8A8_SRGB",
+            vk_format
+        ));
+    }
+    if supercompression_scheme != 0 {
+        return Err(format!(
+            "supercompression scheme {} is not supported",
+            supercompression_scheme
+        ));
+    }
+    if width == 0 || height == 0 || depth != 0 || layer_count > 1 || face_count != 1 {
+        return Err(String::from("only single 2D images are supported"));
+    }
+
+    // More levels than the full mip chain can not be valid, and are rejected before allocating.
+    let max_level_count: u32 = u32::BITS - width.max(height).leading_zeros();
+    if level_count > max_level_count {
+        return Err(format!(
+            "{} levels do not fit a {}x{} image, which has at most {}",
+            level_count, width, height, max_level_count
+        ));
+    }
+    // With a level count of 0 the base level is all that is stored.
+    let stored_level_count: usize = level_count.max(1) as usize;
+    let mut levels: Vec<ImageData> = Vec::with_capacity(stored_level_count);
+    for level in 0..stored_level_count {
+        let entry: usize = HEADER_SIZE + level * LEVEL_INDEX_ENTRY_SIZE;
+        let byte_offset: usize = read_u64(bytes, entry)? as usize;
+        let byte_length: usize = read_u64(bytes, entry + 8)? as usize;
+
+        let level_width: u32 = (width >> level).max(1);
+        let level_height: u32 = (height >> level).max(1);
+        let expected_length: usize = level_width as usize * level_height as usize * 4;
+        if byte_length != expected_length {
+            return Err(format!(
+                "level {} holds {} bytes, expected {} for {}x{}",
+                level, byte_length, expected_length, level_width, level_height
+            ));
+        }
+        let pixels: &[u8] = byte_offset
+            .checked_add(byte_length)
+            .and_then(|end| bytes.get(byte_offset..end))
+            .ok_or_else(|| format!("level {} is out of bounds", level))?;
+
+        levels.push(ImageData {
+            width: level_width,
+            height: level_height,
+            pixels: pixels.to_vec(),
+        });
+    }
+
+    Ok((levels, level_count))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|field| u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+        .ok_or_else(|| format!("truncated at byte {}", offset))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, String> {
+    let low: u64 = read_u32(bytes, offset)? as u64;
+    let high: u64 = read_u32(bytes, offset + 4)? as u64;
+
+    Ok(low | (high << 32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A KTX2 file with the given header fields and one level index entry per level.
+    fn header(vk_format: u32, width: u32, height: u32, level_count: u32) -> Vec<u8> {
+        let mut bytes: Vec<u8> = KTX2_IDENTIFIER.to_vec();
+        for field in [vk_format, 1, width, height, 0, 0, 1, level_count, 0] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.resize(HEADER_SIZE, 0);
+        bytes
+    }
+
+    fn add_level(bytes: &mut Vec<u8>, byte_offset: u64, byte_length: u64) {
+        bytes.extend_from_slice(&byte_offset.to_le_bytes());
+        bytes.extend_from_slice(&byte_length.to_le_bytes());
+        bytes.extend_from_slice(&byte_length.to_le_bytes());
+    }
+
+    fn parse_error(bytes: &[u8]) -> String {
+        match parse(bytes) {
+            Ok((levels, _)) => panic!("expected an error, parsed {} levels", levels.len()),
+            Err(reason) => reason,
+        }
+    }
+
+    #[test]
+    fn reads_every_level() {
+        let srgb: u32 = VK_FORMAT_R8G8B8A8_SRGB as u32;
+        let mut bytes: Vec<u8> = header(srgb, 2, 1, 2);
+        let data_start: u64 = (HEADER_SIZE + 2 * LEVEL_INDEX_ENTRY_SIZE) as u64;
+        add_level(&mut bytes, data_start, 8);
+        add_level(&mut bytes, data_start + 8, 4);
+        bytes.extend((0..12).map(|byte| byte as u8));
+
+        let (levels, level_count) = parse(&bytes).unwrap();
+        let sizes: Vec<(u32, u32)> = levels
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect();
+        assert_eq!(sizes, [(2, 1), (1, 1)]);
+        assert_eq!(levels[1].pixels, [8, 9, 10, 11]);
+        assert_eq!(level_count, 2);
+    }
+
+    #[test]
+    fn zero_levels_store_the_base_level_and_ask_for_mips() {
+        let srgb: u32 = VK_FORMAT_R8G8B8A8_SRGB as u32;
+        let mut bytes: Vec<u8> = header(srgb, 4, 2, 0);
+        let data_start: u64 = (HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE) as u64;
+        add_level(&mut bytes, data_start, 32);
+        bytes.extend([0xFF; 32]);
+
+        let (levels, level_count) = parse(&bytes).unwrap();
+        assert_eq!(level_count, 0);
+        assert_eq!(levels.len(), 1);
+        assert_eq!((levels[0].width, levels[0].height), (4, 2));
+        // The loader generates the rest of the chain from the base level.
+        assert_eq!(levels[0].mip_level_count(), 3);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        let srgb: u32 = VK_FORMAT_R8G8B8A8_SRGB as u32;
+        assert_eq!(parse_error(&header(srgb, 4, 4, 1)[..60]), "not a KTX2 file");
+        assert_eq!(
+            parse_error(&header(37, 4, 4, 1)),
+            "format 37 is not supported, expected VK_FORMAT_R8G8B8A8_SRGB"
+        );
+        // The level index is missing.
+        assert_eq!(parse_error(&header(srgb, 4, 4, 1)), "truncated at byte 80");
+
+        assert_eq!(
+            parse_error(&header(srgb, 4, 4, u32::MAX)),
+            "4294967295 levels do not fit a 4x4 image, which has at most 3"
+        );
+        assert_eq!(
+            parse_error(&header(srgb, 1, u32::MAX, 33)),
+            "33 levels do not fit a 1x4294967295 image, which has at most 32"
+        );
+
+        let mut bytes: Vec<u8> = header(srgb, 1, 1, 1);
+        add_level(&mut bytes, u64::MAX, 4);
+        assert_eq!(parse_error(&bytes), "level 0 is out of bounds");
+    }
+}
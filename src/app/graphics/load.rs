@@ -0,0 +1,39 @@
+use std::fmt;
+use std::io;
+
+// Errors of the model and texture loaders, which every backend shares.
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: String, source: io::Error },
+    // The file was read, but is not a model or image the loader understands.
+    Invalid { path: String, reason: String },
+    Write { path: String, reason: String },
+}
+
+impl LoadError {
+    pub fn invalid(path: &str, reason: impl fmt::Display) -> Self {
+        LoadError::Invalid {
+            path: path.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            LoadError::Invalid { path, reason } => write!(f, "Failed to load {}: {}", path, reason),
+            LoadError::Write { path, reason } => write!(f, "Failed to write {}: {}", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,227 @@
+// Column-major 4x4 matrices, laid out like GLSL mat4 so they can be copied into uniform buffers.
+pub type Mat4 = [[f32; 4]; 4];
+pub type Vec3 = [f32; 3];
+
+pub fn identity() -> Mat4 {
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+pub fn multiply(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut result: Mat4 = [[0.0; 4]; 4];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[column][row] = (0..4).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+
+    result
+}
+
+pub fn rotation(angle: f32, axis: Vec3) -> Mat4 {
+    let axis: Vec3 = normalize(axis);
+    let (sin, cos) = angle.sin_cos();
+    let temp: Vec3 = [
+        (1.0 - cos) * axis[0],
+        (1.0 - cos) * axis[1],
+        (1.0 - cos) * axis[2],
+    ];
+
+    let mut result: Mat4 = identity();
+    result[0][0] = cos + temp[0] * axis[0];
+    result[0][1] = temp[0] * axis[1] + sin * axis[2];
+    result[0][2] = temp[0] * axis[2] - sin * axis[1];
+    result[1][0] = temp[1] * axis[0] - sin * axis[2];
+    result[1][1] = cos + temp[1] * axis[1];
+    result[1][2] = temp[1] * axis[2] + sin * axis[0];
+    result[2][0] = temp[2] * axis[0] + sin * axis[1];
+    result[2][1] = temp[2] * axis[1] - sin * axis[0];
+    result[2][2] = cos + temp[2] * axis[2];
+
+    result
+}
+
+pub fn translation(offset: Vec3) -> Mat4 {
+    let mut result: Mat4 = identity();
+    result[3][0] = offset[0];
+    result[3][1] = offset[1];
+    result[3][2] = offset[2];
+
+    result
+}
+
+pub fn scaling(scale: Vec3) -> Mat4 {
+    let mut result: Mat4 = identity();
+    result[0][0] = scale[0];
+    result[1][1] = scale[1];
+    result[2][2] = scale[2];
+
+    result
+}
+
+// Rotation from a unit quaternion stored as [x, y, z, w].
+pub fn quaternion_rotation(q: [f32; 4]) -> Mat4 {
+    let [x, y, z, w] = q;
+
+    let mut result: Mat4 = identity();
+    result[0][0] = 1.0 - 2.0 * (y * y + z * z);
+    result[0][1] = 2.0 * (x * y + z * w);
+    result[0][2] = 2.0 * (x * z - y * w);
+    result[1][0] = 2.0 * (x * y - z * w);
+    result[1][1] = 1.0 - 2.0 * (x * x + z * z);
+    result[1][2] = 2.0 * (y * z + x * w);
+    result[2][0] = 2.0 * (x * z + y * w);
+    result[2][1] = 2.0 * (y * z - x * w);
+    result[2][2] = 1.0 - 2.0 * (x * x + y * y);
+
+    result
+}
+
+pub fn transpose(m: &Mat4) -> Mat4 {
+    let mut result: Mat4 = [[0.0; 4]; 4];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[column][row] = m[row][column];
+        }
+    }
+
+    result
+}
+
+// Inverse by cofactor expansion, or None for a singular matrix.
+pub fn inverse(m: &Mat4) -> Option<Mat4> {
+    let a: [f32; 16] = [
+        m[0][0], m[0][1], m[0][2], m[0][3], m[1][0], m[1][1], m[1][2], m[1][3], m[2][0], m[2][1],
+        m[2][2], m[2][3], m[3][0], m[3][1], m[3][2], m[3][3],
+    ];
+    let s: [f32; 6] = [
+        a[0] * a[5] - a[4] * a[1],
+        a[0] * a[6] - a[4] * a[2],
+        a[0] * a[7] - a[4] * a[3],
+        a[1] * a[6] - a[5] * a[2],
+        a[1] * a[7] - a[5] * a[3],
+        a[2] * a[7] - a[6] * a[3],
+    ];
+    let c: [f32; 6] = [
+        a[8] * a[13] - a[12] * a[9],
+        a[8] * a[14] - a[12] * a[10],
+        a[8] * a[15] - a[12] * a[11],
+        a[9] * a[14] - a[13] * a[10],
+        a[9] * a[15] - a[13] * a[11],
+        a[10] * a[15] - a[14] * a[11],
+    ];
+    let determinant: f32 =
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+    if determinant.abs() <= f32::EPSILON * f32::EPSILON {
+        return None;
+    }
+    let d: f32 = 1.0 / determinant;
+
+    Some([
+        [
+            (a[5] * c[5] - a[6] * c[4] + a[7] * c[3]) * d,
+            (-a[1] * c[5] + a[2] * c[4] - a[3] * c[3]) * d,
+            (a[13] * s[5] - a[14] * s[4] + a[15] * s[3]) * d,
+            (-a[9] * s[5] + a[10] * s[4] - a[11] * s[3]) * d,
+        ],
+        [
+            (-a[4] * c[5] + a[6] * c[2] - a[7] * c[1]) * d,
+            (a[0] * c[5] - a[2] * c[2] + a[3] * c[1]) * d,
+            (-a[12] * s[5] + a[14] * s[2] - a[15] * s[1]) * d,
+            (a[8] * s[5] - a[10] * s[2] + a[11] * s[1]) * d,
+        ],
+        [
+            (a[4] * c[4] - a[5] * c[2] + a[7] * c[0]) * d,
+            (-a[0] * c[4] + a[1] * c[2] - a[3] * c[0]) * d,
+            (a[12] * s[4] - a[13] * s[2] + a[15] * s[0]) * d,
+            (-a[8] * s[4] + a[9] * s[2] - a[11] * s[0]) * d,
+        ],
+        [
+            (-a[4] * c[3] + a[5] * c[1] - a[6] * c[0]) * d,
+            (a[0] * c[3] - a[1] * c[1] + a[2] * c[0]) * d,
+            (-a[12] * s[3] + a[13] * s[1] - a[14] * s[0]) * d,
+            (a[8] * s[3] - a[9] * s[1] + a[10] * s[0]) * d,
+        ],
+    ])
+}
+
+pub fn transform_point(m: &Mat4, p: Vec3) -> Vec3 {
+    let mut result: Vec3 = [m[3][0], m[3][1], m[3][2]];
+    for (row, value) in result.iter_mut().enumerate() {
+        *value += m[0][row] * p[0] + m[1][row] * p[1] + m[2][row] * p[2];
+    }
+
+    result
+}
+
+// Applies only the upper 3x3 part, for directions.
+pub fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+    let mut result: Vec3 = [0.0; 3];
+    for (row, value) in result.iter_mut().enumerate() {
+        *value = m[0][row] * v[0] + m[1][row] * v[1] + m[2][row] * v[2];
+    }
+
+    result
+}
+
+// Right-handed view matrix looking from eye towards center.
+pub fn look_at(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+    let forward: Vec3 = normalize(subtract(center, eye));
+    let side: Vec3 = normalize(cross(forward, up));
+    let up: Vec3 = cross(side, forward);
+
+    let mut result: Mat4 = identity();
+    for i in 0..3 {
+        result[i][0] = side[i];
+        result[i][1] = up[i];
+        result[i][2] = -forward[i];
+    }
+    result[3][0] = -dot(side, eye);
+    result[3][1] = -dot(up, eye);
+    result[3][2] = dot(forward, eye);
+
+    result
+}
+
+// Right-handed perspective projection with Vulkan's 0..1 depth range and Y pointing down.
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    let focal_length: f32 = 1.0 / (fov_y / 2.0).tan();
+
+    let mut result: Mat4 = [[0.0; 4]; 4];
+    result[0][0] = focal_length / aspect;
+    result[1][1] = -focal_length;
+    result[2][2] = far / (near - far);
+    result[2][3] = -1.0;
+    result[3][2] = -(far * near) / (far - near);
+
+    result
+}
+
+pub fn subtract(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn normalize(v: Vec3) -> Vec3 {
+    let length: f32 = dot(v, v).sqrt();
+    if length == 0.0 {
+        return v;
+    }
+
+    [v[0] / length, v[1] / length, v[2] / length]
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use super::load::LoadError;
+use super::math::Vec3;
+use super::vertex::MeshVertex;
+use super::{gltf, obj};
+
+// Drawn when the settings do not name another model.
+pub const MODEL_PATH: &str = "models/cube.obj";
+
+// Face material ID of triangles drawn without a material.
+pub const NO_MATERIAL: u32 = u32::MAX;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub diffuse: Vec3,
+    // Path of the diffuse texture, resolved against the model's directory by the loader.
+    pub diffuse_texture: Option<String>,
+}
+
+// Triangulated mesh ready to be uploaded, whatever file format it was loaded from.
+#[derive(Debug)]
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    // Material of every triangle, as an index into `materials` or NO_MATERIAL.
+    pub face_materials: Vec<u32>,
+    pub materials: Vec<Material>,
+}
+
+pub fn load(path: &str) -> Result<Mesh, LoadError> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("gltf") | Some("glb") => Ok(gltf::load(path)?.to_mesh()),
+        _ => obj::load(path),
+    }
+}
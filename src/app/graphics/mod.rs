@@ -1,15 +1,37 @@
 pub mod api;
+#[cfg(test)]
+mod golden;
+pub mod gltf;
+pub mod ktx2;
+pub mod load;
+pub mod math;
+pub mod mesh;
+pub mod null;
+pub mod obj;
 pub mod opengl;
+pub mod reflect;
+pub mod shader;
+pub mod shader_include;
+pub mod shader_registry;
+pub mod specialization;
+pub mod spirv;
+pub mod texture;
+pub mod vertex;
 pub mod vulkan;
 
 use api::{GraphicApi, Window};
+
+pub use api::{GpuSelection, GraphicError, GraphicsSettings};
+use null::NullApi;
 use opengl::OpenGLApi;
 use vulkan::VulkanApi;
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GraphicsType {
     Vulkan,
     OpenGL,
+    // Draws nothing and records the calls it gets, for tests.
+    Null,
 }
 
 pub struct Graphics {
@@ -17,15 +39,26 @@ pub struct Graphics {
 }
 
 impl Graphics {
-    pub fn new(width: usize, height: usize, api_type: GraphicsType) -> Self {
+    pub fn new(
+        width: usize,
+        height: usize,
+        api_type: GraphicsType,
+        settings: GraphicsSettings,
+    ) -> Self {
         let api: Box<dyn GraphicApi> = match api_type {
-            GraphicsType::Vulkan => Box::new(VulkanApi::new(width, height)),
-            GraphicsType::OpenGL => Box::new(OpenGLApi::new(width, height)),
+            GraphicsType::Vulkan => Box::new(VulkanApi::new(width, height, settings)),
+            GraphicsType::OpenGL => Box::new(OpenGLApi::new(width, height, settings)),
+            GraphicsType::Null => Box::new(NullApi::new(settings)),
         };
 
         Self { api }
     }
 
+    #[cfg(test)]
+    pub fn with_api(api: Box<dyn GraphicApi>) -> Self {
+        Self { api }
+    }
+
     pub fn init_window(&self) {
         let window = self.api.init_window();
         match window {
@@ -38,11 +71,12 @@ impl Graphics {
                 if window.is_null() {
                     panic!("OpenGL window is null");
                 }
-            }
+            },
+            Window::Headless => {}
         }
     }
 
-    pub fn init_api(&self) {
+    pub fn init_api(&self) -> Result<(), GraphicError> {
         self.api.init_api()
     }
 
@@ -62,7 +96,7 @@ impl Graphics {
         self.api.wait_events()
     }
 
-    pub fn draw_frame(&self) {
+    pub fn draw_frame(&self) -> Result<(), GraphicError> {
         self.api.draw_frame()
     }
 
@@ -0,0 +1,95 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::utils::debug_mode;
+
+use super::api::{GraphicApi, GraphicError, GraphicsSettings, Window};
+
+// One entry per GraphicApi method, in the order the backend was driven.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Call {
+    InitWindow,
+    InitApi,
+    Cleanup,
+    ShouldClose,
+    PoolEvents,
+    WaitEvents,
+    DrawFrame,
+    WaitDeviceIdle,
+}
+
+// Shared with whoever created the backend, so the calls can be inspected once Graphics owns it.
+pub type CallLog = Rc<RefCell<Vec<Call>>>;
+
+// Renders nothing and records every call; lets the app's control flow run without GLFW or a GPU.
+pub struct NullApi {
+    settings: GraphicsSettings,
+    calls: CallLog,
+    frames_drawn: Cell<u32>,
+}
+
+impl NullApi {
+    pub fn new(settings: GraphicsSettings) -> Self {
+        Self {
+            settings,
+            calls: CallLog::default(),
+            frames_drawn: Cell::new(0),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn calls(&self) -> CallLog {
+        Rc::clone(&self.calls)
+    }
+
+    fn _record(&self, call: Call) {
+        if debug_mode() {
+            println!("Null {:?}", call);
+        }
+        self.calls.borrow_mut().push(call);
+    }
+}
+
+impl GraphicApi for NullApi {
+    fn init_window(&self) -> Window {
+        self._record(Call::InitWindow);
+
+        Window::Headless
+    }
+
+    fn init_api(&self) -> Result<(), GraphicError> {
+        self._record(Call::InitApi);
+
+        Ok(())
+    }
+
+    fn cleanup(&self) {
+        self._record(Call::Cleanup);
+    }
+
+    // Closes after the frame limit, or after one frame like headless rendering.
+    fn should_close(&self) -> bool {
+        self._record(Call::ShouldClose);
+
+        self.frames_drawn.get() >= self.settings.frame_limit.unwrap_or(1)
+    }
+
+    fn pool_events(&self) {
+        self._record(Call::PoolEvents);
+    }
+
+    fn wait_events(&self) {
+        self._record(Call::WaitEvents);
+    }
+
+    fn draw_frame(&self) -> Result<(), GraphicError> {
+        self._record(Call::DrawFrame);
+        self.frames_drawn.set(self.frames_drawn.get() + 1);
+
+        Ok(())
+    }
+
+    fn wait_device_idle(&self) {
+        self._record(Call::WaitDeviceIdle);
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::load::LoadError;
+use super::math::{cross, normalize, subtract, Vec3};
+use super::mesh::{Material, Mesh, NO_MATERIAL};
+use super::vertex::MeshVertex;
+
+const DEFAULT_COLOR: Vec3 = [1.0, 1.0, 1.0];
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+// A face corner, as zero-based indices into the position, UV and normal lists.
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+}
+
+// Corners without a normal get the face normal, so its bits take part in deduplication.
+#[derive(Hash, PartialEq, Eq)]
+struct VertexKey {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+    face_normal: [u32; 3],
+    material: u32,
+}
+
+// Reads an OBJ file and the MTL libraries it references, relative to its directory.
+pub fn load(path: &str) -> Result<Mesh, LoadError> {
+    let source: String = fs::read_to_string(path).map_err(|source| LoadError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    let directory: &Path = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let mut mesh: Mesh = parse_obj(path, &source, |name| {
+        fs::read_to_string(directory.join(name)).map_err(|error| error.to_string())
+    })
+    .map_err(|error| LoadError::invalid(path, error))?;
+
+    for material in mesh.materials.iter_mut() {
+        if let Some(texture) = &material.diffuse_texture {
+            material.diffuse_texture = Some(directory.join(texture).to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(mesh)
+}
+
+// Builds one vertex per unique position/UV/normal/material combination.
+// `read_mtl` returns the contents of a material library named by `mtllib`.
+pub fn parse_obj(
+    file: &str,
+    source: &str,
+    mut read_mtl: impl FnMut(&str) -> Result<String, String>,
+) -> Result<Mesh, ParseError> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+
+    let mut mesh: Mesh = Mesh {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+        face_materials: Vec::new(),
+        materials: Vec::new(),
+    };
+    let mut unique_vertices: HashMap<VertexKey, u32> = HashMap::new();
+    let mut current_material: u32 = NO_MATERIAL;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number: usize = line_index + 1;
+        let error = |message: String| ParseError {
+            file: file.to_string(),
+            line: line_number,
+            message,
+        };
+
+        let mut tokens = strip_comment(line).split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parse_floats::<3>(keyword, &arguments, 3).map_err(error)?),
+            "vn" => normals.push(parse_floats::<3>(keyword, &arguments, 3).map_err(error)?),
+            "vt" => {
+                // OBJ puts V = 0 at the bottom of the image, Vulkan samples from the top.
+                let [u, v] = parse_floats::<2>(keyword, &arguments, 1).map_err(error)?;
+                tex_coords.push([u, 1.0 - v]);
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, found {}",
+                        arguments.len()
+                    )));
+                }
+                let corners: Vec<FaceVertex> = arguments
+                    .iter()
+                    .map(|argument| {
+                        parse_face_vertex(
+                            argument,
+                            positions.len(),
+                            tex_coords.len(),
+                            normals.len(),
+                        )
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(error)?;
+
+                // Polygons are split into a fan around their first corner.
+                for i in 1..corners.len() - 1 {
+                    let triangle: [FaceVertex; 3] = [corners[0], corners[i], corners[i + 1]];
+                    let face_normal: Vec3 = normalize(cross(
+                        subtract(
+                            positions[triangle[1].position],
+                            positions[triangle[0].position],
+                        ),
+                        subtract(
+                            positions[triangle[2].position],
+                            positions[triangle[0].position],
+                        ),
+                    ));
+                    let color: Vec3 = match mesh.materials.get(current_material as usize) {
+                        Some(material) => material.diffuse,
+                        None => DEFAULT_COLOR,
+                    };
+
+                    for corner in triangle {
+                        let key: VertexKey = VertexKey {
+                            position: corner.position,
+                            tex_coord: corner.tex_coord,
+                            normal: corner.normal,
+                            face_normal: match corner.normal {
+                                Some(_) => [0; 3],
+                                None => face_normal.map(f32::to_bits),
+                            },
+                            material: current_material,
+                        };
+                        let index: u32 = *unique_vertices.entry(key).or_insert_with(|| {
+                            mesh.vertices.push(MeshVertex {
+                                position: positions[corner.position],
+                                normal: corner.normal.map_or(face_normal, |n| normals[n]),
+                                color,
+                                tex_coord: corner.tex_coord.map_or([0.0, 0.0], |t| tex_coords[t]),
+                            });
+                            mesh.vertices.len() as u32 - 1
+                        });
+                        mesh.indices.push(index);
+                    }
+                    mesh.face_materials.push(current_material);
+                }
+            }
+            "mtllib" => {
+                if arguments.is_empty() {
+                    return Err(error(String::from("mtllib needs a file name")));
+                }
+                let libraries: Vec<(String, String)> =
+                    read_mtl_libraries(&arguments, &mut read_mtl).map_err(|reason| {
+                        error(format!("can not read material library {}", reason))
+                    })?;
+                for (name, mtl_source) in libraries {
+                    let directory: &Path = Path::new(&name).parent().unwrap_or(Path::new(""));
+                    for mut material in parse_mtl(&name, &mtl_source)? {
+                        material.diffuse_texture = material
+                            .diffuse_texture
+                            .map(|texture| directory.join(texture).to_string_lossy().into_owned());
+                        mesh.materials.push(material);
+                    }
+                }
+            }
+            "usemtl" => {
+                let name: String = arguments.join(" ");
+                current_material = mesh
+                    .materials
+                    .iter()
+                    .position(|material| material.name == name)
+                    .ok_or_else(|| error(format!("unknown material '{}'", name)))?
+                    as u32;
+            }
+            // Groups, objects, smoothing groups, lines and points do not affect the mesh.
+            _ => {}
+        }
+    }
+
+    Ok(mesh)
+}
+
+// Each name after `mtllib` is a library of its own. File names may contain spaces though, so when
+// the separate files can not be read the rest of the line is tried as one name.
+fn read_mtl_libraries(
+    names: &[&str],
+    read_mtl: &mut impl FnMut(&str) -> Result<String, String>,
+) -> Result<Vec<(String, String)>, String> {
+    let mut libraries: Vec<(String, String)> = Vec::new();
+    for name in names {
+        match read_mtl(name) {
+            Ok(source) => libraries.push((name.to_string(), source)),
+            Err(reason) => {
+                let joined_name: String = names.join(" ");
+                if names.len() > 1 {
+                    if let Ok(source) = read_mtl(&joined_name) {
+                        return Ok(vec![(joined_name, source)]);
+                    }
+                }
+                return Err(format!("{}: {}", name, reason));
+            }
+        }
+    }
+
+    Ok(libraries)
+}
+
+pub fn parse_mtl(file: &str, source: &str) -> Result<Vec<Material>, ParseError> {
+    let mut materials: Vec<Material> = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number: usize = line_index + 1;
+        let error = |message: String| ParseError {
+            file: file.to_string(),
+            line: line_number,
+            message,
+        };
+
+        let mut tokens = strip_comment(line).split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err(error(String::from("newmtl needs a material name")));
+            }
+            materials.push(Material {
+                name: arguments.join(" "),
+                diffuse: DEFAULT_COLOR,
+                diffuse_texture: None,
+            });
+            continue;
+        }
+
+        // Shading parameters the renderer does not use yet are skipped.
+        if keyword != "Kd" && keyword != "map_Kd" {
+            continue;
+        }
+        let material: &mut Material = materials
+            .last_mut()
+            .ok_or_else(|| error(format!("{} before any newmtl", keyword)))?;
+        if keyword == "Kd" {
+            material.diffuse = parse_floats::<3>(keyword, &arguments, 3).map_err(error)?;
+        } else {
+            // Texture options come before the file name, which is always last.
+            let texture: &str = arguments
+                .last()
+                .ok_or_else(|| error(String::from("map_Kd needs a file name")))?;
+            material.diffuse_texture = Some(texture.to_string());
+        }
+    }
+
+    Ok(materials)
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+// Reads up to N numbers, at least `required` of them; missing trailing ones are 0.
+fn parse_floats<const N: usize>(
+    keyword: &str,
+    arguments: &[&str],
+    required: usize,
+) -> Result<[f32; N], String> {
+    if arguments.len() < required {
+        return Err(format!(
+            "{} needs at least {} numbers, found {}",
+            keyword,
+            required,
+            arguments.len()
+        ));
+    }
+
+    let mut values: [f32; N] = [0.0; N];
+    for (value, argument) in values.iter_mut().zip(arguments) {
+        *value = argument
+            .parse()
+            .map_err(|_| format!("invalid number '{}' in {}", argument, keyword))?;
+    }
+
+    Ok(values)
+}
+
+// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`, where negative indices count back from the end.
+fn parse_face_vertex(
+    argument: &str,
+    position_count: usize,
+    tex_coord_count: usize,
+    normal_count: usize,
+) -> Result<FaceVertex, String> {
+    let mut parts = argument.split('/');
+    let position: &str = parts.next().unwrap_or("");
+    let tex_coord: &str = parts.next().unwrap_or("");
+    let normal: &str = parts.next().unwrap_or("");
+    if parts.next().is_some() {
+        return Err(format!("invalid face vertex '{}'", argument));
+    }
+
+    Ok(FaceVertex {
+        position: resolve_index(position, position_count, "position")?,
+        tex_coord: match tex_coord {
+            "" => None,
+            index => Some(resolve_index(index, tex_coord_count, "texture coordinate")?),
+        },
+        normal: match normal {
+            "" => None,
+            index => Some(resolve_index(index, normal_count, "normal")?),
+        },
+    })
+}
+
+fn resolve_index(index: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let value: i64 = index
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", kind, index))?;
+    let resolved: i64 = if value < 0 {
+        count as i64 + value
+    } else {
+        value - 1
+    };
+    if value == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "{} index {} is out of range ({} defined so far)",
+            kind, value, count
+        ));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_mtl(name: &str) -> Result<String, String> {
+        Err(format!("{} not found", name))
+    }
+
+    const QUAD: &str = "\
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+    #[test]
+    fn shared_corners_are_deduplicated() {
+        let mesh: Mesh = parse_obj("quad.obj", QUAD, no_mtl).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.face_materials, vec![NO_MATERIAL, NO_MATERIAL]);
+        assert_eq!(mesh.vertices[2].normal, [0.0, 0.0, 1.0]);
+        // V is flipped for Vulkan.
+        assert_eq!(mesh.vertices[3].tex_coord, [0.0, 0.0]);
+        assert_eq!(mesh.vertices[0].tex_coord, [0.0, 1.0]);
+    }
+
+    #[test]
+    fn negative_indices_and_missing_normals() {
+        let source: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n";
+        let mesh: Mesh = parse_obj("tri.obj", source, no_mtl).unwrap();
+
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        // Without normals the face normal is used.
+        assert_eq!(mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn materials_are_assigned_per_face() {
+        let source: &str = "\
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vn 0 0 1
+f 1//1 2//1 3//1
+usemtl red
+f 1//1 2//1 3//1
+";
+        let mtl: &str = "newmtl red\nKd 1 0 0\nmap_Kd -bm 1 red.png\n";
+        let mesh: Mesh = parse_obj("scene.obj", source, |_| Ok(mtl.to_string())).unwrap();
+
+        assert_eq!(mesh.face_materials, vec![NO_MATERIAL, 0]);
+        assert_eq!(
+            mesh.materials[0].diffuse_texture.as_deref(),
+            Some("red.png")
+        );
+        // The same corners with another material are separate vertices.
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(mesh.vertices[3].color, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn mtllib_reads_every_library() {
+        let source: &str = "\
+mtllib red.mtl textures/blue.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+usemtl blue
+f 1 2 3
+";
+        let mut read: Vec<String> = Vec::new();
+        let mesh: Mesh = parse_obj("scene.obj", source, |name| {
+            read.push(name.to_string());
+            match name {
+                "red.mtl" => Ok(String::from("newmtl red\nKd 1 0 0\n")),
+                "textures/blue.mtl" => Ok(String::from("newmtl blue\nmap_Kd blue.png\n")),
+                _ => no_mtl(name),
+            }
+        })
+        .unwrap();
+        assert_eq!(read, ["red.mtl", "textures/blue.mtl"]);
+        assert_eq!(mesh.face_materials, vec![1]);
+        assert_eq!(
+            mesh.materials[1].diffuse_texture.as_deref(),
+            Some("textures/blue.png")
+        );
+
+        // Names that only exist together are one file name with spaces.
+        let mesh: Mesh = parse_obj("scene.obj", "mtllib my scene.mtl\n", |name| match name {
+            "my scene.mtl" => Ok(String::from("newmtl red\n")),
+            _ => no_mtl(name),
+        })
+        .unwrap();
+        assert_eq!(mesh.materials[0].name, "red");
+
+        let error: ParseError = parse_obj("scene.obj", "mtllib red.mtl blue.mtl\n", no_mtl)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "scene.obj:1: can not read material library red.mtl: red.mtl not found"
+        );
+    }
+
+    #[test]
+    fn errors_report_the_line_number() {
+        let error: ParseError = parse_obj("bad.obj", "v 0 0 0\n\nv 1 x 0\n", no_mtl)
+            .err()
+            .unwrap();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.to_string(), "bad.obj:3: invalid number 'x' in v");
+
+        let error: ParseError = parse_obj("bad.obj", "v 0 0 0\nf 1 2 3\n", no_mtl)
+            .err()
+            .unwrap();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("position index 2 is out of range"));
+
+        let error: ParseError = parse_obj("bad.obj", "# empty\nusemtl missing\n", no_mtl)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "bad.obj:2: unknown material 'missing'");
+    }
+
+    #[test]
+    fn mtl_errors_point_into_the_library() {
+        let error: ParseError = parse_obj("scene.obj", "mtllib scene.mtl\n", |_| {
+            Ok(String::from("Kd 1 1 1\n"))
+        })
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "scene.mtl:1: Kd before any newmtl");
+    }
+}
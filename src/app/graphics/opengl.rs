@@ -1,39 +1,444 @@
-use std::cell::OnceCell;
+use std::cell::{Cell, OnceCell};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::mem::size_of;
+use std::time::Instant;
 
+use crate::gl::{
+    gl_attach_shader, gl_bind_buffer_base, gl_bind_texture_unit, gl_bind_vertex_array, gl_clear,
+    gl_clear_color, gl_clear_depth, gl_clip_control, gl_compile_shader, gl_create_buffers,
+    gl_create_program, gl_create_shader, gl_create_textures, gl_create_vertex_arrays,
+    gl_delete_buffers, gl_delete_program, gl_delete_shader, gl_delete_textures,
+    gl_delete_vertex_arrays, gl_depth_func, gl_draw_elements, gl_enable,
+    gl_enable_vertex_array_attrib, gl_finish, gl_generate_texture_mipmap, gl_get_program_info_log,
+    gl_get_programiv, gl_get_shader_info_log, gl_get_shaderiv, gl_get_string, gl_link_program,
+    gl_load_functions, gl_named_buffer_storage, gl_named_buffer_sub_data, gl_shader_source,
+    gl_texture_parameteri, gl_texture_storage_2d, gl_texture_sub_image_2d, gl_use_program,
+    gl_vertex_array_attrib_binding, gl_vertex_array_attrib_format, gl_vertex_array_element_buffer,
+    gl_vertex_array_vertex_buffer, gl_viewport, GLbitfield, GLboolean, GLchar, GLenum, GLint,
+    GLsizei, GLsizeiptr, GLuint, GlError, GL_COLOR_BUFFER_BIT, GL_COMPILE_STATUS,
+    GL_DEPTH_BUFFER_BIT, GL_DEPTH_TEST, GL_DYNAMIC_STORAGE_BIT, GL_FALSE, GL_FLOAT,
+    GL_FRAGMENT_SHADER, GL_FRAMEBUFFER_SRGB, GL_INFO_LOG_LENGTH, GL_LESS, GL_LINEAR,
+    GL_LINEAR_MIPMAP_LINEAR, GL_LINK_STATUS, GL_RENDERER, GL_REPEAT, GL_RGBA, GL_SRGB8_ALPHA8,
+    GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_TEXTURE_MIN_FILTER, GL_TEXTURE_WRAP_S,
+    GL_TEXTURE_WRAP_T, GL_TRIANGLES, GL_UNIFORM_BUFFER, GL_UNSIGNED_BYTE, GL_UNSIGNED_INT,
+    GL_UPPER_LEFT, GL_VERSION, GL_VERTEX_SHADER, GL_ZERO_TO_ONE,
+};
 use crate::glfw::{
-    glfw_create_window, glfw_init, glfw_window_hint, GLFWwindow, GLFW_CLIENT_API, GLFW_FALSE,
-    GLFW_NO_API, GLFW_RESIZABLE,
+    glfw_create_window, glfw_destroy_window, glfw_get_framebuffer_size, glfw_get_proc_address,
+    glfw_init, glfw_make_context_current, glfw_poll_events, glfw_swap_buffers, glfw_swap_interval,
+    glfw_terminate, glfw_window_hint, glfw_window_should_close, GLFWwindow, GLFW_CLIENT_API,
+    GLFW_CONTEXT_VERSION_MAJOR, GLFW_CONTEXT_VERSION_MINOR, GLFW_OPENGL_API,
+    GLFW_OPENGL_CORE_PROFILE, GLFW_OPENGL_FORWARD_COMPAT, GLFW_OPENGL_PROFILE, GLFW_RESIZABLE,
+    GLFW_SAMPLES, GLFW_SRGB_CAPABLE, GLFW_TRUE,
 };
+use crate::utils::debug_mode;
+use crate::vulkan::VkFormat;
+
+use super::api::{GraphicApi, GraphicError, GraphicsSettings, Window};
+use super::math::{self, Mat4};
+use super::mesh::{self, Mesh};
+use super::shader::{FRAGMENT_SHADER_PATH, VERTEX_SHADER_PATH};
+use super::texture::{ImageData, TEXTURE_PATH};
+use super::vertex::{MeshVertex, Vertex};
+
+use VkFormat::VK_FORMAT_R32G32B32_SFLOAT;
+use VkFormat::VK_FORMAT_R32G32_SFLOAT;
+
+// Built into the binary so the backend runs from any directory; the paths only name the shaders
+// in compile errors.
+const VERTEX_SHADER_SOURCE: &str = include_str!("../../shaders/shader.vert");
+const FRAGMENT_SHADER_SOURCE: &str = include_str!("../../shaders/shader.frag");
+
+// Bindings declared in the shaders.
+const UNIFORM_BUFFER_BINDING: GLuint = 0;
+const TEXTURE_BINDING: GLuint = 1;
+
+// Three mat4 have the same layout under std140 and repr(C).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UniformBufferObject {
+    model: Mat4,
+    view: Mat4,
+    proj: Mat4,
+}
+
+// Reads the info log of a shader or program, whose query functions have the same shape.
+fn info_log(
+    object: GLuint,
+    get_parameter: fn(GLuint, GLenum, *mut GLint),
+    get_info_log: fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar),
+) -> String {
+    let mut length: GLint = 0;
+    get_parameter(object, GL_INFO_LOG_LENGTH, &mut length);
+
+    let mut log: Vec<u8> = vec![0; length.max(1) as usize];
+    let mut written: GLsizei = 0;
+    get_info_log(
+        object,
+        log.len() as GLsizei,
+        &mut written,
+        log.as_mut_ptr() as *mut GLchar,
+    );
+    log.truncate(written.max(0) as usize);
+
+    String::from_utf8_lossy(&log).trim_end().to_string()
+}
+
+fn gl_string(name: GLenum) -> String {
+    let value: *const u8 = gl_get_string(name);
+    if value.is_null() {
+        return String::from("unknown");
+    }
 
-use super::api::{GraphicApi, Window};
+    unsafe { CStr::from_ptr(value as *const c_char) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn create_buffer<T: Copy>(data: &[T], flags: GLbitfield) -> GLuint {
+    let mut buffer: GLuint = 0;
+    gl_create_buffers(1, &mut buffer);
+    gl_named_buffer_storage(
+        buffer,
+        std::mem::size_of_val(data) as GLsizeiptr,
+        data.as_ptr() as *const c_void,
+        flags,
+    );
+
+    buffer
+}
 
 pub struct OpenGLApi {
     width: usize,
     height: usize,
+    settings: GraphicsSettings,
     window: OnceCell<*mut GLFWwindow>,
+    program: OnceCell<GLuint>,
+    model: OnceCell<Mesh>,
+    vertex_buffer: OnceCell<GLuint>,
+    index_buffer: OnceCell<GLuint>,
+    vertex_array: OnceCell<GLuint>,
+    uniform_buffer: OnceCell<GLuint>,
+    texture: OnceCell<GLuint>,
+    frames_rendered: Cell<u32>,
+    start_time: Instant,
 }
 
 impl OpenGLApi {
-    pub fn new(width: usize, height: usize) -> Self {
+    fn _get_window(&self) -> *mut GLFWwindow {
+        *self.window.get().expect("Window is null")
+    }
+
+    fn _get_program(&self) -> GLuint {
+        *self.program.get().expect("Program is null")
+    }
+
+    fn _get_model(&self) -> &Mesh {
+        self.model.get().expect("Model is null")
+    }
+
+    fn _get_vertex_array(&self) -> GLuint {
+        *self.vertex_array.get().expect("Vertex array is null")
+    }
+
+    fn _get_uniform_buffer(&self) -> GLuint {
+        *self.uniform_buffer.get().expect("Uniform buffer is null")
+    }
+}
+
+impl OpenGLApi {
+    pub fn new(width: usize, height: usize, settings: GraphicsSettings) -> Self {
         Self {
             width,
             height,
+            settings,
             window: OnceCell::new(),
+            program: OnceCell::new(),
+            model: OnceCell::new(),
+            vertex_buffer: OnceCell::new(),
+            index_buffer: OnceCell::new(),
+            vertex_array: OnceCell::new(),
+            uniform_buffer: OnceCell::new(),
+            texture: OnceCell::new(),
+            frames_rendered: Cell::new(0),
+            start_time: Instant::now(),
+        }
+    }
+
+    fn _load_functions(&self) -> Result<(), GlError> {
+        gl_load_functions(&glfw_get_proc_address)?;
+        if debug_mode() {
+            println!(
+                "OpenGL {} on {}",
+                gl_string(GL_VERSION),
+                gl_string(GL_RENDERER)
+            );
+        }
+
+        Ok(())
+    }
+    fn _compile_shader(
+        &self,
+        path: &str,
+        source: &str,
+        shader_type: GLenum,
+    ) -> Result<GLuint, GlError> {
+        let source: CString = CString::new(source)
+            .map_err(|_| GlError::setup("read shader", &format!("{}: contains a NUL", path)))?;
+
+        let shader: GLuint = gl_create_shader(shader_type);
+        gl_shader_source(shader, 1, &source.as_ptr(), std::ptr::null());
+        gl_compile_shader(shader);
+
+        let mut status: GLint = 0;
+        gl_get_shaderiv(shader, GL_COMPILE_STATUS, &mut status);
+        if status == 0 {
+            let log: String = info_log(shader, gl_get_shaderiv, gl_get_shader_info_log);
+            gl_delete_shader(shader);
+            return Err(GlError::Compile {
+                path: path.to_string(),
+                log,
+            });
+        }
+
+        Ok(shader)
+    }
+    fn _create_program(&self) -> Result<(), GlError> {
+        let vertex_shader: GLuint =
+            self._compile_shader(VERTEX_SHADER_PATH, VERTEX_SHADER_SOURCE, GL_VERTEX_SHADER)?;
+        let fragment_shader: GLuint = match self._compile_shader(
+            FRAGMENT_SHADER_PATH,
+            FRAGMENT_SHADER_SOURCE,
+            GL_FRAGMENT_SHADER,
+        ) {
+            Ok(fragment_shader) => fragment_shader,
+            Err(error) => {
+                gl_delete_shader(vertex_shader);
+                return Err(error);
+            }
+        };
+
+        let program: GLuint = gl_create_program();
+        gl_attach_shader(program, vertex_shader);
+        gl_attach_shader(program, fragment_shader);
+        gl_link_program(program);
+        // The program keeps what it needs; the shaders are freed once it is deleted.
+        gl_delete_shader(vertex_shader);
+        gl_delete_shader(fragment_shader);
+
+        let mut status: GLint = 0;
+        gl_get_programiv(program, GL_LINK_STATUS, &mut status);
+        if status == 0 {
+            let log: String = info_log(program, gl_get_programiv, gl_get_program_info_log);
+            gl_delete_program(program);
+            return Err(GlError::Link { log });
+        }
+
+        self.program
+            .set(program)
+            .expect("Program can not be initialized!");
+        if debug_mode() {
+            println!("OpenGL shader program created");
+        }
+
+        Ok(())
+    }
+    fn _load_model(&self) -> Result<(), GlError> {
+        let model: Mesh = mesh::load(&self.settings.model_path)?;
+        self.model
+            .set(model)
+            .expect("Model can not be initialized!");
+
+        Ok(())
+    }
+    // Same vertex layout as the Vulkan pipeline, read from the same descriptions.
+    fn _create_vertex_array(&self) -> Result<(), GlError> {
+        let vertex_buffer: GLuint = create_buffer(&self._get_model().vertices, 0);
+        let index_buffer: GLuint = create_buffer(&self._get_model().indices, 0);
+        self.vertex_buffer
+            .set(vertex_buffer)
+            .expect("Vertex buffer can not be initialized!");
+        self.index_buffer
+            .set(index_buffer)
+            .expect("Index buffer can not be initialized!");
+
+        let mut vertex_array: GLuint = 0;
+        gl_create_vertex_arrays(1, &mut vertex_array);
+        self.vertex_array
+            .set(vertex_array)
+            .expect("Vertex array can not be initialized!");
+
+        let binding = MeshVertex::binding_description();
+        gl_vertex_array_vertex_buffer(
+            vertex_array,
+            binding.binding,
+            vertex_buffer,
+            0,
+            binding.stride as GLsizei,
+        );
+        gl_vertex_array_element_buffer(vertex_array, index_buffer);
+
+        for attribute in MeshVertex::attribute_descriptions() {
+            let components: GLint = match attribute.format {
+                VK_FORMAT_R32G32_SFLOAT => 2,
+                VK_FORMAT_R32G32B32_SFLOAT => 3,
+                format => {
+                    return Err(GlError::setup(
+                        "create vertex array",
+                        &format!("unsupported attribute format {:?}", format),
+                    ));
+                }
+            };
+            gl_enable_vertex_array_attrib(vertex_array, attribute.location);
+            gl_vertex_array_attrib_format(
+                vertex_array,
+                attribute.location,
+                components,
+                GL_FLOAT,
+                GL_FALSE as GLboolean,
+                attribute.offset,
+            );
+            gl_vertex_array_attrib_binding(vertex_array, attribute.location, attribute.binding);
+        }
+
+        Ok(())
+    }
+    fn _create_uniform_buffer(&self) -> Result<(), GlError> {
+        let mut uniform_buffer: GLuint = 0;
+        gl_create_buffers(1, &mut uniform_buffer);
+        gl_named_buffer_storage(
+            uniform_buffer,
+            size_of::<UniformBufferObject>() as GLsizeiptr,
+            std::ptr::null(),
+            GL_DYNAMIC_STORAGE_BIT,
+        );
+        gl_bind_buffer_base(GL_UNIFORM_BUFFER, UNIFORM_BUFFER_BINDING, uniform_buffer);
+
+        self.uniform_buffer
+            .set(uniform_buffer)
+            .expect("Uniform buffer can not be initialized!");
+
+        Ok(())
+    }
+    // Picks the texture the same way as the Vulkan backend, without its KTX2 option.
+    fn _create_texture(&self) -> Result<(), GlError> {
+        let path: String = self
+            ._get_model()
+            .materials
+            .iter()
+            .find_map(|material| material.diffuse_texture.clone())
+            .unwrap_or_else(|| String::from(TEXTURE_PATH));
+        let image: ImageData = ImageData::load(&path)?;
+
+        let mut texture: GLuint = 0;
+        gl_create_textures(GL_TEXTURE_2D, 1, &mut texture);
+        self.texture
+            .set(texture)
+            .expect("Texture can not be initialized!");
+
+        gl_texture_storage_2d(
+            texture,
+            image.mip_level_count() as GLsizei,
+            GL_SRGB8_ALPHA8,
+            image.width as GLsizei,
+            image.height as GLsizei,
+        );
+        gl_texture_sub_image_2d(
+            texture,
+            0,
+            0,
+            0,
+            image.width as GLsizei,
+            image.height as GLsizei,
+            GL_RGBA,
+            GL_UNSIGNED_BYTE,
+            image.pixels.as_ptr() as *const c_void,
+        );
+        gl_generate_texture_mipmap(texture);
+        gl_texture_parameteri(
+            texture,
+            GL_TEXTURE_MIN_FILTER,
+            GL_LINEAR_MIPMAP_LINEAR as GLint,
+        );
+        gl_texture_parameteri(texture, GL_TEXTURE_MAG_FILTER, GL_LINEAR as GLint);
+        gl_texture_parameteri(texture, GL_TEXTURE_WRAP_S, GL_REPEAT as GLint);
+        gl_texture_parameteri(texture, GL_TEXTURE_WRAP_T, GL_REPEAT as GLint);
+        gl_bind_texture_unit(TEXTURE_BINDING, texture);
+
+        if debug_mode() {
+            println!("OpenGL texture {} loaded", path);
         }
+
+        Ok(())
+    }
+    fn _setup_render_state(&self) {
+        // Use the Vulkan conventions, y down in clip space and depth from 0 to 1, so the same
+        // matrices give the same image. Faces are not culled: facing is decided differently
+        // once the origin is flipped, and the depth test hides back faces of closed models.
+        gl_clip_control(GL_UPPER_LEFT, GL_ZERO_TO_ONE);
+        gl_enable(GL_DEPTH_TEST);
+        gl_depth_func(GL_LESS);
+        gl_clear_depth(1.0);
+        gl_clear_color(0.0, 0.0, 0.0, 1.0);
+        // The window was asked for an sRGB framebuffer, like the Vulkan swapchain format.
+        gl_enable(GL_FRAMEBUFFER_SRGB);
+
+        gl_use_program(self._get_program());
+        gl_bind_vertex_array(self._get_vertex_array());
+        // Swap on every vertical blank with vsync, as soon as the frame is done without.
+        glfw_swap_interval(if self.settings.vsync { 1 } else { 0 });
+    }
+    fn _update_uniform_buffer(&self, aspect: f32) {
+        let time: f32 = self.start_time.elapsed().as_secs_f32();
+
+        let ubo: UniformBufferObject = UniformBufferObject {
+            model: math::rotation(time * 90.0_f32.to_radians(), [0.0, 0.0, 1.0]),
+            view: math::look_at([2.0, 2.0, 2.0], [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            proj: math::perspective(45.0_f32.to_radians(), aspect, 0.1, 10.0),
+        };
+
+        gl_named_buffer_sub_data(
+            self._get_uniform_buffer(),
+            0,
+            size_of::<UniformBufferObject>() as GLsizeiptr,
+            &ubo as *const UniformBufferObject as *const c_void,
+        );
     }
 }
 
 impl GraphicApi for OpenGLApi {
     fn init_window(&self) -> Window {
-        println!(
-            "Vulkan window initialized with width: {} and height: {}",
-            self.width, self.height
-        );
+        if debug_mode() {
+            println!(
+                "OpenGL window initialized with width: {} and height: {}",
+                self.width, self.height
+            );
+        }
+
+        // There is no offscreen rendering, init_api reports it instead of opening a window.
+        if self.settings.headless {
+            return Window::Headless;
+        }
 
         glfw_init();
 
-        glfw_window_hint(GLFW_CLIENT_API as isize, GLFW_NO_API as isize);
-        glfw_window_hint(GLFW_RESIZABLE as isize, GLFW_FALSE as isize);
+        glfw_window_hint(GLFW_CLIENT_API as isize, GLFW_OPENGL_API as isize);
+        glfw_window_hint(GLFW_CONTEXT_VERSION_MAJOR as isize, 4);
+        glfw_window_hint(GLFW_CONTEXT_VERSION_MINOR as isize, 5);
+        glfw_window_hint(
+            GLFW_OPENGL_PROFILE as isize,
+            GLFW_OPENGL_CORE_PROFILE as isize,
+        );
+        glfw_window_hint(GLFW_OPENGL_FORWARD_COMPAT as isize, GLFW_TRUE as isize);
+        glfw_window_hint(GLFW_RESIZABLE as isize, GLFW_TRUE as isize);
+        glfw_window_hint(GLFW_SRGB_CAPABLE as isize, GLFW_TRUE as isize);
+        // 0 asks for a single-sampled framebuffer.
+        let samples: u32 = if self.settings.msaa_samples > 1 {
+            self.settings.msaa_samples
+        } else {
+            0
+        };
+        glfw_window_hint(GLFW_SAMPLES as isize, samples as isize);
 
         let window = glfw_create_window(
             self.width as i32,
@@ -42,6 +447,9 @@ impl GraphicApi for OpenGLApi {
             std::ptr::null_mut(),
             std::ptr::null_mut(),
         );
+        if !window.is_null() {
+            glfw_make_context_current(window);
+        }
 
         self.window
             .set(window)
@@ -50,31 +458,105 @@ impl GraphicApi for OpenGLApi {
         Window::OpenGL(window)
     }
 
-    fn init_api(&self) {
-        println!("OpenGL API initialized");
+    fn init_api(&self) -> Result<(), GraphicError> {
+        if debug_mode() {
+            println!("OpenGL API initialized");
+        }
+        if self.settings.headless {
+            return Err(GlError::setup(
+                "initialize OpenGL",
+                "headless rendering is only supported by the Vulkan backend",
+            )
+            .into());
+        }
+        self._load_functions()?;
+        self._create_program()?;
+        self._load_model()?;
+        self._create_vertex_array()?;
+        self._create_uniform_buffer()?;
+        self._create_texture()?;
+        self._setup_render_state();
+
+        Ok(())
     }
 
     fn cleanup(&self) {
-        println!("OpenGL cleanup");
+        if debug_mode() {
+            println!("OpenGL cleanup");
+        }
+
+        // init_api may have stopped half way, so only delete what was created.
+        if let Some(texture) = self.texture.get() {
+            gl_delete_textures(1, texture);
+        }
+        if let Some(uniform_buffer) = self.uniform_buffer.get() {
+            gl_delete_buffers(1, uniform_buffer);
+        }
+        if let Some(vertex_array) = self.vertex_array.get() {
+            gl_delete_vertex_arrays(1, vertex_array);
+        }
+        if let Some(index_buffer) = self.index_buffer.get() {
+            gl_delete_buffers(1, index_buffer);
+        }
+        if let Some(vertex_buffer) = self.vertex_buffer.get() {
+            gl_delete_buffers(1, vertex_buffer);
+        }
+        if let Some(program) = self.program.get() {
+            gl_delete_program(*program);
+        }
+
+        if let Some(window) = self.window.get() {
+            glfw_destroy_window(*window);
+        }
+        glfw_terminate();
     }
 
     fn should_close(&self) -> bool {
-        true
+        if let Some(frame_limit) = self.settings.frame_limit {
+            if self.frames_rendered.get() >= frame_limit {
+                return true;
+            }
+        }
+
+        let should_close: i32 = glfw_window_should_close(self._get_window());
+
+        should_close != 0
     }
 
     fn pool_events(&self) {
-        println!("OpenGL pool events");
+        glfw_poll_events();
     }
 
-    fn wait_events(&self) {
-        println!("OpenGL wait events");
-    }
+    // The driver synchronises buffer swaps itself, there is no frame fence to wait on.
+    fn wait_events(&self) {}
+
+    fn draw_frame(&self) -> Result<(), GraphicError> {
+        let mut width: c_int = 0;
+        let mut height: c_int = 0;
+        glfw_get_framebuffer_size(self._get_window(), &mut width, &mut height);
+        // A minimized window has nothing to draw into.
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        gl_viewport(0, 0, width, height);
+        self._update_uniform_buffer(width as f32 / height as f32);
+
+        gl_clear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
+        gl_draw_elements(
+            GL_TRIANGLES,
+            self._get_model().indices.len() as GLsizei,
+            GL_UNSIGNED_INT,
+            std::ptr::null(),
+        );
+        glfw_swap_buffers(self._get_window());
+
+        self.frames_rendered.set(self.frames_rendered.get() + 1);
 
-    fn draw_frame(&self) {
-        println!("OpenGL draw frame");
+        Ok(())
     }
 
     fn wait_device_idle(&self) {
-        println!("OpenGL wait device idle");
+        gl_finish();
     }
 }
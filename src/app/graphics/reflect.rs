@@ -0,0 +1,376 @@
+use crate::vulkan::{
+    VkDescriptorPoolSize, VkDescriptorSetLayoutBinding, VkDescriptorType, VkFormat,
+    VkPushConstantRange, VkShaderStageFlagBits, VkVertexInputAttributeDescription,
+};
+
+use super::shader::ShaderStage;
+use super::spirv::{self, DescriptorBinding, DescriptorType, ShaderReflection, Type};
+use super::vertex::Vertex;
+
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_INPUT_ATTACHMENT;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_SAMPLED_IMAGE;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_SAMPLER;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_IMAGE;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_TEXEL_BUFFER;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER;
+use VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_TEXEL_BUFFER;
+use VkFormat::VK_FORMAT_R32G32B32A32_SFLOAT;
+use VkFormat::VK_FORMAT_R32G32B32A32_SINT;
+use VkFormat::VK_FORMAT_R32G32B32A32_UINT;
+use VkFormat::VK_FORMAT_R32G32B32_SFLOAT;
+use VkFormat::VK_FORMAT_R32G32B32_SINT;
+use VkFormat::VK_FORMAT_R32G32B32_UINT;
+use VkFormat::VK_FORMAT_R32G32_SFLOAT;
+use VkFormat::VK_FORMAT_R32G32_SINT;
+use VkFormat::VK_FORMAT_R32G32_UINT;
+use VkFormat::VK_FORMAT_R32_SFLOAT;
+use VkFormat::VK_FORMAT_R32_SINT;
+use VkFormat::VK_FORMAT_R32_UINT;
+use VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT;
+use VkShaderStageFlagBits::VK_SHADER_STAGE_FRAGMENT_BIT;
+use VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT;
+
+// A descriptor binding with every stage that uses it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutBinding {
+    pub binding: DescriptorBinding,
+    pub stage_flags: u32,
+}
+
+// The compiled shaders of a graphics pipeline. The descriptor set layout, pipeline layout and
+// vertex input state are derived from their reflection, so they can not drift from the shaders.
+#[derive(Debug)]
+pub struct PipelineShaders {
+    pub vertex_code: Vec<u32>,
+    pub fragment_code: Vec<u32>,
+    pub vertex: ShaderReflection,
+    pub fragment: ShaderReflection,
+    pub bindings: Vec<LayoutBinding>,
+}
+
+impl PipelineShaders {
+    pub fn new(
+        vertex_code: Vec<u32>,
+        fragment_code: Vec<u32>,
+        entry_point: &str,
+    ) -> Result<Self, String> {
+        let vertex: ShaderReflection =
+            reflect_stage(&vertex_code, entry_point, ShaderStage::Vertex)?;
+        let fragment: ShaderReflection =
+            reflect_stage(&fragment_code, entry_point, ShaderStage::Fragment)?;
+        spirv::check_interface(&vertex, &fragment).map_err(|problems| problems.join("\n"))?;
+        let bindings: Vec<LayoutBinding> = merge_bindings(&[&vertex, &fragment])?;
+
+        Ok(Self {
+            vertex_code,
+            fragment_code,
+            vertex,
+            fragment,
+            bindings,
+        })
+    }
+
+    pub fn set_layout_bindings(&self, set: u32) -> Vec<VkDescriptorSetLayoutBinding> {
+        self.bindings
+            .iter()
+            .filter(|layout_binding| layout_binding.binding.set == set)
+            .map(|layout_binding| VkDescriptorSetLayoutBinding {
+                binding: layout_binding.binding.binding,
+                descriptorType: descriptor_type(layout_binding.binding.descriptor_type),
+                descriptorCount: layout_binding.binding.count,
+                stageFlags: layout_binding.stage_flags,
+                pImmutableSamplers: std::ptr::null(),
+            })
+            .collect()
+    }
+
+    // One range per stage, or a single range when both stages declare the same block.
+    pub fn push_constant_ranges(&self) -> Vec<VkPushConstantRange> {
+        let mut ranges: Vec<VkPushConstantRange> = Vec::new();
+        for stage in [&self.vertex, &self.fragment] {
+            let Some(push_constants) = &stage.push_constants else {
+                continue;
+            };
+            let stage_flag: u32 = stage_flag(stage.entry_point.stage) as u32;
+            match ranges.iter_mut().find(|range| {
+                range.offset == push_constants.offset && range.size == push_constants.size
+            }) {
+                Some(range) => range.stageFlags |= stage_flag,
+                None => ranges.push(VkPushConstantRange {
+                    stageFlags: stage_flag,
+                    offset: push_constants.offset,
+                    size: push_constants.size,
+                }),
+            }
+        }
+
+        ranges
+    }
+
+    // Enough descriptors of every type for `sets` copies of the layout.
+    pub fn descriptor_pool_sizes(&self, sets: u32) -> Vec<VkDescriptorPoolSize> {
+        let mut pool_sizes: Vec<VkDescriptorPoolSize> = Vec::new();
+        for layout_binding in &self.bindings {
+            let type_: VkDescriptorType = descriptor_type(layout_binding.binding.descriptor_type);
+            let count: u32 = layout_binding.binding.count * sets;
+            match pool_sizes
+                .iter_mut()
+                .find(|pool_size| pool_size.type_ == type_)
+            {
+                Some(pool_size) => pool_size.descriptorCount += count,
+                None => pool_sizes.push(VkDescriptorPoolSize {
+                    type_,
+                    descriptorCount: count,
+                }),
+            }
+        }
+
+        pool_sizes
+    }
+
+    // The attributes of `V` the vertex shader reads. Each input needs an attribute at its location
+    // in the 32-bit format of its type; attributes the shader does not read are left out.
+    pub fn vertex_attributes<V: Vertex>(
+        &self,
+    ) -> Result<Vec<VkVertexInputAttributeDescription>, String> {
+        let attributes: Vec<VkVertexInputAttributeDescription> = V::attribute_descriptions();
+        self.vertex
+            .inputs
+            .iter()
+            .map(|input| {
+                let format: VkFormat = vertex_format(&input.ty).ok_or_else(|| {
+                    format!(
+                        "vertex input {} is a {}, which can not be read from a vertex buffer",
+                        input.name, input.ty
+                    )
+                })?;
+                let attribute: &VkVertexInputAttributeDescription = attributes
+                    .iter()
+                    .find(|attribute| attribute.location == input.location)
+                    .ok_or_else(|| {
+                        format!(
+                            "vertex input {} at location {} is not in the vertex layout",
+                            input.name, input.location
+                        )
+                    })?;
+                if attribute.format != format {
+                    return Err(format!(
+                        "vertex input {} at location {} is a {} but the vertex layout has {:?}",
+                        input.name, input.location, input.ty, attribute.format
+                    ));
+                }
+
+                Ok(*attribute)
+            })
+            .collect()
+    }
+
+    // A pipeline built from `other` can use the descriptor sets and pipeline layout made for these.
+    pub fn same_layout(&self, other: &PipelineShaders) -> bool {
+        let push_constants = |shaders: &PipelineShaders| {
+            shaders
+                .push_constant_ranges()
+                .iter()
+                .map(|range| (range.stageFlags, range.offset, range.size))
+                .collect::<Vec<(u32, u32, u32)>>()
+        };
+        let layout = |bindings: &[LayoutBinding]| {
+            bindings
+                .iter()
+                .map(|layout_binding| {
+                    (
+                        layout_binding.binding.set,
+                        layout_binding.binding.binding,
+                        layout_binding.binding.descriptor_type,
+                        layout_binding.binding.count,
+                        layout_binding.stage_flags,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        layout(&self.bindings) == layout(&other.bindings)
+            && push_constants(self) == push_constants(other)
+    }
+}
+
+fn reflect_stage(
+    code: &[u32],
+    entry_point: &str,
+    stage: ShaderStage,
+) -> Result<ShaderReflection, String> {
+    let reflection: ShaderReflection =
+        spirv::reflect(code, entry_point).map_err(|error| error.to_string())?;
+    if reflection.entry_point.stage != stage {
+        return Err(format!(
+            "entry point {} is a {:?} shader, expected a {:?} shader",
+            entry_point, reflection.entry_point.stage, stage
+        ));
+    }
+
+    Ok(reflection)
+}
+
+// Stages may share a binding as long as they agree on what it is.
+fn merge_bindings(stages: &[&ShaderReflection]) -> Result<Vec<LayoutBinding>, String> {
+    let mut bindings: Vec<LayoutBinding> = Vec::new();
+    for stage in stages {
+        let stage_flag: u32 = stage_flag(stage.entry_point.stage) as u32;
+        for binding in &stage.descriptor_bindings {
+            match bindings.iter_mut().find(|layout_binding| {
+                layout_binding.binding.set == binding.set
+                    && layout_binding.binding.binding == binding.binding
+            }) {
+                Some(layout_binding)
+                    if layout_binding.binding.descriptor_type != binding.descriptor_type
+                        || layout_binding.binding.count != binding.count =>
+                {
+                    return Err(format!(
+                        "{} and {} both use set {} binding {} as different descriptors",
+                        layout_binding.binding.name, binding.name, binding.set, binding.binding
+                    ));
+                }
+                Some(layout_binding) => layout_binding.stage_flags |= stage_flag,
+                None => bindings.push(LayoutBinding {
+                    binding: binding.clone(),
+                    stage_flags: stage_flag,
+                }),
+            }
+        }
+    }
+    bindings
+        .sort_by_key(|layout_binding| (layout_binding.binding.set, layout_binding.binding.binding));
+
+    Ok(bindings)
+}
+
+fn stage_flag(stage: ShaderStage) -> VkShaderStageFlagBits {
+    match stage {
+        ShaderStage::Vertex => VK_SHADER_STAGE_VERTEX_BIT,
+        ShaderStage::Fragment => VK_SHADER_STAGE_FRAGMENT_BIT,
+        ShaderStage::Compute => VK_SHADER_STAGE_COMPUTE_BIT,
+    }
+}
+
+pub fn descriptor_type(descriptor_type: DescriptorType) -> VkDescriptorType {
+    match descriptor_type {
+        DescriptorType::Sampler => VK_DESCRIPTOR_TYPE_SAMPLER,
+        DescriptorType::CombinedImageSampler => VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER,
+        DescriptorType::SampledImage => VK_DESCRIPTOR_TYPE_SAMPLED_IMAGE,
+        DescriptorType::StorageImage => VK_DESCRIPTOR_TYPE_STORAGE_IMAGE,
+        DescriptorType::UniformTexelBuffer => VK_DESCRIPTOR_TYPE_UNIFORM_TEXEL_BUFFER,
+        DescriptorType::StorageTexelBuffer => VK_DESCRIPTOR_TYPE_STORAGE_TEXEL_BUFFER,
+        DescriptorType::UniformBuffer => VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+        DescriptorType::StorageBuffer => VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+        DescriptorType::InputAttachment => VK_DESCRIPTOR_TYPE_INPUT_ATTACHMENT,
+    }
+}
+
+fn vertex_format(ty: &Type) -> Option<VkFormat> {
+    let (component, count): (&Type, u32) = match ty {
+        Type::Vector { component, count } => (component, *count),
+        scalar => (scalar, 1),
+    };
+    let formats: [VkFormat; 4] = match component {
+        Type::Float { width: 32 } => [
+            VK_FORMAT_R32_SFLOAT,
+            VK_FORMAT_R32G32_SFLOAT,
+            VK_FORMAT_R32G32B32_SFLOAT,
+            VK_FORMAT_R32G32B32A32_SFLOAT,
+        ],
+        Type::Int {
+            width: 32,
+            signed: true,
+        } => [
+            VK_FORMAT_R32_SINT,
+            VK_FORMAT_R32G32_SINT,
+            VK_FORMAT_R32G32B32_SINT,
+            VK_FORMAT_R32G32B32A32_SINT,
+        ],
+        Type::Int {
+            width: 32,
+            signed: false,
+        } => [
+            VK_FORMAT_R32_UINT,
+            VK_FORMAT_R32G32_UINT,
+            VK_FORMAT_R32G32B32_UINT,
+            VK_FORMAT_R32G32B32A32_UINT,
+        ],
+        _ => return None,
+    };
+
+    formats.get(count.checked_sub(1)? as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::graphics::shader::{
+        ShaderCompiler, ENTRY_POINT, FRAGMENT_SHADER_PATH, VERTEX_SHADER_PATH,
+    };
+    use crate::app::graphics::vertex::MeshVertex;
+
+    fn bundled_shaders() -> PipelineShaders {
+        let compiler: ShaderCompiler = ShaderCompiler::default();
+        PipelineShaders::new(
+            compiler.compile(VERTEX_SHADER_PATH, &[]).unwrap(),
+            compiler.compile(FRAGMENT_SHADER_PATH, &[]).unwrap(),
+            ENTRY_POINT,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn derives_the_layout_of_the_bundled_shaders() {
+        let shaders: PipelineShaders = bundled_shaders();
+
+        let bindings: Vec<(u32, DescriptorType, u32)> = shaders
+            .bindings
+            .iter()
+            .map(|layout_binding| {
+                (
+                    layout_binding.binding.binding,
+                    layout_binding.binding.descriptor_type,
+                    layout_binding.stage_flags,
+                )
+            })
+            .collect();
+        assert_eq!(
+            bindings,
+            [
+                (
+                    0,
+                    DescriptorType::UniformBuffer,
+                    VK_SHADER_STAGE_VERTEX_BIT as u32
+                ),
+                (
+                    1,
+                    DescriptorType::CombinedImageSampler,
+                    VK_SHADER_STAGE_FRAGMENT_BIT as u32
+                ),
+            ]
+        );
+        assert!(shaders.push_constant_ranges().is_empty());
+        assert_eq!(shaders.descriptor_pool_sizes(2).len(), 2);
+        assert!(shaders.same_layout(&bundled_shaders()));
+    }
+
+    #[test]
+    fn matches_vertex_inputs_to_the_vertex_layout() {
+        let shaders: PipelineShaders = bundled_shaders();
+        let attributes: Vec<VkVertexInputAttributeDescription> =
+            shaders.vertex_attributes::<MeshVertex>().unwrap();
+
+        for input in &shaders.vertex.inputs {
+            assert!(attributes
+                .iter()
+                .any(|attribute| attribute.location == input.location));
+        }
+        assert_eq!(
+            vertex_format(&shaders.vertex.inputs[0].ty),
+            Some(VK_FORMAT_R32G32B32_SFLOAT)
+        );
+    }
+}
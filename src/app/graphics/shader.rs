@@ -0,0 +1,465 @@
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::shaderc::{
+    shaderc_compilation_result_t, shaderc_compilation_status, shaderc_compile_into_spv,
+    shaderc_compile_options_add_macro_definition, shaderc_compile_options_initialize,
+    shaderc_compile_options_release, shaderc_compile_options_set_include_callbacks,
+    shaderc_compile_options_set_source_language, shaderc_compile_options_set_target_env,
+    shaderc_compile_options_t, shaderc_compiler_initialize, shaderc_compiler_release,
+    shaderc_compiler_t, shaderc_env_version, shaderc_include_result, shaderc_include_type,
+    shaderc_result_get_bytes, shaderc_result_get_compilation_status,
+    shaderc_result_get_error_message, shaderc_result_release, shaderc_shader_kind,
+    shaderc_source_language, shaderc_target_env,
+};
+use crate::utils::debug_mode;
+
+use super::shader_include::include_source;
+use super::spirv;
+
+use shaderc_compilation_status::shaderc_compilation_status_success;
+use shaderc_env_version::shaderc_env_version_vulkan_1_0;
+use shaderc_include_type::shaderc_include_type_relative;
+use shaderc_shader_kind::shaderc_compute_shader;
+use shaderc_shader_kind::shaderc_fragment_shader;
+use shaderc_shader_kind::shaderc_vertex_shader;
+use shaderc_source_language::shaderc_source_language_glsl;
+use shaderc_source_language::shaderc_source_language_hlsl;
+use shaderc_target_env::shaderc_target_env_vulkan;
+
+// Resolved when the binary is built, so the shaders are found from any working directory.
+pub const SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+pub const VERTEX_SHADER_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/shader.vert");
+pub const FRAGMENT_SHADER_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/shader.frag");
+
+pub const ENTRY_POINT: &str = "main";
+
+// Also the names the compiled shaders are embedded under, see shader_registry.rs.
+pub const VERTEX_SHADER: &str = "shader.vert";
+pub const FRAGMENT_SHADER: &str = "shader.frag";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShaderLanguage {
+    Glsl,
+    Hlsl,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// One message from the compiler, pointing at the file it is about, which may be an include.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // The stage is taken from the extension: .vert, .frag or .comp, followed by .hlsl for HLSL.
+    UnknownStage(String),
+    Compile {
+        path: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity: &str = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}: {}", self.file, line, severity, self.message),
+            None => write!(f, "{}: {}: {}", self.file, severity, self.message),
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            ShaderError::UnknownStage(path) => write!(
+                f,
+                "Can not tell the shader stage of {}, expected .vert, .frag or .comp",
+                path
+            ),
+            ShaderError::Compile { path, diagnostics } => {
+                write!(f, "Failed to compile {}", path)?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShaderError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl ShaderStage {
+    // shader.frag is GLSL; shader.frag.hlsl is HLSL for the same stage.
+    pub fn from_path(path: &Path) -> Option<(ShaderStage, ShaderLanguage)> {
+        let (path, language): (&Path, ShaderLanguage) =
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("hlsl") => (Path::new(path.file_stem()?), ShaderLanguage::Hlsl),
+                _ => (path, ShaderLanguage::Glsl),
+            };
+        let stage: ShaderStage = match path.extension()?.to_str()? {
+            "vert" => ShaderStage::Vertex,
+            "frag" => ShaderStage::Fragment,
+            "comp" => ShaderStage::Compute,
+            _ => return None,
+        };
+
+        Some((stage, language))
+    }
+
+    fn kind(self) -> shaderc_shader_kind {
+        match self {
+            ShaderStage::Vertex => shaderc_vertex_shader,
+            ShaderStage::Fragment => shaderc_fragment_shader,
+            ShaderStage::Compute => shaderc_compute_shader,
+        }
+    }
+}
+
+// Compiles GLSL and HLSL sources to SPIR-V for Vulkan 1.0. `#include "file"` is looked up next to
+// the including file first and then in the include directories, `#include <file>` only in the
+// include directories.
+pub struct ShaderCompiler {
+    compiler: shaderc_compiler_t,
+    include_dirs: Vec<PathBuf>,
+}
+
+impl ShaderCompiler {
+    pub fn new(include_dirs: Vec<PathBuf>) -> Self {
+        Self {
+            compiler: shaderc_compiler_initialize(),
+            include_dirs,
+        }
+    }
+
+    // `defines` are set as if by `#define NAME VALUE` before the first line of the source.
+    pub fn compile(&self, path: &str, defines: &[(&str, &str)]) -> Result<Vec<u32>, ShaderError> {
+        if self.compiler.is_null() {
+            return Err(compile_error(
+                path,
+                "the shader compiler could not be initialized",
+            ));
+        }
+        let (stage, language): (ShaderStage, ShaderLanguage) =
+            ShaderStage::from_path(Path::new(path))
+                .ok_or_else(|| ShaderError::UnknownStage(path.to_string()))?;
+        let source: String = fs::read_to_string(path).map_err(|source| ShaderError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+
+        let options: shaderc_compile_options_t = shaderc_compile_options_initialize();
+        shaderc_compile_options_set_source_language(
+            options,
+            match language {
+                ShaderLanguage::Glsl => shaderc_source_language_glsl,
+                ShaderLanguage::Hlsl => shaderc_source_language_hlsl,
+            },
+        );
+        shaderc_compile_options_set_target_env(
+            options,
+            shaderc_target_env_vulkan,
+            shaderc_env_version_vulkan_1_0,
+        );
+        for (name, value) in defines {
+            shaderc_compile_options_add_macro_definition(options, name, value);
+        }
+        // Only read while shaderc_compile_into_spv runs, so a pointer to the field is enough.
+        shaderc_compile_options_set_include_callbacks(
+            options,
+            Some(resolve_include_callback),
+            Some(release_include_callback),
+            &self.include_dirs as *const Vec<PathBuf> as *mut c_void,
+        );
+
+        let result: shaderc_compilation_result_t = shaderc_compile_into_spv(
+            self.compiler,
+            &source,
+            stage.kind(),
+            path,
+            ENTRY_POINT,
+            options,
+        );
+        shaderc_compile_options_release(options);
+        if result.is_null() {
+            return Err(compile_error(path, "the shader compiler ran out of memory"));
+        }
+
+        let status: shaderc_compilation_status = shaderc_result_get_compilation_status(result);
+        let bytes: Vec<u8> = shaderc_result_get_bytes(result);
+        let message: String = shaderc_result_get_error_message(result);
+        shaderc_result_release(result);
+
+        if status != shaderc_compilation_status_success {
+            return Err(ShaderError::Compile {
+                path: path.to_string(),
+                diagnostics: parse_diagnostics(path, &message),
+            });
+        }
+        let code: Vec<u32> = spirv_words(&bytes)
+            .ok_or_else(|| compile_error(path, "the compiler returned invalid SPIR-V"))?;
+
+        if debug_mode() {
+            for diagnostic in parse_diagnostics(path, &message) {
+                println!("{}", diagnostic);
+            }
+            println!("Shader {} compiled to {} words", path, code.len());
+        }
+
+        Ok(code)
+    }
+}
+
+// Includes are looked up in SHADER_DIR.
+impl Default for ShaderCompiler {
+    fn default() -> Self {
+        Self::new(vec![PathBuf::from(SHADER_DIR)])
+    }
+}
+
+impl Drop for ShaderCompiler {
+    fn drop(&mut self) {
+        if !self.compiler.is_null() {
+            shaderc_compiler_release(self.compiler);
+        }
+    }
+}
+
+fn compile_error(path: &str, message: &str) -> ShaderError {
+    ShaderError::Compile {
+        path: path.to_string(),
+        diagnostics: vec![Diagnostic {
+            file: path.to_string(),
+            line: None,
+            severity: Severity::Error,
+            message: message.to_string(),
+        }],
+    }
+}
+
+fn spirv_words(bytes: &[u8]) -> Option<Vec<u32>> {
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+    let words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    (words.first() == Some(&spirv::MAGIC)).then_some(words)
+}
+
+// shaderc reports one message per line as "file:line: error: message", or "file: error: message"
+// when there is no line, and ends with a count such as "2 errors generated.".
+fn parse_diagnostics(path: &str, message: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for text in message
+        .lines()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+    {
+        let found = [
+            (": error: ", Severity::Error),
+            (": warning: ", Severity::Warning),
+        ]
+        .into_iter()
+        .find_map(|(separator, severity)| {
+            text.split_once(separator)
+                .map(|(location, message)| (location, severity, message))
+        });
+        let Some((location, severity, message)) = found else {
+            if !text.ends_with(" generated.") {
+                diagnostics.push(Diagnostic {
+                    file: path.to_string(),
+                    line: None,
+                    severity: Severity::Error,
+                    message: text.to_string(),
+                });
+            }
+            continue;
+        };
+
+        let (file, line): (&str, Option<usize>) = match location.rsplit_once(':') {
+            Some((file, line)) if line.parse::<usize>().is_ok() => (file, line.parse().ok()),
+            _ => (location, None),
+        };
+        diagnostics.push(Diagnostic {
+            file: file.to_string(),
+            line,
+            severity,
+            message: message.to_string(),
+        });
+    }
+
+    diagnostics
+}
+
+// What the resolver hands to shaderc: the C view first, then the strings it points into.
+#[repr(C)]
+struct IncludeResult {
+    result: shaderc_include_result,
+    source_name: String,
+    content: String,
+}
+
+unsafe extern "C" fn resolve_include_callback(
+    user_data: *mut c_void,
+    requested_source: *const c_char,
+    include_type: c_int,
+    requesting_source: *const c_char,
+    _include_depth: usize,
+) -> *mut shaderc_include_result {
+    let include_dirs: &Vec<PathBuf> = unsafe { &*(user_data as *const Vec<PathBuf>) };
+    let requested: String = unsafe { CStr::from_ptr(requested_source) }
+        .to_string_lossy()
+        .into_owned();
+    let requesting: String = unsafe { CStr::from_ptr(requesting_source) }
+        .to_string_lossy()
+        .into_owned();
+
+    let relative: bool = include_type == shaderc_include_type_relative as c_int;
+    let (source_name, content): (String, String) =
+        include_source(&requested, relative, &requesting, include_dirs);
+
+    let mut include: Box<IncludeResult> = Box::new(IncludeResult {
+        result: unsafe { std::mem::zeroed() },
+        source_name,
+        content,
+    });
+    include.result.source_name = include.source_name.as_ptr() as *const c_char;
+    include.result.source_name_length = include.source_name.len();
+    include.result.content = include.content.as_ptr() as *const c_char;
+    include.result.content_length = include.content.len();
+
+    Box::into_raw(include) as *mut shaderc_include_result
+}
+
+unsafe extern "C" fn release_include_callback(
+    _user_data: *mut c_void,
+    include_result: *mut shaderc_include_result,
+) {
+    if !include_result.is_null() {
+        drop(unsafe { Box::from_raw(include_result as *mut IncludeResult) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::app::graphics::shader_include::resolve_include;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/shaders");
+
+    fn fixture(name: &str) -> String {
+        format!("{}/{}", FIXTURES, name)
+    }
+
+    #[test]
+    fn stage_comes_from_the_extension() {
+        assert_eq!(
+            ShaderStage::from_path(Path::new("shader.frag")),
+            Some((ShaderStage::Fragment, ShaderLanguage::Glsl))
+        );
+        assert_eq!(
+            ShaderStage::from_path(Path::new("blur.comp.hlsl")),
+            Some((ShaderStage::Compute, ShaderLanguage::Hlsl))
+        );
+        assert_eq!(ShaderStage::from_path(Path::new("shader.glsl")), None);
+        assert_eq!(ShaderStage::from_path(Path::new("shader.hlsl")), None);
+    }
+
+    #[test]
+    fn diagnostics_keep_file_and_line() {
+        let diagnostics: Vec<Diagnostic> = parse_diagnostics(
+            "shader.frag",
+            "/src/common.glsl:3: error: 'colour' : undeclared identifier\n\
+             shader.frag: warning: version 450 is unknown\n\
+             2 errors generated.\n",
+        );
+
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "/src/common.glsl:3: error: 'colour' : undeclared identifier"
+        );
+        assert_eq!(diagnostics[1].line, None);
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn includes_resolve_next_to_the_source_first() {
+        let include_dirs: Vec<PathBuf> = vec![PathBuf::from(SHADER_DIR)];
+        assert_eq!(
+            resolve_include("lighting.glsl", true, &fixture("lit.frag"), &include_dirs),
+            Ok(PathBuf::from(fixture("lighting.glsl")))
+        );
+        assert!(
+            resolve_include("lighting.glsl", false, &fixture("lit.frag"), &include_dirs).is_err()
+        );
+    }
+
+    #[test]
+    fn compiles_with_includes_and_defines() {
+        let compiler: ShaderCompiler = ShaderCompiler::default();
+        let code: Vec<u32> = compiler
+            .compile(&fixture("lit.frag"), &[("AMBIENT", "0.25")])
+            .unwrap();
+        assert_eq!(code[0], spirv::MAGIC);
+
+        // AMBIENT is only given on the command line.
+        assert!(compiler.compile(&fixture("lit.frag"), &[]).is_err());
+        assert!(compiler.compile(VERTEX_SHADER_PATH, &[]).is_ok());
+        assert!(compiler.compile(FRAGMENT_SHADER_PATH, &[]).is_ok());
+    }
+
+    #[test]
+    fn errors_point_into_the_included_file() {
+        let compiler: ShaderCompiler = ShaderCompiler::new(vec![PathBuf::from(FIXTURES)]);
+        let error: ShaderError = compiler.compile(&fixture("broken.frag"), &[]).unwrap_err();
+
+        let ShaderError::Compile { diagnostics, .. } = &error else {
+            panic!("expected a compile error, got {}", error);
+        };
+        assert_eq!(diagnostics[0].file, fixture("broken.glsl"));
+        assert_eq!(diagnostics[0].line, Some(4));
+    }
+}
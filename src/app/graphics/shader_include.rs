@@ -0,0 +1,46 @@
+// Include lookup for shaderc, shared by the runtime compiler in shader.rs and by build.rs, which
+// compiles this file with #[path]. It can only use std for that reason.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// `#include "file"` is looked up next to the including file first and then in the include
+// directories, `#include <file>` only in the include directories.
+pub fn resolve_include(
+    requested: &str,
+    relative: bool,
+    requesting: &str,
+    include_dirs: &[PathBuf],
+) -> Result<PathBuf, String> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if relative {
+        let directory: &Path = Path::new(requesting).parent().unwrap_or(Path::new(""));
+        candidates.push(directory.join(requested));
+    }
+    candidates.extend(include_dirs.iter().map(|dir| dir.join(requested)));
+
+    candidates
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| format!("can not find the included file \"{}\"", requested))
+}
+
+// The source name and content shaderc gets for an include. An empty source name tells shaderc
+// the include failed; the content is the reason.
+pub fn include_source(
+    requested: &str,
+    relative: bool,
+    requesting: &str,
+    include_dirs: &[PathBuf],
+) -> (String, String) {
+    let resolved: Result<(String, String), String> =
+        resolve_include(requested, relative, requesting, include_dirs).and_then(|path| {
+            let content: String = fs::read_to_string(&path)
+                .map_err(|error| format!("can not read {}: {}", path.display(), error))?;
+            Ok((path.display().to_string(), content))
+        });
+
+    match resolved {
+        Ok(found) => found,
+        Err(message) => (String::new(), message),
+    }
+}
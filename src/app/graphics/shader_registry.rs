@@ -0,0 +1,200 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::utils::debug_mode;
+
+use super::spirv;
+
+// Puts the embedded bytes on a word boundary, so they can be read as SPIR-V words in place.
+#[repr(C, align(4))]
+struct Aligned<T: ?Sized>(T);
+
+// EMBEDDED_SHADERS: every shader in src/shaders, compiled to SPIR-V by build.rs.
+include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+
+#[derive(Debug)]
+pub enum RegistryError {
+    NotFound {
+        name: String,
+        embedded: Vec<&'static str>,
+    },
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // `origin` is the override file or the name of the embedded shader.
+    Invalid {
+        origin: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NotFound { name, embedded } => write!(
+                f,
+                "No shader named {} is built in, there are {}",
+                name,
+                embedded.join(", ")
+            ),
+            RegistryError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            RegistryError::Invalid { origin, reason } => {
+                write!(f, "{} is not valid SPIR-V: {}", origin, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RegistryError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Compiled shaders by their path in src/shaders, e.g. "shader.vert". They are built into the
+// binary, so nothing is read from the working directory. During development an override directory
+// can hold <name>.spv files that are used instead, without rebuilding.
+#[derive(Debug, Default)]
+pub struct ShaderRegistry {
+    override_dir: Option<PathBuf>,
+}
+
+impl ShaderRegistry {
+    pub fn new(override_dir: Option<&str>) -> Self {
+        Self {
+            override_dir: override_dir.map(PathBuf::from),
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        EMBEDDED_SHADERS.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn load(&self, name: &str) -> Result<Vec<u32>, RegistryError> {
+        let Some(path) = self
+            .override_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.spv", name)))
+            .filter(|path| path.is_file())
+        else {
+            return Ok(embedded(name)?.to_vec());
+        };
+
+        let origin: String = path.display().to_string();
+        let bytes: Vec<u8> = fs::read(&path).map_err(|source| RegistryError::Io {
+            path: origin.clone(),
+            source,
+        })?;
+        check_spirv(&origin, &bytes)?;
+        if debug_mode() {
+            println!("Shader {} loaded from {}", name, origin);
+        }
+
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+            .collect())
+    }
+}
+
+// The built-in code of a shader, read in place from the binary.
+pub fn embedded(name: &str) -> Result<&'static [u32], RegistryError> {
+    let (_, code): &(&str, &Aligned<[u8]>) = EMBEDDED_SHADERS
+        .iter()
+        .find(|(embedded, _)| *embedded == name)
+        .ok_or_else(|| RegistryError::NotFound {
+            name: name.to_string(),
+            embedded: ShaderRegistry::names(),
+        })?;
+    let bytes: &'static [u8] = &code.0;
+
+    let origin: String = format!("built-in shader {}", name);
+    check_spirv(&origin, bytes)?;
+    if !(bytes.as_ptr() as usize).is_multiple_of(std::mem::align_of::<u32>()) {
+        return Err(RegistryError::Invalid {
+            origin,
+            reason: "it is not aligned to a word boundary",
+        });
+    }
+
+    Ok(unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const u32, bytes.len() / 4) })
+}
+
+fn check_spirv(origin: &str, bytes: &[u8]) -> Result<(), RegistryError> {
+    let invalid = |reason: &'static str| RegistryError::Invalid {
+        origin: origin.to_string(),
+        reason,
+    };
+    if !bytes.len().is_multiple_of(4) {
+        return Err(invalid("its length is not a whole number of words"));
+    }
+    if bytes.len() < 4
+        || u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) != spirv::MAGIC
+    {
+        return Err(invalid("it does not start with the SPIR-V magic number"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    fn write_words(path: &Path, words: &[u32]) {
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn embeds_every_shader() {
+        let names: Vec<&str> = ShaderRegistry::names();
+        assert!(names.contains(&"shader.vert"));
+        assert!(names.contains(&"shader.frag"));
+
+        for name in names {
+            assert_eq!(embedded(name).unwrap()[0], spirv::MAGIC);
+        }
+        assert!(matches!(
+            embedded("missing.vert"),
+            Err(RegistryError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn override_dir_replaces_embedded_shaders() {
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("oito-caneco-registry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let header: [u32; 5] = [spirv::MAGIC, 0x0001_0000, 0, 1, 0];
+        write_words(&dir.join("shader.vert.spv"), &header);
+        fs::write(dir.join("shader.frag.spv"), [3, 2, 0x23, 7, 0]).unwrap();
+
+        let registry: ShaderRegistry = ShaderRegistry::new(dir.to_str());
+        assert_eq!(registry.load("shader.vert").unwrap(), header);
+        assert_eq!(
+            registry.load("shader.frag").unwrap_err().to_string(),
+            format!(
+                "{} is not valid SPIR-V: its length is not a whole number of words",
+                dir.join("shader.frag.spv").display()
+            )
+        );
+
+        // Shaders without an override file are still the built-in ones.
+        fs::remove_file(dir.join("shader.frag.spv")).unwrap();
+        assert_eq!(
+            registry.load("shader.frag").unwrap(),
+            embedded("shader.frag").unwrap()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::vulkan::VkSpecializationMapEntry;
+
+use super::reflect::PipelineShaders;
+use super::spirv::{ShaderReflection, SpecializationConstant, Type};
+
+// The value of one `layout(constant_id = N) const` in a shader. Every kind is 32 bits wide in the
+// specialization data, booleans as VkBool32.
+#[derive(Clone, Copy, Debug)]
+pub enum SpecializationValue {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+}
+
+impl SpecializationValue {
+    // The type a constant must be declared with to take this value.
+    pub fn ty(self) -> Type {
+        match self {
+            SpecializationValue::Bool(_) => Type::Bool,
+            SpecializationValue::Int(_) => Type::Int {
+                width: 32,
+                signed: true,
+            },
+            SpecializationValue::UInt(_) => Type::Int {
+                width: 32,
+                signed: false,
+            },
+            SpecializationValue::Float(_) => Type::Float { width: 32 },
+        }
+    }
+
+    fn bits(self) -> u32 {
+        match self {
+            SpecializationValue::Bool(value) => value as u32,
+            SpecializationValue::Int(value) => value as u32,
+            SpecializationValue::UInt(value) => value,
+            SpecializationValue::Float(value) => value.to_bits(),
+        }
+    }
+}
+
+// Floats are compared by their bits, so a value can be a key of the pipeline cache.
+impl PartialEq for SpecializationValue {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.bits() == other.bits()
+    }
+}
+
+impl Eq for SpecializationValue {}
+
+impl Hash for SpecializationValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        self.bits().hash(state);
+    }
+}
+
+impl From<bool> for SpecializationValue {
+    fn from(value: bool) -> Self {
+        SpecializationValue::Bool(value)
+    }
+}
+
+impl From<i32> for SpecializationValue {
+    fn from(value: i32) -> Self {
+        SpecializationValue::Int(value)
+    }
+}
+
+impl From<u32> for SpecializationValue {
+    fn from(value: u32) -> Self {
+        SpecializationValue::UInt(value)
+    }
+}
+
+impl From<f32> for SpecializationValue {
+    fn from(value: f32) -> Self {
+        SpecializationValue::Float(value)
+    }
+}
+
+// The constants of one shader stage by constant_id. Constants that are not set keep the default
+// from the shader.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpecializationConstants {
+    values: BTreeMap<u32, SpecializationValue>,
+}
+
+impl SpecializationConstants {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, id: u32, value: impl Into<SpecializationValue>) -> Self {
+        self.set(id, value);
+        self
+    }
+
+    pub fn set(&mut self, id: u32, value: impl Into<SpecializationValue>) {
+        self.values.insert(id, value.into());
+    }
+
+    pub fn get(&self, id: u32) -> Option<SpecializationValue> {
+        self.values.get(&id).copied()
+    }
+
+    // Every value needs a constant with its constant_id and type in the shader.
+    pub fn check(&self, stage: &ShaderReflection) -> Result<(), String> {
+        let mut problems: Vec<String> = Vec::new();
+        for (id, value) in &self.values {
+            let Some(constant) = stage
+                .specialization_constants
+                .iter()
+                .find(|constant| constant.id == *id)
+            else {
+                problems.push(format!(
+                    "the {:?} shader has no specialization constant with constant_id = {}",
+                    stage.entry_point.stage, id
+                ));
+                continue;
+            };
+            if constant.ty != value.ty() {
+                problems.push(format!(
+                    "specialization constant {} of the {:?} shader is declared as {} but \
+                     specialized as {}",
+                    label(constant),
+                    stage.entry_point.stage,
+                    constant.ty,
+                    value.ty()
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+
+    // One entry per value, in constant_id order, each pointing at its word in `data`.
+    pub fn map_entries(&self) -> Vec<VkSpecializationMapEntry> {
+        let mut offset: u32 = 0;
+        self.values
+            .keys()
+            .map(|id| {
+                let entry: VkSpecializationMapEntry = VkSpecializationMapEntry {
+                    constantID: *id,
+                    offset,
+                    size: std::mem::size_of::<u32>(),
+                };
+                offset += std::mem::size_of::<u32>() as u32;
+                entry
+            })
+            .collect()
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        self.values
+            .values()
+            .flat_map(|value| value.bits().to_ne_bytes())
+            .collect()
+    }
+}
+
+// The constants of every stage of a graphics pipeline. Pipelines are cached by it, so switching
+// back to values used before does not build a new pipeline.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PipelineSpecialization {
+    pub vertex: SpecializationConstants,
+    pub fragment: SpecializationConstants,
+}
+
+impl PipelineSpecialization {
+    pub fn check(&self, pipeline_shaders: &PipelineShaders) -> Result<(), String> {
+        let problems: Vec<String> = [
+            self.vertex.check(&pipeline_shaders.vertex),
+            self.fragment.check(&pipeline_shaders.fragment),
+        ]
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+}
+
+// Pipelines by the constants they were specialized with. Asking again for constants used before
+// returns the pipeline built then instead of building another one.
+#[derive(Debug)]
+pub struct PipelineCache<P> {
+    pipelines: HashMap<PipelineSpecialization, P>,
+}
+
+impl<P> Default for PipelineCache<P> {
+    fn default() -> Self {
+        Self {
+            pipelines: HashMap::new(),
+        }
+    }
+}
+
+impl<P: Copy> PipelineCache<P> {
+    // A pipeline that fails to build is not cached, so the next call tries again.
+    pub fn get_or_build<E>(
+        &mut self,
+        specialization: &PipelineSpecialization,
+        build: impl FnOnce() -> Result<P, E>,
+    ) -> Result<P, E> {
+        if let Some(pipeline) = self.pipelines.get(specialization) {
+            return Ok(*pipeline);
+        }
+        let pipeline: P = build()?;
+        self.pipelines.insert(specialization.clone(), pipeline);
+
+        Ok(pipeline)
+    }
+
+    pub fn insert(&mut self, specialization: PipelineSpecialization, pipeline: P) {
+        self.pipelines.insert(specialization, pipeline);
+    }
+
+    // Empties the cache and hands out the pipelines, which the caller destroys.
+    pub fn drain(&mut self) -> impl Iterator<Item = P> + '_ {
+        self.pipelines.drain().map(|(_, pipeline)| pipeline)
+    }
+}
+
+// Names are debug information, which the shader may have been compiled without.
+fn label(constant: &SpecializationConstant) -> String {
+    if constant.name.is_empty() {
+        format!("constant_id = {}", constant.id)
+    } else {
+        format!("{} (constant_id = {})", constant.name, constant.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use crate::app::graphics::shader::ShaderStage;
+    use crate::app::graphics::spirv::EntryPoint;
+
+    fn fragment_stage() -> ShaderReflection {
+        let constant = |name: &str, id: u32, ty: Type| SpecializationConstant {
+            name: name.to_string(),
+            id,
+            ty,
+            default: 0,
+        };
+        ShaderReflection {
+            entry_point: EntryPoint {
+                name: String::from("main"),
+                stage: ShaderStage::Fragment,
+            },
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            descriptor_bindings: Vec::new(),
+            push_constants: None,
+            specialization_constants: vec![
+                constant("TEXTURED", 1, Type::Bool),
+                constant(
+                    "LIGHT_COUNT",
+                    4,
+                    Type::Int {
+                        width: 32,
+                        signed: false,
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn lays_out_values_in_constant_id_order() {
+        let constants: SpecializationConstants = SpecializationConstants::new()
+            .with(7, -2)
+            .with(1, true)
+            .with(4, 0.5f32);
+
+        let entries: Vec<(u32, u32, usize)> = constants
+            .map_entries()
+            .iter()
+            .map(|entry| (entry.constantID, entry.offset, entry.size))
+            .collect();
+        assert_eq!(entries, [(1, 0, 4), (4, 4, 4), (7, 8, 4)]);
+
+        let words: Vec<u32> = [1, 0.5f32.to_bits(), -2i32 as u32].to_vec();
+        let data: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        assert_eq!(constants.data(), data);
+    }
+
+    #[test]
+    fn values_must_match_the_declared_constants() {
+        let stage: ShaderReflection = fragment_stage();
+        assert!(SpecializationConstants::new()
+            .with(1, false)
+            .with(4, 2u32)
+            .check(&stage)
+            .is_ok());
+
+        assert_eq!(
+            SpecializationConstants::new()
+                .with(4, 2)
+                .with(9, 1.0f32)
+                .check(&stage),
+            Err(String::from(
+                "specialization constant LIGHT_COUNT (constant_id = 4) of the Fragment shader is \
+                 declared as uint but specialized as int\n\
+                 the Fragment shader has no specialization constant with constant_id = 9"
+            ))
+        );
+    }
+
+    #[test]
+    fn builds_each_pipeline_once() {
+        let specialization = |light_count: u32| PipelineSpecialization {
+            vertex: SpecializationConstants::new(),
+            fragment: SpecializationConstants::new().with(4, light_count),
+        };
+        let builds: &Cell<u32> = &Cell::new(0);
+        let build = |pipeline: u64| {
+            move || -> Result<u64, String> {
+                builds.set(builds.get() + 1);
+                Ok(pipeline)
+            }
+        };
+        let mut cache: PipelineCache<u64> = PipelineCache::default();
+
+        assert_eq!(cache.get_or_build(&specialization(1), build(10)), Ok(10));
+        assert_eq!(cache.get_or_build(&specialization(2), build(20)), Ok(20));
+        assert_eq!(cache.get_or_build(&specialization(1), build(11)), Ok(10));
+        assert_eq!(builds.get(), 2);
+
+        // A pipeline that failed to build is built again the next time.
+        assert_eq!(
+            cache.get_or_build(&specialization(3), || Err(String::from("failed"))),
+            Err(String::from("failed"))
+        );
+        assert_eq!(cache.get_or_build(&specialization(3), build(30)), Ok(30));
+
+        // Bools and uints with the same bits are different values, so each gets its own pipeline.
+        assert_ne!(
+            SpecializationValue::Bool(true),
+            SpecializationValue::UInt(1)
+        );
+        let textured = |value: SpecializationValue| PipelineSpecialization {
+            vertex: SpecializationConstants::new(),
+            fragment: SpecializationConstants::new().with(1, value),
+        };
+        assert_eq!(
+            cache.get_or_build(&textured(SpecializationValue::Bool(true)), build(40)),
+            Ok(40)
+        );
+        assert_eq!(
+            cache.get_or_build(&textured(SpecializationValue::UInt(1)), build(50)),
+            Ok(50)
+        );
+        assert_eq!(builds.get(), 5);
+
+        let mut drained: Vec<u64> = cache.drain().collect();
+        drained.sort();
+        assert_eq!(drained, [10, 20, 30, 40, 50]);
+        assert_eq!(cache.get_or_build(&specialization(1), build(12)), Ok(12));
+        assert_eq!(builds.get(), 6);
+    }
+}
@@ -0,0 +1,1145 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::shader::ShaderStage;
+
+// Reads what the pipeline needs to know from a SPIR-V module: its entry points, the stage inputs
+// and outputs, descriptor bindings, push constants and specialization constants.
+
+pub const MAGIC: u32 = 0x0723_0203;
+const HEADER_WORDS: usize = 5;
+
+// Numbers from the SPIR-V specification.
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_VOID: u32 = 19;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_OUTPUT: u32 = 3;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+const EXECUTION_MODEL_GL_COMPUTE: u32 = 5;
+
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+// Sampled operand of OpTypeImage: 1 when used with a sampler, 2 when used as a storage image.
+const IMAGE_SAMPLED: u32 = 1;
+const IMAGE_STORAGE: u32 = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Void,
+    Bool,
+    Int {
+        width: u32,
+        signed: bool,
+    },
+    Float {
+        width: u32,
+    },
+    Vector {
+        component: Box<Type>,
+        count: u32,
+    },
+    Matrix {
+        column: Box<Type>,
+        count: u32,
+    },
+    Image {
+        dim: u32,
+        sampled: u32,
+    },
+    Sampler,
+    SampledImage,
+    // Runtime arrays have no length. The stride is only known for arrays inside blocks.
+    Array {
+        element: Box<Type>,
+        length: Option<u32>,
+        stride: Option<u32>,
+    },
+    Struct {
+        name: String,
+        members: Vec<Member>,
+    },
+    Pointer,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    pub offset: Option<u32>,
+    pub matrix_stride: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: ShaderStage,
+}
+
+// A user defined stage input or output; built-ins such as gl_Position are left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceVariable {
+    pub name: String,
+    pub location: u32,
+    pub ty: Type,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DescriptorType {
+    Sampler,
+    CombinedImageSampler,
+    SampledImage,
+    StorageImage,
+    UniformTexelBuffer,
+    StorageTexelBuffer,
+    UniformBuffer,
+    StorageBuffer,
+    InputAttachment,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescriptorBinding {
+    pub name: String,
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: DescriptorType,
+    // Arrays of descriptors take one binding with `count` elements.
+    pub count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PushConstantRange {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecializationConstant {
+    pub name: String,
+    // The constant_id from `layout(constant_id = N)`.
+    pub id: u32,
+    pub ty: Type,
+    // The value used when nothing is specialized, as raw bits; booleans are 0 or 1.
+    pub default: u64,
+}
+
+// One entry point of a module and the resources it can use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderReflection {
+    pub entry_point: EntryPoint,
+    pub inputs: Vec<InterfaceVariable>,
+    pub outputs: Vec<InterfaceVariable>,
+    pub descriptor_bindings: Vec<DescriptorBinding>,
+    pub push_constants: Option<PushConstantRange>,
+    pub specialization_constants: Vec<SpecializationConstant>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SpirvError {
+    // `word` is the index of the instruction that could not be read.
+    Invalid { word: usize, message: String },
+    MissingEntryPoint { name: String, found: Vec<String> },
+    Unsupported(String),
+}
+
+impl fmt::Display for SpirvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpirvError::Invalid { word, message } => {
+                write!(f, "invalid SPIR-V at word {}: {}", word, message)
+            }
+            SpirvError::MissingEntryPoint { name, found } => write!(
+                f,
+                "no entry point named {}, the module has {}",
+                name,
+                found.join(", ")
+            ),
+            SpirvError::Unsupported(message) => write!(f, "unsupported SPIR-V: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SpirvError {}
+
+// Names as they are written in GLSL, for error messages.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Bool => write!(f, "bool"),
+            Type::Int {
+                width: 32,
+                signed: true,
+            } => write!(f, "int"),
+            Type::Int {
+                width: 32,
+                signed: false,
+            } => write!(f, "uint"),
+            Type::Int { width, signed } => {
+                write!(f, "{}int{}_t", if *signed { "" } else { "u" }, width)
+            }
+            Type::Float { width: 32 } => write!(f, "float"),
+            Type::Float { width: 64 } => write!(f, "double"),
+            Type::Float { width } => write!(f, "float{}_t", width),
+            Type::Vector { component, count } => {
+                write!(f, "{}vec{}", vector_prefix(component), count)
+            }
+            Type::Matrix { column, count } => {
+                let (component, rows): (&Type, u32) = match column.as_ref() {
+                    Type::Vector { component, count } => (component, *count),
+                    other => (other, 1),
+                };
+                write!(f, "{}mat{}", vector_prefix(component), count)?;
+                if rows != *count {
+                    write!(f, "x{}", rows)?;
+                }
+                Ok(())
+            }
+            Type::Image { .. } => write!(f, "image"),
+            Type::Sampler => write!(f, "sampler"),
+            Type::SampledImage => write!(f, "sampled image"),
+            Type::Array {
+                element, length, ..
+            } => match length {
+                Some(length) => write!(f, "{}[{}]", element, length),
+                None => write!(f, "{}[]", element),
+            },
+            Type::Struct { name, .. } => write!(f, "struct {}", name),
+            Type::Pointer => write!(f, "pointer"),
+        }
+    }
+}
+
+fn vector_prefix(component: &Type) -> &'static str {
+    match component {
+        Type::Float { width: 64 } => "d",
+        Type::Int { signed: true, .. } => "i",
+        Type::Int { signed: false, .. } => "u",
+        Type::Bool => "b",
+        _ => "",
+    }
+}
+
+impl Type {
+    // Bytes taken in a block, following its explicit offsets and strides.
+    pub fn size(&self) -> Option<u32> {
+        match self {
+            Type::Int { width, .. } | Type::Float { width } => Some(width / 8),
+            Type::Vector { component, count } => Some(component.size()? * count),
+            Type::Matrix { column, count } => Some(column.size()? * count),
+            Type::Array {
+                element,
+                length,
+                stride,
+            } => {
+                let stride: u32 = match stride {
+                    Some(stride) => *stride,
+                    None => element.size()?,
+                };
+                Some(stride * (*length)?)
+            }
+            Type::Struct { members, .. } => members
+                .iter()
+                .map(|member| Some(member.offset? + member.size()?))
+                .try_fold(0, |size, end| Some(u32::max(size, end?))),
+            _ => None,
+        }
+    }
+}
+
+impl Member {
+    pub fn size(&self) -> Option<u32> {
+        match (&self.ty, self.matrix_stride) {
+            (Type::Matrix { count, .. }, Some(matrix_stride)) => Some(count * matrix_stride),
+            (ty, _) => ty.size(),
+        }
+    }
+}
+
+// Reflects the entry point called `entry_point`, usually "main".
+pub fn reflect(words: &[u32], entry_point: &str) -> Result<ShaderReflection, SpirvError> {
+    let module: Module = Module::parse(words)?;
+    module.reflect(entry_point)
+}
+
+// Every fragment shader input has to be written by the vertex shader with the same type.
+// Vertex outputs the fragment shader does not read are allowed.
+pub fn check_interface(
+    vertex: &ShaderReflection,
+    fragment: &ShaderReflection,
+) -> Result<(), Vec<String>> {
+    let mut problems: Vec<String> = Vec::new();
+    for input in &fragment.inputs {
+        match vertex
+            .outputs
+            .iter()
+            .find(|output| output.location == input.location)
+        {
+            None => problems.push(format!(
+                "fragment input {} at location {} is not written by the vertex shader",
+                input.name, input.location
+            )),
+            Some(output) if output.ty != input.ty => problems.push(format!(
+                "fragment input {} at location {} is {} but vertex output {} is {}",
+                input.name, input.location, input.ty, output.name, output.ty
+            )),
+            Some(_) => {}
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+enum RawType {
+    Void,
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer,
+}
+
+struct RawEntryPoint {
+    execution_model: u32,
+    name: String,
+    interface: Vec<u32>,
+}
+
+struct Variable {
+    id: u32,
+    pointee: u32,
+    storage_class: u32,
+}
+
+struct SpecConstant {
+    id: u32,
+    type_id: u32,
+    value: Vec<u32>,
+}
+
+// The instructions reflection needs, indexed by result id.
+#[derive(Default)]
+struct Module {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    decorations: HashMap<(u32, u32), Vec<u32>>,
+    member_decorations: HashMap<(u32, u32, u32), Vec<u32>>,
+    types: HashMap<u32, RawType>,
+    pointers: HashMap<u32, u32>,
+    constants: HashMap<u32, Vec<u32>>,
+    spec_constants: Vec<SpecConstant>,
+    variables: Vec<Variable>,
+    entry_points: Vec<RawEntryPoint>,
+}
+
+struct Instruction<'a> {
+    position: usize,
+    operands: &'a [u32],
+}
+
+impl Instruction<'_> {
+    fn error(&self, message: &str) -> SpirvError {
+        SpirvError::Invalid {
+            word: self.position,
+            message: message.to_string(),
+        }
+    }
+
+    fn word(&self, index: usize) -> Result<u32, SpirvError> {
+        self.operands
+            .get(index)
+            .copied()
+            .ok_or_else(|| self.error("instruction is too short"))
+    }
+
+    // A nul terminated UTF-8 string packed four bytes per word, and the words it takes.
+    fn string(&self, index: usize) -> Result<(String, usize), SpirvError> {
+        let mut bytes: Vec<u8> = Vec::new();
+        for (count, word) in self.operands.iter().skip(index).enumerate() {
+            for byte in word.to_le_bytes() {
+                if byte == 0 {
+                    let text: String = String::from_utf8(bytes)
+                        .map_err(|_| self.error("string is not valid UTF-8"))?;
+                    return Ok((text, count + 1));
+                }
+                bytes.push(byte);
+            }
+        }
+
+        Err(self.error("string is not terminated"))
+    }
+}
+
+impl Module {
+    fn parse(words: &[u32]) -> Result<Module, SpirvError> {
+        if words.len() < HEADER_WORDS || words[0] != MAGIC {
+            return Err(SpirvError::Invalid {
+                word: 0,
+                message: String::from("missing the SPIR-V magic number"),
+            });
+        }
+
+        let mut module: Module = Module::default();
+        let mut position: usize = HEADER_WORDS;
+        while position < words.len() {
+            let word_count: usize = (words[position] >> 16) as usize;
+            let opcode: u32 = words[position] & 0xffff;
+            if word_count == 0 || position + word_count > words.len() {
+                return Err(SpirvError::Invalid {
+                    word: position,
+                    message: format!("instruction {} has a bad word count", opcode),
+                });
+            }
+
+            let instruction: Instruction = Instruction {
+                position,
+                operands: &words[position + 1..position + word_count],
+            };
+            module.add(opcode, &instruction)?;
+            position += word_count;
+        }
+
+        Ok(module)
+    }
+
+    fn add(&mut self, opcode: u32, instruction: &Instruction) -> Result<(), SpirvError> {
+        let word = |index: usize| instruction.word(index);
+        match opcode {
+            OP_NAME => {
+                self.names.insert(word(0)?, instruction.string(1)?.0);
+            }
+            OP_MEMBER_NAME => {
+                self.member_names
+                    .insert((word(0)?, word(1)?), instruction.string(2)?.0);
+            }
+            OP_ENTRY_POINT => {
+                let (name, length): (String, usize) = instruction.string(2)?;
+                self.entry_points.push(RawEntryPoint {
+                    execution_model: word(0)?,
+                    name,
+                    interface: instruction.operands[2 + length..].to_vec(),
+                });
+            }
+            OP_TYPE_VOID => self.add_type(word(0)?, RawType::Void),
+            OP_TYPE_BOOL => self.add_type(word(0)?, RawType::Bool),
+            OP_TYPE_INT => self.add_type(
+                word(0)?,
+                RawType::Int {
+                    width: word(1)?,
+                    signed: word(2)? != 0,
+                },
+            ),
+            OP_TYPE_FLOAT => self.add_type(word(0)?, RawType::Float { width: word(1)? }),
+            OP_TYPE_VECTOR => self.add_type(
+                word(0)?,
+                RawType::Vector {
+                    component: word(1)?,
+                    count: word(2)?,
+                },
+            ),
+            OP_TYPE_MATRIX => self.add_type(
+                word(0)?,
+                RawType::Matrix {
+                    column: word(1)?,
+                    count: word(2)?,
+                },
+            ),
+            OP_TYPE_IMAGE => self.add_type(
+                word(0)?,
+                RawType::Image {
+                    dim: word(2)?,
+                    sampled: word(6)?,
+                },
+            ),
+            OP_TYPE_SAMPLER => self.add_type(word(0)?, RawType::Sampler),
+            OP_TYPE_SAMPLED_IMAGE => self.add_type(word(0)?, RawType::SampledImage),
+            OP_TYPE_ARRAY => self.add_type(
+                word(0)?,
+                RawType::Array {
+                    element: word(1)?,
+                    length: word(2)?,
+                },
+            ),
+            OP_TYPE_RUNTIME_ARRAY => {
+                self.add_type(word(0)?, RawType::RuntimeArray { element: word(1)? })
+            }
+            OP_TYPE_STRUCT => self.add_type(
+                word(0)?,
+                RawType::Struct {
+                    members: instruction.operands[1..].to_vec(),
+                },
+            ),
+            OP_TYPE_POINTER => {
+                self.add_type(word(0)?, RawType::Pointer);
+                self.pointers.insert(word(0)?, word(2)?);
+            }
+            OP_CONSTANT => {
+                word(2)?;
+                self.constants
+                    .insert(word(1)?, instruction.operands[2..].to_vec());
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE => {
+                self.spec_constants.push(SpecConstant {
+                    id: word(1)?,
+                    type_id: word(0)?,
+                    value: vec![(opcode == OP_SPEC_CONSTANT_TRUE) as u32],
+                })
+            }
+            OP_SPEC_CONSTANT => {
+                word(2)?;
+                self.spec_constants.push(SpecConstant {
+                    id: word(1)?,
+                    type_id: word(0)?,
+                    value: instruction.operands[2..].to_vec(),
+                });
+            }
+            OP_VARIABLE => {
+                let pointer: u32 = word(0)?;
+                let pointee: u32 = *self
+                    .pointers
+                    .get(&pointer)
+                    .ok_or_else(|| instruction.error("variable type is not a pointer"))?;
+                self.variables.push(Variable {
+                    id: word(1)?,
+                    pointee,
+                    storage_class: word(2)?,
+                });
+            }
+            OP_DECORATE => {
+                self.decorations
+                    .insert((word(0)?, word(1)?), instruction.operands[2..].to_vec());
+            }
+            OP_MEMBER_DECORATE => {
+                self.member_decorations.insert(
+                    (word(0)?, word(1)?, word(2)?),
+                    instruction.operands[3..].to_vec(),
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn add_type(&mut self, id: u32, ty: RawType) {
+        self.types.insert(id, ty);
+    }
+
+    fn name(&self, id: u32) -> String {
+        self.names.get(&id).cloned().unwrap_or_default()
+    }
+
+    fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decorations
+            .get(&(id, decoration))
+            .map(|literals| literals.first().copied().unwrap_or(0))
+    }
+
+    fn is_built_in(&self, variable: &Variable) -> bool {
+        if self
+            .decorations
+            .contains_key(&(variable.id, DECORATION_BUILT_IN))
+        {
+            return true;
+        }
+        // gl_PerVertex is a block whose members are all built-ins.
+        match self.types.get(&variable.pointee) {
+            Some(RawType::Struct { members }) => (0..members.len() as u32).any(|member| {
+                self.member_decorations.contains_key(&(
+                    variable.pointee,
+                    member,
+                    DECORATION_BUILT_IN,
+                ))
+            }),
+            _ => false,
+        }
+    }
+
+    fn resolve(&self, id: u32) -> Result<Type, SpirvError> {
+        let unknown = || SpirvError::Invalid {
+            word: 0,
+            message: format!("type %{} is not defined", id),
+        };
+        let ty: Type = match self.types.get(&id).ok_or_else(unknown)? {
+            RawType::Void => Type::Void,
+            RawType::Bool => Type::Bool,
+            RawType::Int { width, signed } => Type::Int {
+                width: *width,
+                signed: *signed,
+            },
+            RawType::Float { width } => Type::Float { width: *width },
+            RawType::Vector { component, count } => Type::Vector {
+                component: Box::new(self.resolve(*component)?),
+                count: *count,
+            },
+            RawType::Matrix { column, count } => Type::Matrix {
+                column: Box::new(self.resolve(*column)?),
+                count: *count,
+            },
+            RawType::Image { dim, sampled } => Type::Image {
+                dim: *dim,
+                sampled: *sampled,
+            },
+            RawType::Sampler => Type::Sampler,
+            RawType::SampledImage => Type::SampledImage,
+            RawType::Array { element, length } => Type::Array {
+                element: Box::new(self.resolve(*element)?),
+                length: Some(self.constant(*length)?),
+                stride: self.decoration(id, DECORATION_ARRAY_STRIDE),
+            },
+            RawType::RuntimeArray { element } => Type::Array {
+                element: Box::new(self.resolve(*element)?),
+                length: None,
+                stride: self.decoration(id, DECORATION_ARRAY_STRIDE),
+            },
+            RawType::Struct { members } => Type::Struct {
+                name: self.name(id),
+                members: members
+                    .iter()
+                    .enumerate()
+                    .map(|(index, member)| {
+                        let index: u32 = index as u32;
+                        let member_decoration = |decoration: u32| {
+                            self.member_decorations
+                                .get(&(id, index, decoration))
+                                .and_then(|literals| literals.first().copied())
+                        };
+                        Ok(Member {
+                            name: self
+                                .member_names
+                                .get(&(id, index))
+                                .cloned()
+                                .unwrap_or_default(),
+                            ty: self.resolve(*member)?,
+                            offset: member_decoration(DECORATION_OFFSET),
+                            matrix_stride: member_decoration(DECORATION_MATRIX_STRIDE),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+            },
+            // Pointers only show up inside physical storage buffers; nothing here follows them.
+            RawType::Pointer => Type::Pointer,
+        };
+
+        Ok(ty)
+    }
+
+    // Array lengths are 32-bit integer constants; specialized lengths are not supported.
+    fn constant(&self, id: u32) -> Result<u32, SpirvError> {
+        self.constants
+            .get(&id)
+            .and_then(|value| value.first().copied())
+            .ok_or_else(|| {
+                SpirvError::Unsupported(format!("array length %{} is not a constant", id))
+            })
+    }
+
+    fn reflect(&self, entry_point: &str) -> Result<ShaderReflection, SpirvError> {
+        let raw: &RawEntryPoint = self
+            .entry_points
+            .iter()
+            .find(|raw| raw.name == entry_point)
+            .ok_or_else(|| SpirvError::MissingEntryPoint {
+                name: entry_point.to_string(),
+                found: self
+                    .entry_points
+                    .iter()
+                    .map(|raw| raw.name.clone())
+                    .collect(),
+            })?;
+        let stage: ShaderStage = match raw.execution_model {
+            EXECUTION_MODEL_VERTEX => ShaderStage::Vertex,
+            EXECUTION_MODEL_FRAGMENT => ShaderStage::Fragment,
+            EXECUTION_MODEL_GL_COMPUTE => ShaderStage::Compute,
+            model => {
+                return Err(SpirvError::Unsupported(format!(
+                    "execution model {} of entry point {}",
+                    model, raw.name
+                )))
+            }
+        };
+
+        let mut inputs: Vec<InterfaceVariable> = Vec::new();
+        let mut outputs: Vec<InterfaceVariable> = Vec::new();
+        for variable in self
+            .variables
+            .iter()
+            .filter(|variable| raw.interface.contains(&variable.id))
+        {
+            let list: &mut Vec<InterfaceVariable> = match variable.storage_class {
+                STORAGE_INPUT => &mut inputs,
+                STORAGE_OUTPUT => &mut outputs,
+                _ => continue,
+            };
+            if self.is_built_in(variable) {
+                continue;
+            }
+            let name: String = self.name(variable.id);
+            let location: u32 = self
+                .decoration(variable.id, DECORATION_LOCATION)
+                .ok_or_else(|| SpirvError::Unsupported(format!("{} has no location", name)))?;
+            list.push(InterfaceVariable {
+                name,
+                location,
+                ty: self.resolve(variable.pointee)?,
+            });
+        }
+        inputs.sort_by_key(|variable| variable.location);
+        outputs.sort_by_key(|variable| variable.location);
+
+        Ok(ShaderReflection {
+            entry_point: EntryPoint {
+                name: raw.name.clone(),
+                stage,
+            },
+            inputs,
+            outputs,
+            descriptor_bindings: self.descriptor_bindings()?,
+            push_constants: self.push_constants()?,
+            specialization_constants: self.specialization_constants()?,
+        })
+    }
+
+    fn descriptor_bindings(&self) -> Result<Vec<DescriptorBinding>, SpirvError> {
+        let mut bindings: Vec<DescriptorBinding> = Vec::new();
+        for variable in &self.variables {
+            if !matches!(
+                variable.storage_class,
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER
+            ) {
+                continue;
+            }
+            let Some(binding) = self.decoration(variable.id, DECORATION_BINDING) else {
+                continue;
+            };
+
+            let name: String = self.name(variable.id);
+            // Arrays of resources, possibly nested, are one binding.
+            let mut type_id: u32 = variable.pointee;
+            let mut count: u32 = 1;
+            loop {
+                match self.types.get(&type_id) {
+                    Some(RawType::Array { element, length }) => {
+                        count *= self.constant(*length)?;
+                        type_id = *element;
+                    }
+                    Some(RawType::RuntimeArray { .. }) => {
+                        return Err(SpirvError::Unsupported(format!(
+                            "{} is an array of descriptors without a size",
+                            name
+                        )));
+                    }
+                    _ => break,
+                }
+            }
+
+            let descriptor_type: DescriptorType =
+                match (variable.storage_class, self.types.get(&type_id)) {
+                    (STORAGE_STORAGE_BUFFER, _) => DescriptorType::StorageBuffer,
+                    (STORAGE_UNIFORM, _)
+                        if self
+                            .decorations
+                            .contains_key(&(type_id, DECORATION_BUFFER_BLOCK)) =>
+                    {
+                        DescriptorType::StorageBuffer
+                    }
+                    (STORAGE_UNIFORM, _)
+                        if self.decorations.contains_key(&(type_id, DECORATION_BLOCK)) =>
+                    {
+                        DescriptorType::UniformBuffer
+                    }
+                    (_, Some(RawType::SampledImage)) => DescriptorType::CombinedImageSampler,
+                    (_, Some(RawType::Sampler)) => DescriptorType::Sampler,
+                    (_, Some(RawType::Image { dim, sampled })) => match (*dim, *sampled) {
+                        (DIM_SUBPASS_DATA, _) => DescriptorType::InputAttachment,
+                        (DIM_BUFFER, IMAGE_STORAGE) => DescriptorType::StorageTexelBuffer,
+                        (DIM_BUFFER, _) => DescriptorType::UniformTexelBuffer,
+                        (_, IMAGE_STORAGE) => DescriptorType::StorageImage,
+                        (_, IMAGE_SAMPLED) => DescriptorType::SampledImage,
+                        _ => {
+                            return Err(SpirvError::Unsupported(format!(
+                                "{} is an image without a known use",
+                                name
+                            )));
+                        }
+                    },
+                    _ => {
+                        return Err(SpirvError::Unsupported(format!(
+                            "{} at binding {} is not a known kind of descriptor",
+                            name, binding
+                        )));
+                    }
+                };
+
+            bindings.push(DescriptorBinding {
+                name,
+                set: self
+                    .decoration(variable.id, DECORATION_DESCRIPTOR_SET)
+                    .unwrap_or(0),
+                binding,
+                descriptor_type,
+                count,
+            });
+        }
+        bindings.sort_by_key(|binding| (binding.set, binding.binding));
+
+        Ok(bindings)
+    }
+
+    // Vulkan allows one push constant block per entry point.
+    fn push_constants(&self) -> Result<Option<PushConstantRange>, SpirvError> {
+        let Some(variable) = self
+            .variables
+            .iter()
+            .find(|variable| variable.storage_class == STORAGE_PUSH_CONSTANT)
+        else {
+            return Ok(None);
+        };
+
+        let name: String = self.name(variable.id);
+        let Type::Struct { members, .. } = self.resolve(variable.pointee)? else {
+            return Err(SpirvError::Unsupported(format!(
+                "push constant {} is not a block",
+                name
+            )));
+        };
+        let offset: u32 = members
+            .iter()
+            .filter_map(|member| member.offset)
+            .min()
+            .unwrap_or(0);
+        let end: u32 = members
+            .iter()
+            .map(|member| Some(member.offset? + member.size()?))
+            .try_fold(0, |size, end| Some(u32::max(size, end?)))
+            .ok_or_else(|| {
+                SpirvError::Unsupported(format!(
+                    "the size of push constant block {} can not be worked out",
+                    name
+                ))
+            })?;
+
+        Ok(Some(PushConstantRange {
+            name,
+            offset,
+            size: end - offset,
+        }))
+    }
+
+    fn specialization_constants(&self) -> Result<Vec<SpecializationConstant>, SpirvError> {
+        let mut constants: Vec<SpecializationConstant> = Vec::new();
+        for constant in &self.spec_constants {
+            // Without a SpecId the constant is an operation on other constants, not an input.
+            let Some(id) = self.decoration(constant.id, DECORATION_SPEC_ID) else {
+                continue;
+            };
+            let low: u64 = constant.value.first().copied().unwrap_or(0) as u64;
+            let high: u64 = constant.value.get(1).copied().unwrap_or(0) as u64;
+            constants.push(SpecializationConstant {
+                name: self.name(constant.id),
+                id,
+                ty: self.resolve(constant.type_id)?,
+                default: high << 32 | low,
+            });
+        }
+        constants.sort_by_key(|constant| constant.id);
+
+        Ok(constants)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // Builds modules instruction by instruction, like an assembler would.
+    #[derive(Default)]
+    pub struct Assembler {
+        words: Vec<u32>,
+    }
+
+    impl Assembler {
+        pub fn op(&mut self, opcode: u32, operands: &[u32]) -> &mut Self {
+            self.words
+                .push(((operands.len() as u32 + 1) << 16) | opcode);
+            self.words.extend_from_slice(operands);
+            self
+        }
+
+        // An instruction whose string operand comes after `before` and is followed by `after`.
+        pub fn op_string(
+            &mut self,
+            opcode: u32,
+            before: &[u32],
+            text: &str,
+            after: &[u32],
+        ) -> &mut Self {
+            let mut bytes: Vec<u8> = text.as_bytes().to_vec();
+            bytes.resize(bytes.len() / 4 * 4 + 4, 0);
+            let mut operands: Vec<u32> = before.to_vec();
+            operands.extend(
+                bytes
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]])),
+            );
+            operands.extend_from_slice(after);
+            self.op(opcode, &operands)
+        }
+
+        pub fn finish(&self) -> Vec<u32> {
+            let mut words: Vec<u32> = vec![MAGIC, 0x0001_0000, 0, 100, 0];
+            words.extend_from_slice(&self.words);
+            words
+        }
+    }
+
+    // layout(location = 0) in vec3 inPosition;
+    // layout(location = 1) out vec2 fragTexCoord;
+    // out gl_PerVertex { vec4 gl_Position; };
+    // layout(set = 0, binding = 0) uniform Ubo { mat4 model; } ubo;
+    // layout(set = 1, binding = 2) uniform sampler2D textures[4];
+    // layout(push_constant) uniform Push { float scale; vec4 tint; } push;
+    // layout(constant_id = 3) const int LIGHTS = 4;
+    fn vertex_module() -> Vec<u32> {
+        let mut assembler: Assembler = Assembler::default();
+        assembler
+            .op_string(
+                OP_ENTRY_POINT,
+                &[EXECUTION_MODEL_VERTEX, 1],
+                "main",
+                &[10, 11, 12],
+            )
+            .op_string(OP_NAME, &[10], "inPosition", &[])
+            .op_string(OP_NAME, &[11], "fragTexCoord", &[])
+            .op_string(OP_NAME, &[13], "ubo", &[])
+            .op_string(OP_NAME, &[14], "textures", &[])
+            .op_string(OP_NAME, &[15], "push", &[])
+            .op_string(OP_NAME, &[16], "LIGHTS", &[])
+            .op_string(OP_NAME, &[40], "Push", &[])
+            .op_string(OP_MEMBER_NAME, &[40, 1], "tint", &[])
+            .op(OP_DECORATE, &[10, DECORATION_LOCATION, 0])
+            .op(OP_DECORATE, &[11, DECORATION_LOCATION, 1])
+            .op(OP_MEMBER_DECORATE, &[30, 0, DECORATION_BUILT_IN, 0])
+            .op(OP_DECORATE, &[31, DECORATION_BLOCK])
+            .op(OP_MEMBER_DECORATE, &[31, 0, DECORATION_OFFSET, 0])
+            .op(OP_MEMBER_DECORATE, &[31, 0, DECORATION_MATRIX_STRIDE, 16])
+            .op(OP_DECORATE, &[13, DECORATION_DESCRIPTOR_SET, 0])
+            .op(OP_DECORATE, &[13, DECORATION_BINDING, 0])
+            .op(OP_DECORATE, &[14, DECORATION_DESCRIPTOR_SET, 1])
+            .op(OP_DECORATE, &[14, DECORATION_BINDING, 2])
+            .op(OP_DECORATE, &[40, DECORATION_BLOCK])
+            .op(OP_MEMBER_DECORATE, &[40, 0, DECORATION_OFFSET, 0])
+            .op(OP_MEMBER_DECORATE, &[40, 1, DECORATION_OFFSET, 16])
+            .op(OP_DECORATE, &[16, DECORATION_SPEC_ID, 3])
+            .op(OP_TYPE_FLOAT, &[20, 32])
+            .op(OP_TYPE_VECTOR, &[21, 20, 2])
+            .op(OP_TYPE_VECTOR, &[22, 20, 3])
+            .op(OP_TYPE_VECTOR, &[23, 20, 4])
+            .op(OP_TYPE_MATRIX, &[24, 23, 4])
+            .op(OP_TYPE_INT, &[25, 32, 1])
+            .op(OP_TYPE_INT, &[26, 32, 0])
+            .op(OP_CONSTANT, &[26, 27, 4])
+            .op(OP_SPEC_CONSTANT, &[25, 16, 4])
+            .op(OP_TYPE_STRUCT, &[30, 23])
+            .op(OP_TYPE_STRUCT, &[31, 24])
+            .op(OP_TYPE_IMAGE, &[32, 20, 1, 0, 0, 0, 1, 0])
+            .op(OP_TYPE_SAMPLED_IMAGE, &[33, 32])
+            .op(OP_TYPE_ARRAY, &[34, 33, 27])
+            .op(OP_TYPE_STRUCT, &[40, 20, 23])
+            .op(OP_TYPE_POINTER, &[50, STORAGE_INPUT, 22])
+            .op(OP_TYPE_POINTER, &[51, STORAGE_OUTPUT, 21])
+            .op(OP_TYPE_POINTER, &[52, STORAGE_OUTPUT, 30])
+            .op(OP_TYPE_POINTER, &[53, STORAGE_UNIFORM, 31])
+            .op(OP_TYPE_POINTER, &[54, STORAGE_UNIFORM_CONSTANT, 34])
+            .op(OP_TYPE_POINTER, &[55, STORAGE_PUSH_CONSTANT, 40])
+            .op(OP_VARIABLE, &[50, 10, STORAGE_INPUT])
+            .op(OP_VARIABLE, &[51, 11, STORAGE_OUTPUT])
+            .op(OP_VARIABLE, &[52, 12, STORAGE_OUTPUT])
+            .op(OP_VARIABLE, &[53, 13, STORAGE_UNIFORM])
+            .op(OP_VARIABLE, &[54, 14, STORAGE_UNIFORM_CONSTANT])
+            .op(OP_VARIABLE, &[55, 15, STORAGE_PUSH_CONSTANT]);
+        assembler.finish()
+    }
+
+    // layout(location = 1) in vec3 fragTexCoord;
+    fn fragment_module(location: u32) -> Vec<u32> {
+        let mut assembler: Assembler = Assembler::default();
+        assembler
+            .op_string(
+                OP_ENTRY_POINT,
+                &[EXECUTION_MODEL_FRAGMENT, 1],
+                "main",
+                &[10],
+            )
+            .op_string(OP_NAME, &[10], "fragTexCoord", &[])
+            .op(OP_DECORATE, &[10, DECORATION_LOCATION, location])
+            .op(OP_TYPE_FLOAT, &[20, 32])
+            .op(OP_TYPE_VECTOR, &[22, 20, 3])
+            .op(OP_TYPE_POINTER, &[50, STORAGE_INPUT, 22])
+            .op(OP_VARIABLE, &[50, 10, STORAGE_INPUT]);
+        assembler.finish()
+    }
+
+    fn vec(count: u32) -> Type {
+        Type::Vector {
+            component: Box::new(Type::Float { width: 32 }),
+            count,
+        }
+    }
+
+    #[test]
+    fn reflects_the_stage_interface() {
+        let reflection: ShaderReflection = reflect(&vertex_module(), "main").unwrap();
+
+        assert_eq!(reflection.entry_point.stage, ShaderStage::Vertex);
+        assert_eq!(
+            reflection.inputs,
+            [InterfaceVariable {
+                name: String::from("inPosition"),
+                location: 0,
+                ty: vec(3),
+            }]
+        );
+        // gl_PerVertex is a built-in block and is left out.
+        assert_eq!(reflection.outputs.len(), 1);
+        assert_eq!(reflection.outputs[0].ty.to_string(), "vec2");
+    }
+
+    #[test]
+    fn reflects_resources() {
+        let reflection: ShaderReflection = reflect(&vertex_module(), "main").unwrap();
+
+        assert_eq!(
+            reflection.descriptor_bindings,
+            [
+                DescriptorBinding {
+                    name: String::from("ubo"),
+                    set: 0,
+                    binding: 0,
+                    descriptor_type: DescriptorType::UniformBuffer,
+                    count: 1,
+                },
+                DescriptorBinding {
+                    name: String::from("textures"),
+                    set: 1,
+                    binding: 2,
+                    descriptor_type: DescriptorType::CombinedImageSampler,
+                    count: 4,
+                },
+            ]
+        );
+        assert_eq!(
+            reflection.push_constants,
+            Some(PushConstantRange {
+                name: String::from("push"),
+                offset: 0,
+                size: 32,
+            })
+        );
+        assert_eq!(
+            reflection.specialization_constants,
+            [SpecializationConstant {
+                name: String::from("LIGHTS"),
+                id: 3,
+                ty: Type::Int {
+                    width: 32,
+                    signed: true
+                },
+                default: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn checks_the_interface_between_stages() {
+        let vertex: ShaderReflection = reflect(&vertex_module(), "main").unwrap();
+
+        let fragment: ShaderReflection = reflect(&fragment_module(1), "main").unwrap();
+        assert_eq!(
+            check_interface(&vertex, &fragment),
+            Err(vec![String::from(
+                "fragment input fragTexCoord at location 1 is vec3 but vertex output \
+                 fragTexCoord is vec2"
+            )])
+        );
+
+        let fragment: ShaderReflection = reflect(&fragment_module(2), "main").unwrap();
+        assert_eq!(
+            check_interface(&vertex, &fragment).unwrap_err()[0],
+            "fragment input fragTexCoord at location 2 is not written by the vertex shader"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_modules() {
+        assert!(matches!(
+            reflect(&[0, 0, 0, 0, 0], "main"),
+            Err(SpirvError::Invalid { word: 0, .. })
+        ));
+
+        let mut words: Vec<u32> = vertex_module();
+        words.push(10 << 16 | OP_NAME);
+        assert!(matches!(
+            reflect(&words, "main"),
+            Err(SpirvError::Invalid { .. })
+        ));
+
+        assert_eq!(
+            reflect(&vertex_module(), "vs_main")
+                .unwrap_err()
+                .to_string(),
+            "no entry point named vs_main, the module has main"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::ffi::c_int;
+
+use crate::stb::{stbi_failure_reason, stbi_image_free, stbi_load, stbi_write_png, STBI_rgb_alpha};
+use crate::vulkan::{
+    vk_destroy_image, vk_destroy_image_view, vk_destroy_sampler, vk_free_memory,
+    VkDescriptorImageInfo, VkDevice, VkDeviceMemory, VkImage, VkImageLayout, VkImageView,
+    VkSampler,
+};
+
+use super::load::LoadError;
+
+use VkImageLayout::VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL;
+
+// Used when the model's materials do not name a diffuse texture.
+pub const TEXTURE_PATH: &str = "textures/texture.png";
+
+// Tightly packed RGBA8 pixels, decoded from a PNG or JPEG file or read back from the GPU.
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl ImageData {
+    pub fn load(path: &str) -> Result<Self, LoadError> {
+        let mut width: c_int = 0;
+        let mut height: c_int = 0;
+        let mut channels: c_int = 0;
+
+        let pixels: *mut u8 = stbi_load(
+            path,
+            &mut width,
+            &mut height,
+            &mut channels,
+            STBI_rgb_alpha as c_int,
+        );
+        if pixels.is_null() {
+            return Err(LoadError::invalid(path, stbi_failure_reason()));
+        }
+
+        // stb_image converted the file to 4 channels, whatever it had originally.
+        let size: usize = width as usize * height as usize * 4;
+        let data: Vec<u8> = unsafe { std::slice::from_raw_parts(pixels, size) }.to_vec();
+        stbi_image_free(pixels);
+
+        Ok(Self {
+            width: width as u32,
+            height: height as u32,
+            pixels: data,
+        })
+    }
+
+    pub fn save_png(&self, path: &str) -> Result<(), LoadError> {
+        let written: c_int = stbi_write_png(
+            path,
+            self.width as c_int,
+            self.height as c_int,
+            4,
+            &self.pixels,
+            self.width as c_int * 4,
+        );
+        if written == 0 {
+            return Err(LoadError::Write {
+                path: path.to_string(),
+                reason: String::from("stb_image_write failed"),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Swapchain images are usually BGRA; PNG files and textures expect RGBA.
+    pub fn swap_red_and_blue(&mut self) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    // Number of levels in a full mip chain, down to a 1x1 image.
+    pub fn mip_level_count(&self) -> u32 {
+        u32::BITS - self.width.max(self.height).max(1).leading_zeros()
+    }
+
+    // Box-filters the image to half its size. The color channels are averaged in linear space
+    // because the pixels are sRGB encoded; alpha is already linear.
+    pub fn downsample(&self) -> ImageData {
+        let width: u32 = (self.width / 2).max(1);
+        let height: u32 = (self.height / 2).max(1);
+        let mut pixels: Vec<u8> = Vec::with_capacity(width as usize * height as usize * 4);
+
+        for y in 0..height {
+            // Odd sizes drop the last row or column instead of reading past the edge.
+            let rows: [u32; 2] = [
+                (y * 2).min(self.height - 1),
+                (y * 2 + 1).min(self.height - 1),
+            ];
+            for x in 0..width {
+                let columns: [u32; 2] =
+                    [(x * 2).min(self.width - 1), (x * 2 + 1).min(self.width - 1)];
+                let mut sum: [f32; 4] = [0.0; 4];
+                for row in rows {
+                    for column in columns {
+                        let offset: usize =
+                            (row as usize * self.width as usize + column as usize) * 4;
+                        for (channel, total) in sum.iter_mut().enumerate() {
+                            let value: f32 = self.pixels[offset + channel] as f32 / 255.0;
+                            *total += if channel < 3 {
+                                srgb_to_linear(value)
+                            } else {
+                                value
+                            };
+                        }
+                    }
+                }
+                for (channel, total) in sum.iter().enumerate() {
+                    let average: f32 = total / 4.0;
+                    let value: f32 = if channel < 3 {
+                        linear_to_srgb(average)
+                    } else {
+                        average
+                    };
+                    pixels.push((value * 255.0).round().clamp(0.0, 255.0) as u8);
+                }
+            }
+        }
+
+        ImageData {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // Builds every mip level on the CPU, for formats the GPU can not blit with linear filtering.
+    pub fn mip_chain(self) -> Vec<ImageData> {
+        let mip_levels: u32 = self.mip_level_count();
+        let mut levels: Vec<ImageData> = Vec::with_capacity(mip_levels as usize);
+        levels.push(self);
+        for level in 1..mip_levels as usize {
+            let next: ImageData = levels[level - 1].downsample();
+            levels.push(next);
+        }
+
+        levels
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[derive(Debug)]
+pub struct Texture {
+    pub image: VkImage,
+    pub image_memory: VkDeviceMemory,
+    pub image_view: VkImageView,
+    pub sampler: VkSampler,
+}
+
+impl Texture {
+    pub fn descriptor_image_info(&self) -> VkDescriptorImageInfo {
+        VkDescriptorImageInfo {
+            sampler: self.sampler,
+            imageView: self.image_view,
+            imageLayout: VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+
+    pub fn destroy(&self, device: VkDevice) {
+        vk_destroy_sampler(device, self.sampler, std::ptr::null());
+        vk_destroy_image_view(device, self.image_view, std::ptr::null());
+        vk_destroy_image(device, self.image, std::ptr::null());
+        vk_free_memory(device, self.image_memory, std::ptr::null());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> ImageData {
+        ImageData {
+            width,
+            height,
+            pixels: pixel.repeat(width as usize * height as usize),
+        }
+    }
+
+    #[test]
+    fn counts_mip_levels_down_to_one_pixel() {
+        assert_eq!(image(1, 1, [0; 4]).mip_level_count(), 1);
+        assert_eq!(image(2, 2, [0; 4]).mip_level_count(), 2);
+        assert_eq!(image(512, 512, [0; 4]).mip_level_count(), 10);
+        assert_eq!(image(300, 17, [0; 4]).mip_level_count(), 9);
+
+        let sizes: Vec<(u32, u32)> = image(5, 2, [0; 4])
+            .mip_chain()
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect();
+        assert_eq!(sizes, [(5, 2), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn downsamples_odd_sizes() {
+        // The last column of a 3x3 image is dropped, so the white pixels never reach the result.
+        let mut odd: ImageData = image(3, 3, [0, 0, 0, 255]);
+        for row in 0..3 {
+            let offset: usize = (row * 3 + 2) * 4;
+            odd.pixels[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+        }
+        let half: ImageData = odd.downsample();
+        assert_eq!((half.width, half.height), (1, 1));
+        assert_eq!(half.pixels, [0, 0, 0, 255]);
+
+        // A 1x3 column keeps its width and halves its height.
+        let column: ImageData = image(1, 3, [10, 20, 30, 40]).downsample();
+        assert_eq!((column.width, column.height), (1, 1));
+        assert_eq!(column.pixels, [10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn averages_color_in_linear_space() {
+        let mut checker: ImageData = image(2, 1, [0, 0, 0, 0]);
+        checker.pixels[4..].copy_from_slice(&[255, 255, 255, 255]);
+
+        // Half the light is 188 in sRGB, not 128; alpha is averaged as it is.
+        assert_eq!(checker.downsample().pixels, [188, 188, 188, 128]);
+    }
+}
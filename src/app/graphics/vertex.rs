@@ -0,0 +1,64 @@
+use std::mem::{offset_of, size_of};
+
+use crate::vulkan::{
+    VkFormat, VkVertexInputAttributeDescription, VkVertexInputBindingDescription, VkVertexInputRate,
+};
+
+use VkFormat::VK_FORMAT_R32G32B32_SFLOAT;
+use VkFormat::VK_FORMAT_R32G32_SFLOAT;
+use VkVertexInputRate::VK_VERTEX_INPUT_RATE_VERTEX;
+
+// Describes how a vertex type is laid out in a vertex buffer so the pipeline can read it.
+pub trait Vertex: Copy {
+    fn binding_description() -> VkVertexInputBindingDescription;
+    fn attribute_descriptions() -> Vec<VkVertexInputAttributeDescription>;
+}
+
+// Vertex layout of loaded models; the color carries the diffuse color of the face's material.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub color: [f32; 3],
+    pub tex_coord: [f32; 2],
+}
+
+impl Vertex for MeshVertex {
+    fn binding_description() -> VkVertexInputBindingDescription {
+        VkVertexInputBindingDescription {
+            binding: 0,
+            stride: size_of::<MeshVertex>() as u32,
+            inputRate: VK_VERTEX_INPUT_RATE_VERTEX,
+        }
+    }
+
+    fn attribute_descriptions() -> Vec<VkVertexInputAttributeDescription> {
+        vec![
+            VkVertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: VK_FORMAT_R32G32B32_SFLOAT,
+                offset: offset_of!(MeshVertex, position) as u32,
+            },
+            VkVertexInputAttributeDescription {
+                location: 1,
+                binding: 0,
+                format: VK_FORMAT_R32G32B32_SFLOAT,
+                offset: offset_of!(MeshVertex, normal) as u32,
+            },
+            VkVertexInputAttributeDescription {
+                location: 2,
+                binding: 0,
+                format: VK_FORMAT_R32G32B32_SFLOAT,
+                offset: offset_of!(MeshVertex, color) as u32,
+            },
+            VkVertexInputAttributeDescription {
+                location: 3,
+                binding: 0,
+                format: VK_FORMAT_R32G32_SFLOAT,
+                offset: offset_of!(MeshVertex, tex_coord) as u32,
+            },
+        ]
+    }
+}
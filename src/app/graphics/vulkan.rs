@@ -1,72 +1,124 @@
-use std::cell::OnceCell;
+use std::cell::{Cell, OnceCell, Ref, RefCell};
 use std::collections::HashSet;
 use std::ffi::{c_char, c_float, c_int, c_void, CStr, CString};
-use std::fs::File;
-use std::io::Read;
+use std::fs;
 use std::mem::MaybeUninit;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::vec;
 
+use crate::glfw::GLFWwindow;
 use crate::glfw::{
     glfw_create_window, glfw_create_window_surface, glfw_destroy_window, glfw_get_framebuffer_size,
-    glfw_get_required_instance_extensions, glfw_init, glfw_poll_events, glfw_terminate,
-    glfw_window_hint, glfw_window_should_close, GLFW_CLIENT_API, GLFW_FALSE, GLFW_NO_API,
-    GLFW_RESIZABLE,
+    glfw_get_required_instance_extensions, glfw_get_window_user_pointer, glfw_init,
+    glfw_poll_events, glfw_set_framebuffer_size_callback, glfw_set_key_callback,
+    glfw_set_window_user_pointer, glfw_terminate, glfw_wait_events, glfw_window_hint,
+    glfw_window_should_close, GLFWframebuffersizefun, GLFWkeyfun, GLFW_CLIENT_API, GLFW_KEY_F12,
+    GLFW_KEY_T, GLFW_NO_API, GLFW_PRESS, GLFW_RESIZABLE, GLFW_TRUE,
 };
 use crate::utils::debug_mode;
+use crate::utils::watcher::FileWatcher;
 use crate::vulkan::{
-    vk_acquire_next_image_khr, vk_allocate_command_buffers, vk_begin_command_buffer,
-    vk_bit_message_severity, vk_bit_message_type, vk_cmd_begin_render_pass, vk_cmd_bind_pipeline,
-    vk_cmd_draw, vk_cmd_end_render_pass, vk_cmd_set_scissor, vk_cmd_set_viewport,
-    vk_create_command_pool, vk_create_device, vk_create_fence, vk_create_framebuffer,
-    vk_create_graphics_pipelines, vk_create_image_view, vk_create_instance,
-    vk_create_pipeline_layout, vk_create_render_pass, vk_create_semaphore, vk_create_shader_module,
-    vk_create_swapchain_khr, vk_destroy_command_pool, vk_destroy_device, vk_destroy_fence,
-    vk_destroy_framebuffer, vk_destroy_image_view, vk_destroy_instance, vk_destroy_pipeline,
-    vk_destroy_pipeline_layout, vk_destroy_render_pass, vk_destroy_semaphore,
-    vk_destroy_shader_module, vk_destroy_surface_khr, vk_destroy_swapchain_khr,
-    vk_device_wait_idle, vk_end_command_buffer, vk_enumerate_device_extension_properties,
-    vk_enumerate_instance_extension_properties, vk_enumerate_instance_layer_properties,
-    vk_enumerate_physical_devices, vk_get_device_queue, vk_get_instance_proc_addr,
-    vk_get_physical_device_features, vk_get_physical_device_properties,
-    vk_get_physical_device_queue_family_properties,
+    vk_acquire_next_image_khr, vk_allocate_command_buffers, vk_allocate_descriptor_sets,
+    vk_allocate_memory, vk_begin_command_buffer, vk_bind_buffer_memory, vk_bind_image_memory,
+    vk_bit_message_severity, vk_bit_message_type, vk_cmd_begin_render_pass,
+    vk_cmd_bind_descriptor_sets, vk_cmd_bind_index_buffer, vk_cmd_bind_pipeline,
+    vk_cmd_bind_vertex_buffers, vk_cmd_blit_image, vk_cmd_copy_buffer, vk_cmd_copy_buffer_to_image,
+    vk_cmd_copy_image_to_buffer, vk_cmd_draw_indexed, vk_cmd_end_render_pass,
+    vk_cmd_pipeline_barrier, vk_cmd_set_scissor, vk_cmd_set_viewport, vk_create_buffer,
+    vk_create_command_pool, vk_create_descriptor_pool, vk_create_descriptor_set_layout,
+    vk_create_device, vk_create_fence, vk_create_framebuffer, vk_create_graphics_pipelines,
+    vk_create_image, vk_create_image_view, vk_create_instance, vk_create_pipeline_layout,
+    vk_create_render_pass, vk_create_sampler, vk_create_semaphore, vk_create_shader_module,
+    vk_create_swapchain_khr, vk_destroy_buffer, vk_destroy_command_pool,
+    vk_destroy_descriptor_pool, vk_destroy_descriptor_set_layout, vk_destroy_device,
+    vk_destroy_fence, vk_destroy_framebuffer, vk_destroy_image, vk_destroy_image_view,
+    vk_destroy_instance, vk_destroy_pipeline, vk_destroy_pipeline_layout, vk_destroy_render_pass,
+    vk_destroy_semaphore, vk_destroy_shader_module, vk_destroy_surface_khr,
+    vk_destroy_swapchain_khr, vk_device_wait_idle, vk_end_command_buffer,
+    vk_enumerate_device_extension_properties, vk_enumerate_instance_extension_properties,
+    vk_enumerate_instance_layer_properties, vk_enumerate_physical_devices, vk_free_command_buffers,
+    vk_free_memory, vk_get_buffer_memory_requirements, vk_get_device_queue,
+    vk_get_image_memory_requirements, vk_get_instance_proc_addr, vk_get_physical_device_features,
+    vk_get_physical_device_format_properties, vk_get_physical_device_memory_properties,
+    vk_get_physical_device_properties, vk_get_physical_device_queue_family_properties,
     vk_get_physical_device_surface_capabilities_khr, vk_get_physical_device_surface_formats_khr,
     vk_get_physical_device_surface_present_modes_khr, vk_get_physical_device_surface_support_khr,
-    vk_get_swapchain_images_khr, vk_queue_present_khr, vk_queue_submit, vk_reset_command_buffer,
-    vk_reset_fences, vk_wait_for_fences, PFN_vkCreateDebugUtilsMessengerEXT,
+    vk_get_swapchain_images_khr, vk_map_memory, vk_queue_present_khr, vk_queue_submit,
+    vk_queue_wait_idle, vk_reset_command_buffer, vk_reset_fences, vk_unmap_memory,
+    vk_update_descriptor_sets, vk_wait_for_fences, PFN_vkCreateDebugUtilsMessengerEXT,
     PFN_vkDebugUtilsMessengerCallbackEXT, PFN_vkDestroyDebugUtilsMessengerEXT, VkAccessFlagBits,
-    VkAllocationCallbacks, VkApplicationInfo, VkAttachmentDescription, VkAttachmentLoadOp,
-    VkAttachmentReference, VkAttachmentStoreOp, VkBlendFactor, VkBlendOp, VkBool32,
-    VkClearColorValue, VkClearValue, VkColorComponentFlagBits, VkColorSpaceKHR, VkCommandBuffer,
-    VkCommandBufferAllocateInfo, VkCommandBufferBeginInfo, VkCommandBufferLevel, VkCommandPool,
-    VkCommandPoolCreateFlagBits, VkCommandPoolCreateInfo, VkComponentMapping, VkComponentSwizzle,
+    VkAccessFlags, VkAllocationCallbacks, VkApplicationInfo, VkAttachmentDescription,
+    VkAttachmentLoadOp, VkAttachmentReference, VkAttachmentStoreOp, VkBlendFactor, VkBlendOp,
+    VkBool32, VkBorderColor, VkBuffer, VkBufferCopy, VkBufferCreateInfo, VkBufferImageCopy,
+    VkBufferMemoryBarrier, VkBufferUsageFlagBits, VkBufferUsageFlags, VkClearColorValue,
+    VkClearDepthStencilValue, VkClearValue, VkColorComponentFlagBits, VkColorSpaceKHR,
+    VkCommandBuffer, VkCommandBufferAllocateInfo, VkCommandBufferBeginInfo, VkCommandBufferLevel,
+    VkCommandBufferUsageFlagBits, VkCommandPool, VkCommandPoolCreateFlagBits,
+    VkCommandPoolCreateInfo, VkCompareOp, VkComponentMapping, VkComponentSwizzle,
     VkCompositeAlphaFlagBitsKHR, VkCullModeFlagBits, VkDebugUtilsMessageSeverityFlagBitsEXT,
     VkDebugUtilsMessageTypeFlagBitsEXT, VkDebugUtilsMessageTypeFlagsEXT,
     VkDebugUtilsMessengerCallbackDataEXT, VkDebugUtilsMessengerCreateInfoEXT,
-    VkDebugUtilsMessengerEXT, VkDevice, VkDeviceCreateInfo, VkDeviceQueueCreateInfo,
-    VkDynamicState, VkExtensionProperties, VkExtent2D, VkFence, VkFenceCreateFlagBits,
-    VkFenceCreateInfo, VkFormat, VkFramebuffer, VkFramebufferCreateInfo, VkFrontFace,
-    VkGraphicsPipelineCreateInfo, VkImage, VkImageAspectFlagBits, VkImageLayout,
-    VkImageSubresourceRange, VkImageUsageFlagBits, VkImageView, VkImageViewCreateInfo,
-    VkImageViewType, VkInstance, VkInstanceCreateFlags, VkInstanceCreateInfo, VkLayerProperties,
-    VkLogicOp, VkOffset2D, VkPhysicalDevice, VkPhysicalDeviceFeatures, VkPhysicalDeviceProperties,
-    VkPipeline, VkPipelineBindPoint, VkPipelineColorBlendAttachmentState,
-    VkPipelineColorBlendStateCreateInfo, VkPipelineDynamicStateCreateInfo,
+    VkDebugUtilsMessengerEXT, VkDescriptorBufferInfo, VkDescriptorImageInfo, VkDescriptorPool,
+    VkDescriptorPoolCreateInfo, VkDescriptorPoolSize, VkDescriptorSet, VkDescriptorSetAllocateInfo,
+    VkDescriptorSetLayout, VkDescriptorSetLayoutBinding, VkDescriptorSetLayoutCreateInfo, VkDevice,
+    VkDeviceCreateInfo, VkDeviceMemory, VkDeviceQueueCreateInfo, VkDeviceSize, VkDynamicState,
+    VkExtensionProperties, VkExtent2D, VkExtent3D, VkFence, VkFenceCreateFlagBits,
+    VkFenceCreateInfo, VkFilter, VkFormat, VkFormatFeatureFlagBits, VkFormatFeatureFlags,
+    VkFormatProperties, VkFramebuffer, VkFramebufferCreateInfo, VkFrontFace,
+    VkGraphicsPipelineCreateInfo, VkImage, VkImageAspectFlagBits, VkImageAspectFlags, VkImageBlit,
+    VkImageCreateInfo, VkImageLayout, VkImageMemoryBarrier, VkImageSubresourceLayers,
+    VkImageSubresourceRange, VkImageTiling, VkImageType, VkImageUsageFlagBits, VkImageUsageFlags,
+    VkImageView, VkImageViewCreateInfo, VkImageViewType, VkIndexType, VkInstance,
+    VkInstanceCreateFlags, VkInstanceCreateInfo, VkLayerProperties, VkLogicOp,
+    VkMemoryAllocateInfo, VkMemoryHeapFlagBits, VkMemoryPropertyFlagBits, VkMemoryPropertyFlags,
+    VkMemoryRequirements, VkOffset2D, VkOffset3D, VkPhysicalDevice, VkPhysicalDeviceFeatures,
+    VkPhysicalDeviceMemoryProperties, VkPhysicalDeviceProperties, VkPhysicalDeviceType, VkPipeline,
+    VkPipelineBindPoint, VkPipelineColorBlendAttachmentState, VkPipelineColorBlendStateCreateInfo,
+    VkPipelineDepthStencilStateCreateInfo, VkPipelineDynamicStateCreateInfo,
     VkPipelineInputAssemblyStateCreateInfo, VkPipelineLayout, VkPipelineLayoutCreateInfo,
     VkPipelineMultisampleStateCreateInfo, VkPipelineRasterizationStateCreateInfo,
     VkPipelineShaderStageCreateInfo, VkPipelineStageFlagBits, VkPipelineStageFlags,
     VkPipelineVertexInputStateCreateInfo, VkPipelineViewportStateCreateInfo, VkPolygonMode,
-    VkPresentInfoKHR, VkPresentModeKHR, VkPrimitiveTopology, VkQueue, VkQueueFamilyProperties,
-    VkQueueFlagBits, VkRect2D, VkRenderPass, VkRenderPassBeginInfo, VkRenderPassCreateInfo,
-    VkResult, VkSampleCountFlagBits, VkSemaphore, VkSemaphoreCreateInfo, VkShaderModule,
-    VkShaderModuleCreateInfo, VkShaderStageFlagBits, VkSharingMode, VkStructureType, VkSubmitInfo,
-    VkSubpassContents, VkSubpassDependency, VkSubpassDescription, VkSurfaceCapabilitiesKHR,
-    VkSurfaceFormatKHR, VkSurfaceKHR, VkSwapchainCreateInfoKHR, VkSwapchainKHR, VkViewport,
+    VkPresentInfoKHR, VkPresentModeKHR, VkPrimitiveTopology, VkPushConstantRange, VkQueue,
+    VkQueueFamilyProperties, VkQueueFlagBits, VkRect2D, VkRenderPass, VkRenderPassBeginInfo,
+    VkRenderPassCreateInfo, VkResult, VkSampleCountFlagBits, VkSampler, VkSamplerAddressMode,
+    VkSamplerCreateInfo, VkSamplerMipmapMode, VkSemaphore, VkSemaphoreCreateInfo, VkShaderModule,
+    VkShaderModuleCreateInfo, VkShaderStageFlagBits, VkSharingMode, VkSpecializationInfo,
+    VkSpecializationMapEntry, VkStencilOpState, VkStructureType, VkSubmitInfo, VkSubpassContents,
+    VkSubpassDependency, VkSubpassDescription, VkSurfaceCapabilitiesKHR, VkSurfaceFormatKHR,
+    VkSurfaceKHR, VkSwapchainCreateInfoKHR, VkSwapchainKHR, VkVertexInputAttributeDescription,
+    VkVertexInputBindingDescription, VkViewport, VkWriteDescriptorSet, VulkanError,
     VK_API_VERSION_1_0, VK_EXT_DEBUG_UTILS_EXTENSION_NAME, VK_FALSE,
-    VK_KHR_SWAPCHAIN_EXTENSION_NAME, VK_MAKE_API_VERSION, VK_SUBPASS_EXTERNAL, VK_TRUE,
+    VK_KHR_SWAPCHAIN_EXTENSION_NAME, VK_MAKE_API_VERSION, VK_QUEUE_FAMILY_IGNORED,
+    VK_SUBPASS_EXTERNAL, VK_TRUE,
 };
-use crate::{glfw::GLFWwindow, utils};
+
+use super::ktx2;
+use super::math::{self, Mat4};
+use super::mesh::{self, Mesh};
+use super::reflect::{self, LayoutBinding, PipelineShaders};
+use super::shader::{
+    ShaderCompiler, ENTRY_POINT, FRAGMENT_SHADER, FRAGMENT_SHADER_PATH, SHADER_DIR, VERTEX_SHADER,
+    VERTEX_SHADER_PATH,
+};
+use super::shader_registry::ShaderRegistry;
+use super::specialization::{
+    PipelineCache, PipelineSpecialization, SpecializationConstants, SpecializationValue,
+};
+use super::spirv::{DescriptorBinding, DescriptorType};
+use super::texture::{ImageData, Texture, TEXTURE_PATH};
+use super::vertex::{MeshVertex, Vertex};
 
 use VkAccessFlagBits::VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT;
+use VkAccessFlagBits::VK_ACCESS_DEPTH_STENCIL_ATTACHMENT_WRITE_BIT;
+use VkAccessFlagBits::VK_ACCESS_HOST_READ_BIT;
+use VkAccessFlagBits::VK_ACCESS_INDEX_READ_BIT;
+use VkAccessFlagBits::VK_ACCESS_MEMORY_READ_BIT;
+use VkAccessFlagBits::VK_ACCESS_SHADER_READ_BIT;
+use VkAccessFlagBits::VK_ACCESS_TRANSFER_READ_BIT;
+use VkAccessFlagBits::VK_ACCESS_TRANSFER_WRITE_BIT;
+use VkAccessFlagBits::VK_ACCESS_UNIFORM_READ_BIT;
+use VkAccessFlagBits::VK_ACCESS_VERTEX_ATTRIBUTE_READ_BIT;
 use VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR;
 use VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE;
 use VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE;
@@ -76,13 +128,23 @@ use VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC_ALPHA;
 use VkBlendFactor::VK_BLEND_FACTOR_SRC_ALPHA;
 use VkBlendFactor::VK_BLEND_FACTOR_ZERO;
 use VkBlendOp::VK_BLEND_OP_ADD;
+use VkBorderColor::VK_BORDER_COLOR_INT_OPAQUE_BLACK;
+use VkBufferUsageFlagBits::VK_BUFFER_USAGE_INDEX_BUFFER_BIT;
+use VkBufferUsageFlagBits::VK_BUFFER_USAGE_TRANSFER_DST_BIT;
+use VkBufferUsageFlagBits::VK_BUFFER_USAGE_TRANSFER_SRC_BIT;
+use VkBufferUsageFlagBits::VK_BUFFER_USAGE_UNIFORM_BUFFER_BIT;
+use VkBufferUsageFlagBits::VK_BUFFER_USAGE_VERTEX_BUFFER_BIT;
 use VkColorComponentFlagBits::VK_COLOR_COMPONENT_A_BIT;
 use VkColorComponentFlagBits::VK_COLOR_COMPONENT_B_BIT;
 use VkColorComponentFlagBits::VK_COLOR_COMPONENT_G_BIT;
 use VkColorComponentFlagBits::VK_COLOR_COMPONENT_R_BIT;
 use VkColorSpaceKHR::VK_COLOR_SPACE_SRGB_NONLINEAR_KHR;
 use VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_PRIMARY;
+use VkCommandBufferUsageFlagBits::VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT;
 use VkCommandPoolCreateFlagBits::VK_COMMAND_POOL_CREATE_RESET_COMMAND_BUFFER_BIT;
+use VkCommandPoolCreateFlagBits::VK_COMMAND_POOL_CREATE_TRANSIENT_BIT;
+use VkCompareOp::VK_COMPARE_OP_ALWAYS;
+use VkCompareOp::VK_COMPARE_OP_LESS;
 use VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY;
 use VkCompositeAlphaFlagBitsKHR::VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR;
 use VkCullModeFlagBits::VK_CULL_MODE_BACK_BIT;
@@ -95,36 +157,91 @@ use VkDebugUtilsMessageTypeFlagBitsEXT::VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_B
 use VkDynamicState::VK_DYNAMIC_STATE_SCISSOR;
 use VkDynamicState::VK_DYNAMIC_STATE_VIEWPORT;
 use VkFenceCreateFlagBits::VK_FENCE_CREATE_SIGNALED_BIT;
+use VkFilter::VK_FILTER_LINEAR;
 use VkFormat::VK_FORMAT_B8G8R8A8_SRGB;
-use VkFrontFace::VK_FRONT_FACE_CLOCKWISE;
+use VkFormat::VK_FORMAT_B8G8R8A8_UNORM;
+use VkFormat::VK_FORMAT_D24_UNORM_S8_UINT;
+use VkFormat::VK_FORMAT_D32_SFLOAT;
+use VkFormat::VK_FORMAT_D32_SFLOAT_S8_UINT;
+use VkFormat::VK_FORMAT_R8G8B8A8_SRGB;
+use VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+use VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT;
+use VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT;
+use VkFrontFace::VK_FRONT_FACE_COUNTER_CLOCKWISE;
 use VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT;
+use VkImageAspectFlagBits::VK_IMAGE_ASPECT_DEPTH_BIT;
 use VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL;
+use VkImageLayout::VK_IMAGE_LAYOUT_DEPTH_STENCIL_ATTACHMENT_OPTIMAL;
 use VkImageLayout::VK_IMAGE_LAYOUT_PRESENT_SRC_KHR;
+use VkImageLayout::VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL;
+use VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL;
+use VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL;
 use VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED;
+use VkImageTiling::VK_IMAGE_TILING_LINEAR;
+use VkImageTiling::VK_IMAGE_TILING_OPTIMAL;
+use VkImageType::VK_IMAGE_TYPE_2D;
 use VkImageUsageFlagBits::VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT;
+use VkImageUsageFlagBits::VK_IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT;
+use VkImageUsageFlagBits::VK_IMAGE_USAGE_SAMPLED_BIT;
+use VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_DST_BIT;
+use VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_SRC_BIT;
+use VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT;
 use VkImageViewType::VK_IMAGE_VIEW_TYPE_2D;
+use VkIndexType::VK_INDEX_TYPE_UINT32;
 use VkLogicOp::VK_LOGIC_OP_COPY;
+use VkMemoryHeapFlagBits::VK_MEMORY_HEAP_DEVICE_LOCAL_BIT;
+use VkMemoryPropertyFlagBits::VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT;
+use VkMemoryPropertyFlagBits::VK_MEMORY_PROPERTY_HOST_COHERENT_BIT;
+use VkMemoryPropertyFlagBits::VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT;
+use VkPhysicalDeviceType::VK_PHYSICAL_DEVICE_TYPE_CPU;
+use VkPhysicalDeviceType::VK_PHYSICAL_DEVICE_TYPE_DISCRETE_GPU;
+use VkPhysicalDeviceType::VK_PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU;
+use VkPhysicalDeviceType::VK_PHYSICAL_DEVICE_TYPE_VIRTUAL_GPU;
 use VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_ALL_COMMANDS_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT;
 use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_EARLY_FRAGMENT_TESTS_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_HOST_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_LATE_FRAGMENT_TESTS_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TRANSFER_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_VERTEX_INPUT_BIT;
+use VkPipelineStageFlagBits::VK_PIPELINE_STAGE_VERTEX_SHADER_BIT;
 use VkPolygonMode::VK_POLYGON_MODE_FILL;
 use VkPresentModeKHR::VK_PRESENT_MODE_FIFO_KHR;
 use VkPresentModeKHR::VK_PRESENT_MODE_MAILBOX_KHR;
 use VkPrimitiveTopology::VK_PRIMITIVE_TOPOLOGY_TRIANGLE_LIST;
 use VkQueueFlagBits::VK_QUEUE_GRAPHICS_BIT;
+use VkQueueFlagBits::VK_QUEUE_TRANSFER_BIT;
+use VkResult::VK_ERROR_OUT_OF_DATE_KHR;
+use VkResult::VK_SUBOPTIMAL_KHR;
 use VkResult::VK_SUCCESS;
 use VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT;
+use VkSampleCountFlagBits::VK_SAMPLE_COUNT_2_BIT;
+use VkSampleCountFlagBits::VK_SAMPLE_COUNT_4_BIT;
+use VkSampleCountFlagBits::VK_SAMPLE_COUNT_8_BIT;
+use VkSamplerAddressMode::VK_SAMPLER_ADDRESS_MODE_REPEAT;
+use VkSamplerMipmapMode::VK_SAMPLER_MIPMAP_MODE_LINEAR;
 use VkShaderStageFlagBits::VK_SHADER_STAGE_FRAGMENT_BIT;
 use VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT;
 use VkSharingMode::VK_SHARING_MODE_CONCURRENT;
 use VkSharingMode::VK_SHARING_MODE_EXCLUSIVE;
 use VkStructureType::{
-    VK_STRUCTURE_TYPE_APPLICATION_INFO, VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
+    VK_STRUCTURE_TYPE_APPLICATION_INFO, VK_STRUCTURE_TYPE_BUFFER_CREATE_INFO,
+    VK_STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER, VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
     VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO, VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
-    VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT, VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
+    VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
+    VK_STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO, VK_STRUCTURE_TYPE_DESCRIPTOR_SET_ALLOCATE_INFO,
+    VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_CREATE_INFO, VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
     VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO, VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
     VK_STRUCTURE_TYPE_FRAMEBUFFER_CREATE_INFO, VK_STRUCTURE_TYPE_GRAPHICS_PIPELINE_CREATE_INFO,
+    VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO, VK_STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER,
     VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO, VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+    VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
     VK_STRUCTURE_TYPE_PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+    VK_STRUCTURE_TYPE_PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
     VK_STRUCTURE_TYPE_PIPELINE_DYNAMIC_STATE_CREATE_INFO,
     VK_STRUCTURE_TYPE_PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
     VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO,
@@ -134,8 +251,9 @@ use VkStructureType::{
     VK_STRUCTURE_TYPE_PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
     VK_STRUCTURE_TYPE_PIPELINE_VIEWPORT_STATE_CREATE_INFO, VK_STRUCTURE_TYPE_PRESENT_INFO_KHR,
     VK_STRUCTURE_TYPE_RENDER_PASS_BEGIN_INFO, VK_STRUCTURE_TYPE_RENDER_PASS_CREATE_INFO,
-    VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO, VK_STRUCTURE_TYPE_SHADER_MODULE_CREATE_INFO,
-    VK_STRUCTURE_TYPE_SUBMIT_INFO, VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR,
+    VK_STRUCTURE_TYPE_SAMPLER_CREATE_INFO, VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+    VK_STRUCTURE_TYPE_SHADER_MODULE_CREATE_INFO, VK_STRUCTURE_TYPE_SUBMIT_INFO,
+    VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR, VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
 };
 use VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE;
 
@@ -159,11 +277,197 @@ pub extern "C" fn debug_callback(
     VK_FALSE
 }
 
-use super::api::{GraphicApi, Window};
+// How many frames the CPU may record ahead of the GPU before waiting on a fence.
+const MAX_FRAMES_IN_FLIGHT: usize = 2;
+
+// Same texture with its mip levels already built, used when KTX2 textures are enabled.
+const KTX2_TEXTURE_PATH: &str = "textures/texture.ktx2";
+
+// Headless frames are rendered in the byte order PNG files use, so they are written as read back.
+const OFFSCREEN_FORMAT: VkFormat = VK_FORMAT_R8G8B8A8_SRGB;
+// Headless frames are written here as frame_0000.png, frame_0001.png, ...
+const HEADLESS_OUTPUT_DIR: &str = "frames";
+// Headless animation advances by a fixed step so a given frame always renders the same image.
+const HEADLESS_FRAME_TIME: f32 = 1.0 / 60.0;
+
+// Pressing this key saves the window contents to SCREENSHOT_DIR.
+const SCREENSHOT_KEY: c_int = GLFW_KEY_F12 as c_int;
+const SCREENSHOT_DIR: &str = "screenshots";
+// Pressing this key switches between the textured and the untextured pipeline.
+const TEXTURE_TOGGLE_KEY: c_int = GLFW_KEY_T as c_int;
+
+// How often hot reload looks for changed files in SHADER_DIR.
+const SHADER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// The descriptors the renderer writes into set 0; the shaders may use any of them.
+const UNIFORM_BUFFER_BINDING: u32 = 0;
+const TEXTURE_BINDING: u32 = 1;
+
+// The specialization constants declared in src/shaders, by constant_id.
+const TEXTURED_CONSTANT_ID: u32 = 1;
+
+extern "C" fn framebuffer_resize_callback(window: *mut GLFWwindow, _width: c_int, _height: c_int) {
+    let app = glfw_get_window_user_pointer(window) as *const VulkanApi;
+    if app.is_null() {
+        return;
+    }
+
+    unsafe {
+        (*app).framebuffer_resized.set(true);
+    }
+}
+
+extern "C" fn key_callback(
+    window: *mut GLFWwindow,
+    key: c_int,
+    _scancode: c_int,
+    action: c_int,
+    _mods: c_int,
+) {
+    let app = glfw_get_window_user_pointer(window) as *const VulkanApi;
+    if app.is_null() || action != GLFW_PRESS as c_int {
+        return;
+    }
+
+    unsafe {
+        match key {
+            // Copied from the next frame, see draw_frame.
+            SCREENSHOT_KEY => (*app).screenshot_requested.set(true),
+            // Switched before the next frame is recorded.
+            TEXTURE_TOGGLE_KEY => (*app).texture_toggle_requested.set(true),
+            _ => {}
+        }
+    }
+}
+
+// The constants the renderer starts with.
+fn default_specialization() -> PipelineSpecialization {
+    PipelineSpecialization {
+        vertex: SpecializationConstants::new(),
+        fragment: SpecializationConstants::new().with(TEXTURED_CONSTANT_ID, true),
+    }
+}
+
+// Shaders may only use the descriptors the renderer writes into its descriptor sets.
+fn is_provided_binding(layout_binding: &LayoutBinding) -> bool {
+    let binding: &DescriptorBinding = &layout_binding.binding;
+    binding.set == 0
+        && binding.count == 1
+        && matches!(
+            (binding.binding, binding.descriptor_type),
+            (UNIFORM_BUFFER_BINDING, DescriptorType::UniformBuffer)
+                | (TEXTURE_BINDING, DescriptorType::CombinedImageSampler)
+        )
+}
+
+// Only the sample counts the app may ask for; larger ones are rarely worth their memory.
+fn sample_count_flag(samples: u32) -> Option<VkSampleCountFlagBits> {
+    match samples {
+        1 => Some(VK_SAMPLE_COUNT_1_BIT),
+        2 => Some(VK_SAMPLE_COUNT_2_BIT),
+        4 => Some(VK_SAMPLE_COUNT_4_BIT),
+        8 => Some(VK_SAMPLE_COUNT_8_BIT),
+        _ => None,
+    }
+}
+
+// Access and stages through which the graphics queue first reads a buffer of the given usage.
+fn buffer_usage_access(usage: VkBufferUsageFlags) -> (VkAccessFlags, VkPipelineStageFlags) {
+    if usage & (VK_BUFFER_USAGE_VERTEX_BUFFER_BIT as u32) != 0 {
+        (
+            VK_ACCESS_VERTEX_ATTRIBUTE_READ_BIT as u32,
+            VK_PIPELINE_STAGE_VERTEX_INPUT_BIT as u32,
+        )
+    } else if usage & (VK_BUFFER_USAGE_INDEX_BUFFER_BIT as u32) != 0 {
+        (
+            VK_ACCESS_INDEX_READ_BIT as u32,
+            VK_PIPELINE_STAGE_VERTEX_INPUT_BIT as u32,
+        )
+    } else if usage & (VK_BUFFER_USAGE_UNIFORM_BUFFER_BIT as u32) != 0 {
+        (
+            VK_ACCESS_UNIFORM_READ_BIT as u32,
+            VK_PIPELINE_STAGE_VERTEX_SHADER_BIT as u32
+                | VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT as u32,
+        )
+    } else {
+        (
+            VK_ACCESS_MEMORY_READ_BIT as u32,
+            VK_PIPELINE_STAGE_ALL_COMMANDS_BIT as u32,
+        )
+    }
+}
+
+fn buffer_memory_barrier(
+    buffer: VkBuffer,
+    size: VkDeviceSize,
+    src_access_mask: VkAccessFlags,
+    dst_access_mask: VkAccessFlags,
+    src_queue_family: u32,
+    dst_queue_family: u32,
+) -> VkBufferMemoryBarrier {
+    VkBufferMemoryBarrier {
+        sType: VK_STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER,
+        srcAccessMask: src_access_mask,
+        dstAccessMask: dst_access_mask,
+        srcQueueFamilyIndex: src_queue_family,
+        dstQueueFamilyIndex: dst_queue_family,
+        buffer,
+        offset: 0,
+        size,
+        pNext: std::ptr::null(),
+    }
+}
+
+fn color_subresource_range(base_mip_level: u32, level_count: u32) -> VkImageSubresourceRange {
+    VkImageSubresourceRange {
+        aspectMask: VK_IMAGE_ASPECT_COLOR_BIT as u32,
+        baseMipLevel: base_mip_level,
+        levelCount: level_count,
+        baseArrayLayer: 0,
+        layerCount: 1,
+    }
+}
+
+fn color_subresource_layers(mip_level: u32) -> VkImageSubresourceLayers {
+    VkImageSubresourceLayers {
+        aspectMask: VK_IMAGE_ASPECT_COLOR_BIT as u32,
+        mipLevel: mip_level,
+        baseArrayLayer: 0,
+        layerCount: 1,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn image_memory_barrier(
+    image: VkImage,
+    subresource_range: VkImageSubresourceRange,
+    old_layout: VkImageLayout,
+    new_layout: VkImageLayout,
+    src_access_mask: VkAccessFlags,
+    dst_access_mask: VkAccessFlags,
+    src_queue_family: u32,
+    dst_queue_family: u32,
+) -> VkImageMemoryBarrier {
+    VkImageMemoryBarrier {
+        sType: VK_STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER,
+        srcAccessMask: src_access_mask,
+        dstAccessMask: dst_access_mask,
+        oldLayout: old_layout,
+        newLayout: new_layout,
+        srcQueueFamilyIndex: src_queue_family,
+        dstQueueFamilyIndex: dst_queue_family,
+        image,
+        subresourceRange: subresource_range,
+        pNext: std::ptr::null(),
+    }
+}
+
+use super::api::{GpuSelection, GraphicApi, GraphicError, GraphicsSettings, Window};
 
 struct QueueFamilyIndices {
     graphics_family: Option<u32>,
     present_family: Option<u32>,
+    transfer_family: Option<u32>,
 }
 
 impl QueueFamilyIndices {
@@ -171,6 +475,7 @@ impl QueueFamilyIndices {
         Self {
             graphics_family: None,
             present_family: None,
+            transfer_family: None,
         }
     }
 
@@ -185,6 +490,62 @@ impl QueueFamilyIndices {
     fn get_present_family(&self) -> u32 {
         self.present_family.expect("Present family is null")
     }
+
+    // Graphics queues always support transfers, so they are used when there is no dedicated family.
+    fn get_transfer_family(&self) -> u32 {
+        self.transfer_family
+            .unwrap_or_else(|| self.get_graphics_family())
+    }
+
+    fn has_dedicated_transfer_family(&self) -> bool {
+        self.transfer_family.is_some()
+    }
+}
+
+// Suitable GPUs are compared field by field: the device type decides, then the optional features
+// the renderer can use, then the amount of device-local memory.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct DeviceScore {
+    type_rank: u32,
+    optional_features: u32,
+    device_local_memory: VkDeviceSize,
+}
+
+impl DeviceScore {
+    fn type_rank(device_type: VkPhysicalDeviceType) -> u32 {
+        match device_type {
+            VK_PHYSICAL_DEVICE_TYPE_DISCRETE_GPU => 4,
+            VK_PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU => 3,
+            VK_PHYSICAL_DEVICE_TYPE_VIRTUAL_GPU => 2,
+            VK_PHYSICAL_DEVICE_TYPE_CPU => 1,
+            _ => 0,
+        }
+    }
+}
+
+fn device_name(properties: &VkPhysicalDeviceProperties) -> String {
+    unsafe { CStr::from_ptr(properties.deviceName.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn device_type_name(device_type: VkPhysicalDeviceType) -> &'static str {
+    match device_type {
+        VK_PHYSICAL_DEVICE_TYPE_DISCRETE_GPU => "discrete",
+        VK_PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU => "integrated",
+        VK_PHYSICAL_DEVICE_TYPE_VIRTUAL_GPU => "virtual",
+        VK_PHYSICAL_DEVICE_TYPE_CPU => "CPU",
+        _ => "other",
+    }
+}
+
+// Matches the UniformBufferObject block at binding 0 of shader.vert.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UniformBufferObject {
+    model: Mat4,
+    view: Mat4,
+    proj: Mat4,
 }
 
 struct SwapChainSupportDetails {
@@ -196,30 +557,82 @@ struct SwapChainSupportDetails {
 pub struct VulkanApi {
     width: usize,
     height: usize,
+    settings: GraphicsSettings,
     window: OnceCell<*mut GLFWwindow>,
     validation_layers: Vec<CString>,
     instance: OnceCell<VkInstance>,
     debug_messenger: OnceCell<VkDebugUtilsMessengerEXT>,
     surface: OnceCell<VkSurfaceKHR>,
     physical_device: OnceCell<VkPhysicalDevice>,
+    msaa_samples: OnceCell<VkSampleCountFlagBits>,
     device_extensions: Vec<CString>,
     device: OnceCell<VkDevice>,
     graphics_queue: OnceCell<VkQueue>,
     present_queue: OnceCell<VkQueue>,
-    swapchain: OnceCell<VkSwapchainKHR>,
-    swapchain_images: OnceCell<Vec<VkImage>>,
-    swapchain_image_format: OnceCell<VkFormat>,
-    swapchain_extent: OnceCell<VkExtent2D>,
-    swapchain_image_views: OnceCell<Vec<VkImageView>>,
+    transfer_queue: OnceCell<VkQueue>,
+    swapchain: Cell<Option<VkSwapchainKHR>>,
+    swapchain_images: RefCell<Option<Vec<VkImage>>>,
+    swapchain_image_format: Cell<Option<VkFormat>>,
+    // Whether the surface lets the swapchain images be copied from, which screenshots need.
+    swapchain_transfer_src: Cell<bool>,
+    swapchain_extent: Cell<Option<VkExtent2D>>,
+    swapchain_image_views: RefCell<Option<Vec<VkImageView>>>,
+    // Headless mode has no swapchain; the swapchain fields above describe the offscreen image.
+    offscreen_image_memory: Cell<Option<VkDeviceMemory>>,
+    readback_buffer: OnceCell<VkBuffer>,
+    readback_buffer_memory: OnceCell<VkDeviceMemory>,
+    color_image: Cell<Option<VkImage>>,
+    color_image_memory: Cell<Option<VkDeviceMemory>>,
+    color_image_view: Cell<Option<VkImageView>>,
+    depth_format: OnceCell<VkFormat>,
+    depth_image: Cell<Option<VkImage>>,
+    depth_image_memory: Cell<Option<VkDeviceMemory>>,
+    depth_image_view: Cell<Option<VkImageView>>,
     render_pass: OnceCell<VkRenderPass>,
+    descriptor_set_layout: OnceCell<VkDescriptorSetLayout>,
     pipeline_layout: OnceCell<VkPipelineLayout>,
-    graphics_pipeline: OnceCell<VkPipeline>,
-    swapchain_framebuffers: OnceCell<Vec<VkFramebuffer>>,
+    // Replaced when the shaders are hot reloaded or the specialization changes.
+    graphics_pipeline: Cell<Option<VkPipeline>>,
+    // Every pipeline built from the current shaders, graphics_pipeline among them, by the
+    // constants it was specialized with.
+    specialized_pipelines: RefCell<PipelineCache<VkPipeline>>,
+    // The constants of graphics_pipeline.
+    specialization: RefCell<PipelineSpecialization>,
+    shader_registry: ShaderRegistry,
+    // Only used by hot reload, the shaders the renderer starts with are built in.
+    shader_compiler: ShaderCompiler,
+    // The shaders of the current pipeline; the layouts above are derived from them.
+    pipeline_shaders: RefCell<Option<PipelineShaders>>,
+    shader_watcher: OnceCell<FileWatcher>,
+    swapchain_framebuffers: RefCell<Option<Vec<VkFramebuffer>>>,
     command_pool: OnceCell<VkCommandPool>,
-    command_buffer: OnceCell<VkCommandBuffer>,
-    image_available_semaphore: OnceCell<VkSemaphore>,
-    render_finished_semaphore: OnceCell<VkSemaphore>,
-    in_flight_fence: OnceCell<VkFence>,
+    transfer_command_pool: OnceCell<VkCommandPool>,
+    vertex_buffer: OnceCell<VkBuffer>,
+    vertex_buffer_memory: OnceCell<VkDeviceMemory>,
+    index_buffer: OnceCell<VkBuffer>,
+    index_buffer_memory: OnceCell<VkDeviceMemory>,
+    model: OnceCell<Mesh>,
+    texture: OnceCell<Texture>,
+    uniform_buffers: OnceCell<Vec<VkBuffer>>,
+    uniform_buffers_memory: OnceCell<Vec<VkDeviceMemory>>,
+    uniform_buffers_mapped: OnceCell<Vec<*mut c_void>>,
+    descriptor_pool: OnceCell<VkDescriptorPool>,
+    descriptor_sets: OnceCell<Vec<VkDescriptorSet>>,
+    command_buffers: OnceCell<Vec<VkCommandBuffer>>,
+    image_available_semaphores: OnceCell<Vec<VkSemaphore>>,
+    render_finished_semaphores: OnceCell<Vec<VkSemaphore>>,
+    in_flight_fences: OnceCell<Vec<VkFence>>,
+    images_in_flight: RefCell<Vec<VkFence>>,
+    current_frame: Cell<usize>,
+    frames_rendered: Cell<u32>,
+    screenshot_requested: Cell<bool>,
+    // Where the next frame is saved, see capture_screenshot.
+    screenshot_path: RefCell<Option<String>>,
+    texture_toggle_requested: Cell<bool>,
+    framebuffer_resized: Cell<bool>,
+    sampler_anisotropy: Cell<bool>,
+    sample_rate_shading: Cell<bool>,
+    start_time: Instant,
 }
 
 impl VulkanApi {
@@ -231,10 +644,6 @@ impl VulkanApi {
         *self.instance.get().expect("Instance is null")
     }
 
-    fn _get_debug_messenger(&self) -> VkDebugUtilsMessengerEXT {
-        *self.debug_messenger.get().expect("Debug messenger is null")
-    }
-
     fn _get_surface(&self) -> VkSurfaceKHR {
         *self.surface.get().expect("Surface is null")
     }
@@ -255,132 +664,290 @@ impl VulkanApi {
         *self.present_queue.get().expect("Present queue is null")
     }
 
+    fn _get_transfer_queue(&self) -> VkQueue {
+        *self.transfer_queue.get().expect("Transfer queue is null")
+    }
+
     fn _get_swapchain(&self) -> VkSwapchainKHR {
-        *self.swapchain.get().expect("Swapchain is null")
+        self.swapchain.get().expect("Swapchain is null")
     }
 
-    fn _get_swapchain_images(&self) -> &Vec<VkImage> {
-        self.swapchain_images
-            .get()
-            .expect("Swapchain images is null")
+    fn _get_swapchain_images(&self) -> Ref<Vec<VkImage>> {
+        Ref::map(self.swapchain_images.borrow(), |swapchain_images| {
+            swapchain_images.as_ref().expect("Swapchain images is null")
+        })
     }
 
     fn _get_swapchain_image_format(&self) -> VkFormat {
-        *self
-            .swapchain_image_format
+        self.swapchain_image_format
             .get()
             .expect("Swapchain image format is null")
     }
 
     fn _get_swapchain_extent(&self) -> VkExtent2D {
-        *self
-            .swapchain_extent
+        self.swapchain_extent
             .get()
             .expect("Swapchain extent is null")
     }
 
-    fn _get_swapchain_image_views(&self) -> &Vec<VkImageView> {
-        self.swapchain_image_views
+    fn _get_swapchain_image_views(&self) -> Ref<Vec<VkImageView>> {
+        Ref::map(
+            self.swapchain_image_views.borrow(),
+            |swapchain_image_views| {
+                swapchain_image_views
+                    .as_ref()
+                    .expect("Swapchain image views is null")
+            },
+        )
+    }
+
+    fn _get_readback_buffer(&self) -> VkBuffer {
+        *self.readback_buffer.get().expect("Readback buffer is null")
+    }
+
+    fn _get_readback_buffer_memory(&self) -> VkDeviceMemory {
+        *self
+            .readback_buffer_memory
+            .get()
+            .expect("Readback buffer memory is null")
+    }
+
+    fn _get_msaa_samples(&self) -> VkSampleCountFlagBits {
+        *self.msaa_samples.get().expect("MSAA samples is null")
+    }
+
+    fn _get_color_image_view(&self) -> VkImageView {
+        self.color_image_view
+            .get()
+            .expect("Color image view is null")
+    }
+
+    fn _get_depth_format(&self) -> VkFormat {
+        *self.depth_format.get().expect("Depth format is null")
+    }
+
+    fn _get_depth_image_view(&self) -> VkImageView {
+        self.depth_image_view
             .get()
-            .expect("Swapchain image views is null")
+            .expect("Depth image view is null")
     }
 
     fn _get_render_pass(&self) -> VkRenderPass {
         *self.render_pass.get().expect("Render pass is null")
     }
 
+    fn _get_descriptor_set_layout(&self) -> VkDescriptorSetLayout {
+        *self
+            .descriptor_set_layout
+            .get()
+            .expect("Descriptor set layout is null")
+    }
+
     fn _get_pipeline_layout(&self) -> VkPipelineLayout {
         *self.pipeline_layout.get().expect("Pipeline layout is null")
     }
 
+    fn _get_pipeline_shaders(&self) -> Ref<PipelineShaders> {
+        Ref::map(self.pipeline_shaders.borrow(), |pipeline_shaders| {
+            pipeline_shaders.as_ref().expect("Pipeline shaders is null")
+        })
+    }
+
     fn _get_graphics_pipeline(&self) -> VkPipeline {
-        *self
-            .graphics_pipeline
+        self.graphics_pipeline
             .get()
             .expect("Graphics pipeline is null")
     }
 
-    fn _get_swapchain_framebuffers(&self) -> &Vec<VkFramebuffer> {
-        self.swapchain_framebuffers
-            .get()
-            .expect("Swapchain framebuffers is null")
+    fn _get_swapchain_framebuffers(&self) -> Ref<Vec<VkFramebuffer>> {
+        Ref::map(
+            self.swapchain_framebuffers.borrow(),
+            |swapchain_framebuffers| {
+                swapchain_framebuffers
+                    .as_ref()
+                    .expect("Swapchain framebuffers is null")
+            },
+        )
     }
 
     fn _get_command_pool(&self) -> VkCommandPool {
         *self.command_pool.get().expect("Command pool is null")
     }
 
-    fn _get_command_buffer(&self) -> VkCommandBuffer {
-        *self.command_buffer.get().expect("Command buffer is null")
+    fn _get_transfer_command_pool(&self) -> VkCommandPool {
+        *self
+            .transfer_command_pool
+            .get()
+            .expect("Transfer command pool is null")
     }
 
-    fn _get_image_available_semaphore(&self) -> VkSemaphore {
-        *self
-            .image_available_semaphore
+    fn _get_vertex_buffer(&self) -> VkBuffer {
+        *self.vertex_buffer.get().expect("Vertex buffer is null")
+    }
+
+    fn _get_index_buffer(&self) -> VkBuffer {
+        *self.index_buffer.get().expect("Index buffer is null")
+    }
+
+    fn _get_model(&self) -> &Mesh {
+        self.model.get().expect("Model is null")
+    }
+
+    fn _get_texture(&self) -> &Texture {
+        self.texture.get().expect("Texture is null")
+    }
+
+    fn _get_uniform_buffer(&self, frame: usize) -> VkBuffer {
+        self.uniform_buffers.get().expect("Uniform buffers is null")[frame]
+    }
+
+    fn _get_uniform_buffer_mapped(&self, frame: usize) -> *mut c_void {
+        self.uniform_buffers_mapped
             .get()
-            .expect("Image available semaphore is null")
+            .expect("Uniform buffers mapped is null")[frame]
     }
 
-    fn _get_render_finished_semaphore(&self) -> VkSemaphore {
-        *self
-            .render_finished_semaphore
+    fn _get_descriptor_pool(&self) -> VkDescriptorPool {
+        *self.descriptor_pool.get().expect("Descriptor pool is null")
+    }
+
+    fn _get_descriptor_set(&self, frame: usize) -> VkDescriptorSet {
+        self.descriptor_sets.get().expect("Descriptor sets is null")[frame]
+    }
+
+    fn _get_command_buffer(&self, frame: usize) -> VkCommandBuffer {
+        self.command_buffers.get().expect("Command buffers is null")[frame]
+    }
+
+    fn _get_image_available_semaphore(&self, frame: usize) -> VkSemaphore {
+        self.image_available_semaphores
             .get()
-            .expect("Render finished semaphore is null")
+            .expect("Image available semaphores is null")[frame]
     }
 
-    fn _get_in_flight_fence(&self) -> VkFence {
-        *self.in_flight_fence.get().expect("In flight fence is null")
+    fn _get_render_finished_semaphore(&self, frame: usize) -> VkSemaphore {
+        self.render_finished_semaphores
+            .get()
+            .expect("Render finished semaphores is null")[frame]
+    }
+
+    fn _get_in_flight_fence(&self, frame: usize) -> VkFence {
+        self.in_flight_fences
+            .get()
+            .expect("In flight fences is null")[frame]
     }
 }
 
 impl VulkanApi {
-    pub fn new(width: usize, height: usize) -> Self {
-        let validation_layers: Vec<CString> = vec![CString::new("VK_LAYER_KHRONOS_validation")
-            .expect("CString::new VK_LAYER_KHRONOS_validation failed!")];
-        let device_extensions: Vec<CString> =
-            vec![CStr::from_bytes_with_nul(VK_KHR_SWAPCHAIN_EXTENSION_NAME)
-                .expect("CStr::from_bytes_with_nul VK_KHR_SWAPCHAIN_EXTENSION_NAME failed!")
-                .to_owned()];
+    pub fn new(width: usize, height: usize, settings: GraphicsSettings) -> Self {
+        let validation_layers: Vec<CString> = settings
+            .validation_layers
+            .iter()
+            .map(|layer| CString::new(layer.as_str()).expect("CString::new layer name failed!"))
+            .collect();
+        let mut device_extensions: Vec<CString> = settings
+            .device_extensions
+            .iter()
+            .map(|extension| {
+                CString::new(extension.as_str()).expect("CString::new extension name failed!")
+            })
+            .collect();
+        // Nothing is presented headless, so the swapchain extension is not needed.
+        if !settings.headless {
+            device_extensions.push(
+                CStr::from_bytes_with_nul(VK_KHR_SWAPCHAIN_EXTENSION_NAME)
+                    .expect("CStr::from_bytes_with_nul VK_KHR_SWAPCHAIN_EXTENSION_NAME failed!")
+                    .to_owned(),
+            );
+        }
+        let shader_registry: ShaderRegistry =
+            ShaderRegistry::new(settings.shader_override_dir.as_deref());
         Self {
             width,
             height,
+            settings,
             window: OnceCell::new(),
             validation_layers,
             instance: OnceCell::new(),
             debug_messenger: OnceCell::new(),
             surface: OnceCell::new(),
             physical_device: OnceCell::new(),
+            msaa_samples: OnceCell::new(),
             device_extensions,
             device: OnceCell::new(),
             graphics_queue: OnceCell::new(),
             present_queue: OnceCell::new(),
-            swapchain: OnceCell::new(),
-            swapchain_images: OnceCell::new(),
-            swapchain_image_format: OnceCell::new(),
-            swapchain_extent: OnceCell::new(),
-            swapchain_image_views: OnceCell::new(),
+            transfer_queue: OnceCell::new(),
+            swapchain: Cell::new(None),
+            swapchain_images: RefCell::new(None),
+            swapchain_image_format: Cell::new(None),
+            swapchain_transfer_src: Cell::new(false),
+            swapchain_extent: Cell::new(None),
+            swapchain_image_views: RefCell::new(None),
+            offscreen_image_memory: Cell::new(None),
+            readback_buffer: OnceCell::new(),
+            readback_buffer_memory: OnceCell::new(),
+            color_image: Cell::new(None),
+            color_image_memory: Cell::new(None),
+            color_image_view: Cell::new(None),
+            depth_format: OnceCell::new(),
+            depth_image: Cell::new(None),
+            depth_image_memory: Cell::new(None),
+            depth_image_view: Cell::new(None),
             render_pass: OnceCell::new(),
+            descriptor_set_layout: OnceCell::new(),
             pipeline_layout: OnceCell::new(),
-            graphics_pipeline: OnceCell::new(),
-            swapchain_framebuffers: OnceCell::new(),
+            graphics_pipeline: Cell::new(None),
+            specialized_pipelines: RefCell::new(PipelineCache::default()),
+            specialization: RefCell::new(default_specialization()),
+            shader_registry,
+            shader_compiler: ShaderCompiler::default(),
+            pipeline_shaders: RefCell::new(None),
+            shader_watcher: OnceCell::new(),
+            swapchain_framebuffers: RefCell::new(None),
             command_pool: OnceCell::new(),
-            command_buffer: OnceCell::new(),
-            image_available_semaphore: OnceCell::new(),
-            render_finished_semaphore: OnceCell::new(),
-            in_flight_fence: OnceCell::new(),
+            transfer_command_pool: OnceCell::new(),
+            vertex_buffer: OnceCell::new(),
+            vertex_buffer_memory: OnceCell::new(),
+            index_buffer: OnceCell::new(),
+            index_buffer_memory: OnceCell::new(),
+            model: OnceCell::new(),
+            texture: OnceCell::new(),
+            uniform_buffers: OnceCell::new(),
+            uniform_buffers_memory: OnceCell::new(),
+            uniform_buffers_mapped: OnceCell::new(),
+            descriptor_pool: OnceCell::new(),
+            descriptor_sets: OnceCell::new(),
+            command_buffers: OnceCell::new(),
+            image_available_semaphores: OnceCell::new(),
+            render_finished_semaphores: OnceCell::new(),
+            in_flight_fences: OnceCell::new(),
+            images_in_flight: RefCell::new(Vec::new()),
+            current_frame: Cell::new(0),
+            frames_rendered: Cell::new(0),
+            screenshot_requested: Cell::new(false),
+            screenshot_path: RefCell::new(None),
+            texture_toggle_requested: Cell::new(false),
+            framebuffer_resized: Cell::new(false),
+            sampler_anisotropy: Cell::new(false),
+            sample_rate_shading: Cell::new(false),
+            start_time: Instant::now(),
         }
     }
 
     fn _enable_validation_layers(&self) -> bool {
-        utils::debug_mode()
+        self.settings.validation
     }
 
-    fn _create_instance(&self) {
+    fn _create_instance(&self) -> Result<(), VulkanError> {
         if debug_mode() {
             println!("Creating Vulkan instance");
         }
         if self._enable_validation_layers() && !self._check_validation_layer_support() {
-            panic!("Validation layers requested, but not available!");
+            return Err(VulkanError::setup(
+                "create instance",
+                "validation layers requested, but not available",
+            ));
         }
 
         let app_name = CString::new("Hello Triangle").expect("CString::new Hello Triangle failed!");
@@ -423,10 +990,12 @@ impl VulkanApi {
         let mut instance: VkInstance = std::ptr::null_mut();
         let result: VkResult = vk_create_instance(&create_info, std::ptr::null(), &mut instance);
         if result != VK_SUCCESS {
-            panic!("Failed to create instance");
+            return Err(VulkanError::vk("create instance", result));
         }
 
         self.instance.set(instance).expect("Failed to set instance");
+
+        Ok(())
     }
 
     fn _populate_debug_messenger_create_info(
@@ -462,13 +1031,17 @@ impl VulkanApi {
     }
 
     fn _get_required_extensions(&self) -> Vec<*const c_char> {
-        let mut glfw_extension_count: u32 = 0;
-        let glfw_extensions: *const *const c_char =
-            glfw_get_required_instance_extensions(&mut glfw_extension_count);
-        let mut extensions: Vec<*const c_char> = unsafe {
-            assert!(!glfw_extensions.is_null());
-            std::slice::from_raw_parts(glfw_extensions, glfw_extension_count as usize).to_vec()
-        };
+        // GLFW is never initialized headless, and without a surface no extension is required.
+        let mut extensions: Vec<*const c_char> = Vec::new();
+        if !self.settings.headless {
+            let mut glfw_extension_count: u32 = 0;
+            let glfw_extensions: *const *const c_char =
+                glfw_get_required_instance_extensions(&mut glfw_extension_count);
+            extensions = unsafe {
+                assert!(!glfw_extensions.is_null());
+                std::slice::from_raw_parts(glfw_extensions, glfw_extension_count as usize).to_vec()
+            };
+        }
 
         if self._enable_validation_layers() {
             extensions.push(VK_EXT_DEBUG_UTILS_EXTENSION_NAME.as_ptr() as *const c_char);
@@ -562,9 +1135,9 @@ impl VulkanApi {
         true
     }
 
-    fn _setup_debug_messenger(&self) {
+    fn _setup_debug_messenger(&self) -> Result<(), VulkanError> {
         if !self._enable_validation_layers() {
-            return;
+            return Ok(());
         }
 
         let mut debug_create_info: VkDebugUtilsMessengerCreateInfoEXT =
@@ -586,8 +1159,10 @@ impl VulkanApi {
             .expect("Failed to set debug messenger");
 
         if result != VK_SUCCESS {
-            panic!("Failed to set up debug messenger");
+            return Err(VulkanError::vk("set up debug messenger", result));
         }
+
+        Ok(())
     }
 
     fn _create_debug_utils_messenger_ext(
@@ -627,7 +1202,7 @@ impl VulkanApi {
         }
     }
 
-    fn _create_surface(&self) {
+    fn _create_surface(&self) -> Result<(), VulkanError> {
         let mut surface: VkSurfaceKHR = unsafe { std::mem::zeroed() };
         let result: VkResult = glfw_create_window_surface(
             self._get_instance(),
@@ -639,11 +1214,13 @@ impl VulkanApi {
         self.surface.set(surface).expect("Failed to set surface");
 
         if result != VK_SUCCESS {
-            panic!("Failed to create window surface");
+            return Err(VulkanError::vk("create window surface", result));
         }
+
+        Ok(())
     }
 
-    fn _pick_physical_device(&self) {
+    fn _pick_physical_device(&self) -> Result<(), VulkanError> {
         let mut device_count: u32 = 0;
         vk_enumerate_physical_devices(
             self._get_instance(),
@@ -652,7 +1229,10 @@ impl VulkanApi {
         );
 
         if device_count == 0 {
-            panic!("Failed to find GPUs with Vulkan support");
+            return Err(VulkanError::setup(
+                "pick physical device",
+                "no GPUs with Vulkan support",
+            ));
         }
 
         let mut devices: Vec<VkPhysicalDevice> = Vec::with_capacity(device_count as usize);
@@ -666,40 +1246,193 @@ impl VulkanApi {
             devices.as_mut_ptr(),
         );
 
-        for device in &devices {
-            if self._is_device_suitable(device) {
-                self.physical_device
-                    .set(*device)
-                    .expect("Failed to set physical device");
-                break;
+        // One line per device, saying how it scored or why it was rejected.
+        let mut report: Vec<String> = Vec::with_capacity(devices.len());
+        let mut best: Option<(DeviceScore, usize)> = None;
+        for (index, device) in devices.iter().enumerate() {
+            let mut properties: VkPhysicalDeviceProperties = unsafe { std::mem::zeroed() };
+            vk_get_physical_device_properties(*device, &mut properties);
+            let description: String = format!(
+                "GPU {}: {} ({:04x}:{:04x}, {})",
+                index,
+                device_name(&properties),
+                properties.vendorID,
+                properties.deviceID,
+                device_type_name(properties.deviceType)
+            );
+
+            match self._rate_device(index, device, &properties) {
+                Ok(score) => {
+                    report.push(format!(
+                        "{}: {} MiB of device memory, {} optional features",
+                        description,
+                        score.device_local_memory / (1024 * 1024),
+                        score.optional_features
+                    ));
+                    if best.is_none_or(|(best_score, _)| score > best_score) {
+                        best = Some((score, index));
+                    }
+                }
+                Err(reason) => report.push(format!("{}: rejected, {}", description, reason)),
+            }
+        }
+
+        let Some((_, selected)) = best else {
+            let message: String = match &self.settings.gpu {
+                Some(gpu) => format!("no suitable GPU matches \"{}\"", gpu),
+                None => String::from("no suitable GPU found"),
+            };
+            return Err(VulkanError::setup(
+                "pick physical device",
+                &format!("{}\n\t{}", message, report.join("\n\t")),
+            ));
+        };
+        if debug_mode() {
+            println!("Vulkan physical devices:");
+            for line in &report {
+                println!("\t{}", line);
+            }
+            println!("Using GPU {}", selected);
+        }
+        self.physical_device
+            .set(devices[selected])
+            .expect("Failed to set physical device");
+
+        self._select_msaa_samples()
+    }
+
+    fn _get_max_usable_sample_count(&self) -> VkSampleCountFlagBits {
+        let mut properties: VkPhysicalDeviceProperties = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_properties(self._get_physical_device(), &mut properties);
+
+        // Color and depth targets share the sample count, so both limits apply.
+        let counts: u32 = properties.limits.framebufferColorSampleCounts
+            & properties.limits.framebufferDepthSampleCounts;
+        for sample_count in [
+            VK_SAMPLE_COUNT_8_BIT,
+            VK_SAMPLE_COUNT_4_BIT,
+            VK_SAMPLE_COUNT_2_BIT,
+        ] {
+            if counts & sample_count as u32 != 0 {
+                return sample_count;
             }
         }
 
-        if self.physical_device.get().is_none() {
-            // VK_NULL_HANDLE is nullptr
-            panic!("Failed to find a suitable GPU");
+        VK_SAMPLE_COUNT_1_BIT
+    }
+
+    fn _select_msaa_samples(&self) -> Result<(), VulkanError> {
+        let requested: VkSampleCountFlagBits = sample_count_flag(self.settings.msaa_samples)
+            .ok_or_else(|| {
+                VulkanError::setup(
+                    "select msaa samples",
+                    &format!(
+                        "{} samples requested, expected 1, 2, 4 or 8",
+                        self.settings.msaa_samples
+                    ),
+                )
+            })?;
+        let max_usable: VkSampleCountFlagBits = self._get_max_usable_sample_count();
+
+        // Falling back to fewer samples keeps the app running on GPUs with lower limits.
+        let msaa_samples: VkSampleCountFlagBits = if requested as u32 > max_usable as u32 {
+            max_usable
+        } else {
+            requested
+        };
+        if debug_mode() {
+            println!(
+                "Using {:?} for MSAA ({} requested, up to {:?} supported)",
+                msaa_samples, self.settings.msaa_samples, max_usable
+            );
         }
+        self.msaa_samples
+            .set(msaa_samples)
+            .expect("MSAA samples can not be initialized!");
+
+        Ok(())
+    }
+
+    fn _msaa_enabled(&self) -> bool {
+        self._get_msaa_samples() != VK_SAMPLE_COUNT_1_BIT
     }
 
-    fn _is_device_suitable(&self, device: &VkPhysicalDevice) -> bool {
-        let mut device_properties: VkPhysicalDeviceProperties = unsafe { std::mem::zeroed() };
-        vk_get_physical_device_properties(*device, &mut device_properties);
+    fn _is_selected_device(&self, index: usize, properties: &VkPhysicalDeviceProperties) -> bool {
+        match &self.settings.gpu {
+            None => true,
+            Some(GpuSelection::Index(selected)) => index == *selected,
+            // Names match case-insensitively on any part: "nvidia" picks "NVIDIA GeForce RTX 3060".
+            Some(GpuSelection::Name(name)) => device_name(properties)
+                .to_lowercase()
+                .contains(&name.to_lowercase()),
+            Some(GpuSelection::Id {
+                vendor_id,
+                device_id,
+            }) => properties.vendorID == *vendor_id && properties.deviceID == *device_id,
+        }
+    }
 
-        let mut device_features: VkPhysicalDeviceFeatures = unsafe { std::mem::zeroed() };
-        vk_get_physical_device_features(*device, &mut device_features);
+    // Scores a device the renderer can use, or says why it can not.
+    fn _rate_device(
+        &self,
+        index: usize,
+        device: &VkPhysicalDevice,
+        properties: &VkPhysicalDeviceProperties,
+    ) -> Result<DeviceScore, String> {
+        if !self._is_selected_device(index, properties) {
+            let gpu: &GpuSelection = self.settings.gpu.as_ref().expect("GPU selection is null");
+            return Err(format!("not the requested GPU \"{}\"", gpu));
+        }
 
         let indices: QueueFamilyIndices = self._find_queue_families(device);
-        let extensions_supported: bool = self._check_device_extension_support(device);
+        if indices.graphics_family.is_none() {
+            return Err(String::from("no graphics queue family"));
+        }
+        if indices.present_family.is_none() {
+            return Err(String::from("no queue family can present to the window"));
+        }
 
-        let mut swap_chain_adequate: bool = false;
-        if extensions_supported {
+        let missing_extensions: Vec<String> = self._missing_device_extensions(device);
+        if !missing_extensions.is_empty() {
+            return Err(format!(
+                "missing device extensions {}",
+                missing_extensions.join(", ")
+            ));
+        }
+
+        if !self.settings.headless {
             let swap_chain_support: SwapChainSupportDetails =
                 self._query_swap_chain_support(device);
-            swap_chain_adequate = !swap_chain_support.formats.is_empty()
-                && !swap_chain_support.present_modes.is_empty();
+            if swap_chain_support.formats.is_empty() || swap_chain_support.present_modes.is_empty()
+            {
+                return Err(String::from(
+                    "no surface formats or present modes for the window",
+                ));
+            }
         }
 
-        indices.is_complete() && extensions_supported && swap_chain_adequate
+        let mut features: VkPhysicalDeviceFeatures = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_features(*device, &mut features);
+        // Anisotropic filtering is always used when available, sample shading only on request.
+        let mut optional_features: u32 = (features.samplerAnisotropy == VK_TRUE) as u32;
+        if self.settings.sample_shading {
+            optional_features += (features.sampleRateShading == VK_TRUE) as u32;
+        }
+
+        let mut memory_properties: VkPhysicalDeviceMemoryProperties = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_memory_properties(*device, &mut memory_properties);
+        let device_local_memory: VkDeviceSize = memory_properties.memoryHeaps
+            [..memory_properties.memoryHeapCount as usize]
+            .iter()
+            .filter(|heap| heap.flags & VK_MEMORY_HEAP_DEVICE_LOCAL_BIT as u32 != 0)
+            .map(|heap| heap.size)
+            .sum();
+
+        Ok(DeviceScore {
+            type_rank: DeviceScore::type_rank(properties.deviceType),
+            optional_features,
+            device_local_memory,
+        })
     }
 
     fn _query_swap_chain_support(&self, device: &VkPhysicalDevice) -> SwapChainSupportDetails {
@@ -759,7 +1492,7 @@ impl VulkanApi {
         details
     }
 
-    fn _check_device_extension_support(&self, device: &VkPhysicalDevice) -> bool {
+    fn _missing_device_extensions(&self, device: &VkPhysicalDevice) -> Vec<String> {
         let mut extension_count: u32 = 0;
         vk_enumerate_device_extension_properties(
             *device,
@@ -808,7 +1541,10 @@ impl VulkanApi {
             });
         }
 
-        required_extensions.is_empty()
+        required_extensions
+            .iter()
+            .map(|extension| extension.to_string_lossy().into_owned())
+            .collect()
     }
 
     fn _find_queue_families(&self, device: &VkPhysicalDevice) -> QueueFamilyIndices {
@@ -834,23 +1570,37 @@ impl VulkanApi {
 
         let mut i = 0;
         for queue_family in &queue_families {
-            if queue_family.queueFlags & (VK_QUEUE_GRAPHICS_BIT as u32) != 0 {
-                indices.graphics_family = Some(i);
+            let is_graphics: bool = queue_family.queueFlags & (VK_QUEUE_GRAPHICS_BIT as u32) != 0;
+            let is_transfer: bool = queue_family.queueFlags & (VK_QUEUE_TRANSFER_BIT as u32) != 0;
+
+            if is_transfer && !is_graphics && indices.transfer_family.is_none() {
+                indices.transfer_family = Some(i);
             }
 
-            let mut present_support: VkBool32 = 0;
-            vk_get_physical_device_surface_support_khr(
-                *device,
-                i,
-                self._get_surface(),
-                &mut present_support,
-            );
+            if !indices.is_complete() {
+                if is_graphics {
+                    indices.graphics_family = Some(i);
+                }
+
+                let mut present_support: VkBool32 = 0;
+                if self.settings.headless {
+                    // There is no surface; the graphics queue stands in for the present queue.
+                    present_support = is_graphics as VkBool32;
+                } else {
+                    vk_get_physical_device_surface_support_khr(
+                        *device,
+                        i,
+                        self._get_surface(),
+                        &mut present_support,
+                    );
+                }
 
-            if present_support != 0 {
-                indices.present_family = Some(i);
+                if present_support != 0 {
+                    indices.present_family = Some(i);
+                }
             }
 
-            if indices.is_complete() {
+            if indices.is_complete() && indices.has_dedicated_transfer_family() {
                 break;
             }
 
@@ -860,12 +1610,15 @@ impl VulkanApi {
         indices
     }
 
-    fn _create_logical_device(&self) {
+    fn _create_logical_device(&self) -> Result<(), VulkanError> {
         let indices: QueueFamilyIndices = self._find_queue_families(&self._get_physical_device());
 
         let mut queue_create_infos: Vec<VkDeviceQueueCreateInfo> = Vec::new();
-        let unique_queue_families: HashSet<u32> =
-            HashSet::from([indices.get_graphics_family(), indices.get_present_family()]);
+        let unique_queue_families: HashSet<u32> = HashSet::from([
+            indices.get_graphics_family(),
+            indices.get_present_family(),
+            indices.get_transfer_family(),
+        ]);
         let queue_priority: c_float = 1.0;
         for queue_family in unique_queue_families {
             let queue_create_info = VkDeviceQueueCreateInfo {
@@ -886,7 +1639,23 @@ impl VulkanApi {
             pp_enabled_layer_names = self.validation_layers.as_ptr() as *const *const i8;
         }
 
-        let device_features: VkPhysicalDeviceFeatures = unsafe { std::mem::zeroed() };
+        let mut supported_features: VkPhysicalDeviceFeatures = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_features(self._get_physical_device(), &mut supported_features);
+
+        let mut device_features: VkPhysicalDeviceFeatures = unsafe { std::mem::zeroed() };
+        device_features.samplerAnisotropy = supported_features.samplerAnisotropy;
+        self.sampler_anisotropy
+            .set(supported_features.samplerAnisotropy == VK_TRUE);
+        // Sample shading only does anything on multisampled targets.
+        if self.settings.sample_shading && self._msaa_enabled() {
+            device_features.sampleRateShading = supported_features.sampleRateShading;
+            if debug_mode() && supported_features.sampleRateShading != VK_TRUE {
+                println!("Sample shading requested but not supported, leaving it disabled");
+            }
+        }
+        self.sample_rate_shading
+            .set(device_features.sampleRateShading == VK_TRUE);
+
         let create_info: VkDeviceCreateInfo = VkDeviceCreateInfo {
             sType: VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
             queueCreateInfoCount: queue_create_infos.len() as u32,
@@ -909,7 +1678,7 @@ impl VulkanApi {
         );
 
         if result != VK_SUCCESS {
-            panic!("Failed to create logical device!");
+            return Err(VulkanError::vk("create logical device", result));
         }
 
         self.device
@@ -918,6 +1687,7 @@ impl VulkanApi {
 
         let mut graphics_queue: VkQueue = unsafe { std::mem::zeroed() };
         let mut present_queue: VkQueue = unsafe { std::mem::zeroed() };
+        let mut transfer_queue: VkQueue = unsafe { std::mem::zeroed() };
         vk_get_device_queue(
             self._get_device(),
             indices.get_graphics_family(),
@@ -930,6 +1700,12 @@ impl VulkanApi {
             0,
             &mut present_queue,
         );
+        vk_get_device_queue(
+            self._get_device(),
+            indices.get_transfer_family(),
+            0,
+            &mut transfer_queue,
+        );
 
         self.graphics_queue
             .set(graphics_queue)
@@ -937,9 +1713,20 @@ impl VulkanApi {
         self.present_queue
             .set(present_queue)
             .expect("Present queue can not be inicialized!");
+        self.transfer_queue
+            .set(transfer_queue)
+            .expect("Transfer queue can not be inicialized!");
+        if debug_mode() && indices.has_dedicated_transfer_family() {
+            println!(
+                "Using dedicated transfer queue family {}",
+                indices.get_transfer_family()
+            );
+        }
+
+        Ok(())
     }
 
-    fn _create_swap_chain(&self) {
+    fn _create_swap_chain(&self) -> Result<(), VulkanError> {
         let swap_chain_support: SwapChainSupportDetails =
             self._query_swap_chain_support(&self._get_physical_device());
 
@@ -969,6 +1756,19 @@ impl VulkanApi {
             p_queue_family_indices = queue_families_indices.as_ptr();
         }
 
+        // On recreation the retired swapchain is handed over so the driver can reuse its resources.
+        let old_swapchain: VkSwapchainKHR = self.swapchain.get().unwrap_or(std::ptr::null_mut());
+
+        // Screenshots copy out of the swapchain images, which not every surface allows.
+        let transfer_src: bool = swap_chain_support.capabilities.supportedUsageFlags
+            & VK_IMAGE_USAGE_TRANSFER_SRC_BIT as u32
+            != 0;
+        let image_usage: u32 = if transfer_src {
+            VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT as u32 | VK_IMAGE_USAGE_TRANSFER_SRC_BIT as u32
+        } else {
+            VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT as u32
+        };
+
         let create_info: VkSwapchainCreateInfoKHR = VkSwapchainCreateInfoKHR {
             sType: VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR,
             surface: self._get_surface(),
@@ -977,7 +1777,7 @@ impl VulkanApi {
             imageColorSpace: surface_format.colorSpace,
             imageExtent: extent,
             imageArrayLayers: 1,
-            imageUsage: VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT as u32,
+            imageUsage: image_usage,
             imageSharingMode: image_sharing_mode,
             queueFamilyIndexCount: queue_family_index_count,
             pQueueFamilyIndices: p_queue_family_indices,
@@ -985,7 +1785,7 @@ impl VulkanApi {
             compositeAlpha: VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR,
             presentMode: present_mode,
             clipped: VK_TRUE,
-            oldSwapchain: std::ptr::null_mut(), // C++ nullptr
+            oldSwapchain: old_swapchain,
             pNext: std::ptr::null(),
             flags: 0,
         };
@@ -1000,12 +1800,13 @@ impl VulkanApi {
         );
 
         if result != VK_SUCCESS {
-            panic!("failed to create swap chain!");
+            return Err(VulkanError::vk("create swap chain", result));
         }
 
-        self.swapchain
-            .set(swapchain)
-            .expect("Failed to set swapchain");
+        if !old_swapchain.is_null() {
+            vk_destroy_swapchain_khr(self._get_device(), old_swapchain, std::ptr::null());
+        }
+        self.swapchain.set(Some(swapchain));
 
         vk_get_swapchain_images_khr(
             self._get_device(),
@@ -1025,15 +1826,114 @@ impl VulkanApi {
             swapchain_images.as_mut_ptr(),
         );
 
-        self.swapchain_images
-            .set(swapchain_images)
-            .expect("Failed to set swapchain images");
-        self.swapchain_image_format
-            .set(surface_format.format)
-            .expect("Failed to set swapchain image format");
-        self.swapchain_extent
-            .set(extent)
-            .expect("Failed to set swapchain extent");
+        // A new swapchain has new images, none of them is in use by a frame yet.
+        self.images_in_flight
+            .replace(vec![std::ptr::null_mut(); swapchain_images.len()]);
+        self.swapchain_transfer_src.set(transfer_src);
+        self.swapchain_images.replace(Some(swapchain_images));
+        self.swapchain_image_format.set(Some(surface_format.format));
+        self.swapchain_extent.set(Some(extent));
+
+        Ok(())
+    }
+
+    // Headless stand-in for _create_swap_chain: a single image the render pass draws into and
+    // the frame is copied out of.
+    fn _create_offscreen_target(&self) -> Result<(), VulkanError> {
+        let extent: VkExtent2D = VkExtent2D {
+            width: self.width as u32,
+            height: self.height as u32,
+        };
+        let (image, image_memory) = self._create_image(
+            extent.width,
+            extent.height,
+            1,
+            VK_SAMPLE_COUNT_1_BIT,
+            OFFSCREEN_FORMAT,
+            VK_IMAGE_TILING_OPTIMAL,
+            VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT as u32 | VK_IMAGE_USAGE_TRANSFER_SRC_BIT as u32,
+            VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT as u32,
+        )?;
+        self.offscreen_image_memory.set(Some(image_memory));
+
+        self.images_in_flight.replace(vec![std::ptr::null_mut()]);
+        self.swapchain_images.replace(Some(vec![image]));
+        self.swapchain_image_format.set(Some(OFFSCREEN_FORMAT));
+        self.swapchain_extent.set(Some(extent));
+
+        if debug_mode() {
+            println!(
+                "Vulkan offscreen target created with width: {} and height: {}",
+                extent.width, extent.height
+            );
+        }
+
+        Ok(())
+    }
+    // The swapchain is not destroyed here, _create_swap_chain retires it through oldSwapchain.
+    fn _cleanup_swap_chain(&self) {
+        if let Some(swapchain_framebuffers) = self.swapchain_framebuffers.take() {
+            for swapchain_framebuffer in swapchain_framebuffers {
+                vk_destroy_framebuffer(self._get_device(), swapchain_framebuffer, std::ptr::null());
+            }
+        }
+
+        if let Some(color_image_view) = self.color_image_view.take() {
+            vk_destroy_image_view(self._get_device(), color_image_view, std::ptr::null());
+        }
+        if let Some(color_image) = self.color_image.take() {
+            vk_destroy_image(self._get_device(), color_image, std::ptr::null());
+        }
+        if let Some(color_image_memory) = self.color_image_memory.take() {
+            vk_free_memory(self._get_device(), color_image_memory, std::ptr::null());
+        }
+
+        if let Some(depth_image_view) = self.depth_image_view.take() {
+            vk_destroy_image_view(self._get_device(), depth_image_view, std::ptr::null());
+        }
+        if let Some(depth_image) = self.depth_image.take() {
+            vk_destroy_image(self._get_device(), depth_image, std::ptr::null());
+        }
+        if let Some(depth_image_memory) = self.depth_image_memory.take() {
+            vk_free_memory(self._get_device(), depth_image_memory, std::ptr::null());
+        }
+
+        if let Some(swapchain_image_views) = self.swapchain_image_views.take() {
+            for swapchain_image_view in swapchain_image_views {
+                vk_destroy_image_view(self._get_device(), swapchain_image_view, std::ptr::null());
+            }
+        }
+    }
+
+    fn _recreate_swap_chain(&self) -> Result<(), VulkanError> {
+        let mut width: c_int = 0;
+        let mut height: c_int = 0;
+        glfw_get_framebuffer_size(self._get_window(), &mut width, &mut height);
+
+        // A minimized window has a 0x0 framebuffer, so wait until it is visible again.
+        while width == 0 || height == 0 {
+            if glfw_window_should_close(self._get_window()) != 0 {
+                return Ok(());
+            }
+            glfw_wait_events();
+            glfw_get_framebuffer_size(self._get_window(), &mut width, &mut height);
+        }
+
+        if debug_mode() {
+            println!("Recreating swapchain for {}x{}", width, height);
+        }
+
+        vk_device_wait_idle(self._get_device());
+
+        self._cleanup_swap_chain();
+
+        self._create_swap_chain()?;
+        self._create_image_views()?;
+        self._create_color_resources()?;
+        self._create_depth_resources()?;
+        self._create_framebuffers()?;
+
+        Ok(())
     }
 
     fn _choose_swap_extent(&self, capabilities: &VkSurfaceCapabilitiesKHR) -> VkExtent2D {
@@ -1062,6 +1962,11 @@ impl VulkanApi {
         &self,
         avaliable_present_modes: &Vec<VkPresentModeKHR>,
     ) -> VkPresentModeKHR {
+        // FIFO waits for the vertical blank and is always available.
+        if self.settings.vsync {
+            return VK_PRESENT_MODE_FIFO_KHR;
+        }
+
         for avaliable_present_mode in avaliable_present_modes {
             if *avaliable_present_mode == VK_PRESENT_MODE_MAILBOX_KHR {
                 return *avaliable_present_mode;
@@ -1086,7 +1991,7 @@ impl VulkanApi {
         avaliable_formats[0]
     }
 
-    fn _create_image_views(&self) {
+    fn _create_image_views(&self) -> Result<(), VulkanError> {
         let mut swapchain_image_views: Vec<VkImageView> =
             Vec::with_capacity(self._get_swapchain_images().len());
         unsafe {
@@ -1094,38 +1999,13 @@ impl VulkanApi {
         }
 
         let mut i = 0;
-        for swapchain_image in self._get_swapchain_images() {
-            let create_info: VkImageViewCreateInfo = VkImageViewCreateInfo {
-                sType: VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
-                image: *swapchain_image,
-                viewType: VK_IMAGE_VIEW_TYPE_2D,
-                format: self._get_swapchain_image_format(),
-                components: VkComponentMapping {
-                    r: VK_COMPONENT_SWIZZLE_IDENTITY,
-                    g: VK_COMPONENT_SWIZZLE_IDENTITY,
-                    b: VK_COMPONENT_SWIZZLE_IDENTITY,
-                    a: VK_COMPONENT_SWIZZLE_IDENTITY,
-                },
-                subresourceRange: VkImageSubresourceRange {
-                    aspectMask: VK_IMAGE_ASPECT_COLOR_BIT as u32,
-                    baseMipLevel: 0,
-                    levelCount: 1,
-                    baseArrayLayer: 0,
-                    layerCount: 1,
-                },
-                pNext: std::ptr::null(),
-                flags: 0,
-            };
-
-            let result = vk_create_image_view(
-                self._get_device(),
-                &create_info,
-                std::ptr::null(),
-                &mut swapchain_image_views[i],
-            );
-            if result != VK_SUCCESS {
-                panic!("Failed to create image views!");
-            }
+        for swapchain_image in self._get_swapchain_images().iter() {
+            swapchain_image_views[i] = self._create_image_view(
+                *swapchain_image,
+                self._get_swapchain_image_format(),
+                VK_IMAGE_ASPECT_COLOR_BIT as u32,
+                1,
+            )?;
 
             i = i + 1;
         }
@@ -1134,19 +2014,206 @@ impl VulkanApi {
             println!("Vulkan swapchain image views created");
         }
         self.swapchain_image_views
-            .set(swapchain_image_views)
-            .expect("Failed to set swapchain image views");
+            .replace(Some(swapchain_image_views));
+
+        Ok(())
     }
-    fn _create_render_pass(&self) {
-        let color_attachment: VkAttachmentDescription = VkAttachmentDescription {
-            format: self._get_swapchain_image_format(),
-            samples: VK_SAMPLE_COUNT_1_BIT,
-            loadOp: VK_ATTACHMENT_LOAD_OP_CLEAR,
-            storeOp: VK_ATTACHMENT_STORE_OP_STORE,
+    fn _create_image_view(
+        &self,
+        image: VkImage,
+        format: VkFormat,
+        aspect_flags: VkImageAspectFlags,
+        mip_levels: u32,
+    ) -> Result<VkImageView, VulkanError> {
+        let create_info: VkImageViewCreateInfo = VkImageViewCreateInfo {
+            sType: VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
+            image,
+            viewType: VK_IMAGE_VIEW_TYPE_2D,
+            format,
+            components: VkComponentMapping {
+                r: VK_COMPONENT_SWIZZLE_IDENTITY,
+                g: VK_COMPONENT_SWIZZLE_IDENTITY,
+                b: VK_COMPONENT_SWIZZLE_IDENTITY,
+                a: VK_COMPONENT_SWIZZLE_IDENTITY,
+            },
+            subresourceRange: VkImageSubresourceRange {
+                aspectMask: aspect_flags,
+                baseMipLevel: 0,
+                levelCount: mip_levels,
+                baseArrayLayer: 0,
+                layerCount: 1,
+            },
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let mut image_view: VkImageView = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_image_view(
+            self._get_device(),
+            &create_info,
+            std::ptr::null(),
+            &mut image_view,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create image views", result));
+        }
+
+        Ok(image_view)
+    }
+    fn _find_supported_format(
+        &self,
+        candidates: &[VkFormat],
+        tiling: VkImageTiling,
+        features: VkFormatFeatureFlags,
+    ) -> Result<VkFormat, VulkanError> {
+        for format in candidates {
+            let mut properties: VkFormatProperties = unsafe { std::mem::zeroed() };
+            vk_get_physical_device_format_properties(
+                self._get_physical_device(),
+                *format,
+                &mut properties,
+            );
+
+            let supported_features: VkFormatFeatureFlags = if tiling == VK_IMAGE_TILING_LINEAR {
+                properties.linearTilingFeatures
+            } else {
+                properties.optimalTilingFeatures
+            };
+            if supported_features & features == features {
+                return Ok(*format);
+            }
+        }
+
+        Err(VulkanError::setup(
+            "find supported format",
+            &format!("none of {:?} supports features {:#x}", candidates, features),
+        ))
+    }
+
+    fn _find_depth_format(&self) -> Result<VkFormat, VulkanError> {
+        if let Some(depth_format) = self.depth_format.get() {
+            return Ok(*depth_format);
+        }
+
+        let depth_format: VkFormat = self._find_supported_format(
+            &[
+                VK_FORMAT_D32_SFLOAT,
+                VK_FORMAT_D32_SFLOAT_S8_UINT,
+                VK_FORMAT_D24_UNORM_S8_UINT,
+            ],
+            VK_IMAGE_TILING_OPTIMAL,
+            VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT as u32,
+        )?;
+        if debug_mode() {
+            println!("Using depth format {:?}", depth_format);
+        }
+        self.depth_format
+            .set(depth_format)
+            .expect("Depth format can not be initialized!");
+
+        Ok(depth_format)
+    }
+
+    fn _create_color_resources(&self) -> Result<(), VulkanError> {
+        // Without MSAA the pipeline renders straight into the swapchain images.
+        if !self._msaa_enabled() {
+            return Ok(());
+        }
+
+        let extent: VkExtent2D = self._get_swapchain_extent();
+        let (color_image, color_image_memory) = self._create_image(
+            extent.width,
+            extent.height,
+            1,
+            self._get_msaa_samples(),
+            self._get_swapchain_image_format(),
+            VK_IMAGE_TILING_OPTIMAL,
+            VK_IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT as u32
+                | VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT as u32,
+            VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT as u32,
+        )?;
+        self.color_image.set(Some(color_image));
+        self.color_image_memory.set(Some(color_image_memory));
+
+        let color_image_view: VkImageView = self._create_image_view(
+            color_image,
+            self._get_swapchain_image_format(),
+            VK_IMAGE_ASPECT_COLOR_BIT as u32,
+            1,
+        )?;
+        self.color_image_view.set(Some(color_image_view));
+
+        if debug_mode() {
+            println!("Vulkan multisampled color resources created");
+        }
+
+        Ok(())
+    }
+
+    fn _create_depth_resources(&self) -> Result<(), VulkanError> {
+        let extent: VkExtent2D = self._get_swapchain_extent();
+        let (depth_image, depth_image_memory) = self._create_image(
+            extent.width,
+            extent.height,
+            1,
+            self._get_msaa_samples(),
+            self._get_depth_format(),
+            VK_IMAGE_TILING_OPTIMAL,
+            VK_IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT as u32,
+            VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT as u32,
+        )?;
+        // Stored right away so _cleanup_swap_chain releases them even if the view fails.
+        self.depth_image.set(Some(depth_image));
+        self.depth_image_memory.set(Some(depth_image_memory));
+
+        let depth_image_view: VkImageView = self._create_image_view(
+            depth_image,
+            self._get_depth_format(),
+            VK_IMAGE_ASPECT_DEPTH_BIT as u32,
+            1,
+        )?;
+        self.depth_image_view.set(Some(depth_image_view));
+
+        if debug_mode() {
+            println!("Vulkan depth resources created");
+        }
+
+        Ok(())
+    }
+
+    fn _create_render_pass(&self) -> Result<(), VulkanError> {
+        // With MSAA the multisampled color attachment is resolved into the swapchain image,
+        // so only the resolve attachment has to be stored and presented.
+        let msaa_enabled: bool = self._msaa_enabled();
+        let color_attachment: VkAttachmentDescription = VkAttachmentDescription {
+            format: self._get_swapchain_image_format(),
+            samples: self._get_msaa_samples(),
+            loadOp: VK_ATTACHMENT_LOAD_OP_CLEAR,
+            storeOp: if msaa_enabled {
+                VK_ATTACHMENT_STORE_OP_DONT_CARE
+            } else {
+                VK_ATTACHMENT_STORE_OP_STORE
+            },
+            stencilLoadOp: VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            stencilStoreOp: VK_ATTACHMENT_STORE_OP_DONT_CARE,
+            initialLayout: VK_IMAGE_LAYOUT_UNDEFINED,
+            finalLayout: if msaa_enabled {
+                VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL
+            } else {
+                self._final_color_layout()
+            },
+            flags: 0,
+        };
+
+        let depth_attachment: VkAttachmentDescription = VkAttachmentDescription {
+            format: self._find_depth_format()?,
+            samples: self._get_msaa_samples(),
+            loadOp: VK_ATTACHMENT_LOAD_OP_CLEAR,
+            storeOp: VK_ATTACHMENT_STORE_OP_DONT_CARE,
             stencilLoadOp: VK_ATTACHMENT_LOAD_OP_DONT_CARE,
             stencilStoreOp: VK_ATTACHMENT_STORE_OP_DONT_CARE,
             initialLayout: VK_IMAGE_LAYOUT_UNDEFINED,
-            finalLayout: VK_IMAGE_LAYOUT_PRESENT_SRC_KHR,
+            finalLayout: VK_IMAGE_LAYOUT_DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
             flags: 0,
         };
 
@@ -1155,14 +2222,40 @@ impl VulkanApi {
             layout: VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
         };
 
+        let depth_attachment_ref: VkAttachmentReference = VkAttachmentReference {
+            attachment: 1,
+            layout: VK_IMAGE_LAYOUT_DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let color_attachment_resolve: VkAttachmentDescription = VkAttachmentDescription {
+            format: self._get_swapchain_image_format(),
+            samples: VK_SAMPLE_COUNT_1_BIT,
+            loadOp: VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            storeOp: VK_ATTACHMENT_STORE_OP_STORE,
+            stencilLoadOp: VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            stencilStoreOp: VK_ATTACHMENT_STORE_OP_DONT_CARE,
+            initialLayout: VK_IMAGE_LAYOUT_UNDEFINED,
+            finalLayout: self._final_color_layout(),
+            flags: 0,
+        };
+
+        let color_attachment_resolve_ref: VkAttachmentReference = VkAttachmentReference {
+            attachment: 2,
+            layout: VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+        };
+
         let subpass: VkSubpassDescription = VkSubpassDescription {
             pipelineBindPoint: VK_PIPELINE_BIND_POINT_GRAPHICS,
             colorAttachmentCount: 1,
             pColorAttachments: &color_attachment_ref,
             inputAttachmentCount: 0,
             pInputAttachments: std::ptr::null(),
-            pResolveAttachments: std::ptr::null(),
-            pDepthStencilAttachment: std::ptr::null(),
+            pResolveAttachments: if msaa_enabled {
+                &color_attachment_resolve_ref
+            } else {
+                std::ptr::null()
+            },
+            pDepthStencilAttachment: &depth_attachment_ref,
             preserveAttachmentCount: 0,
             pPreserveAttachments: std::ptr::null(),
             flags: 0,
@@ -1171,21 +2264,44 @@ impl VulkanApi {
         let dependency: VkSubpassDependency = VkSubpassDependency {
             srcSubpass: VK_SUBPASS_EXTERNAL as u32,
             dstSubpass: 0,
+            srcStageMask: VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT as u32
+                | VK_PIPELINE_STAGE_LATE_FRAGMENT_TESTS_BIT as u32,
+            srcAccessMask: VK_ACCESS_DEPTH_STENCIL_ATTACHMENT_WRITE_BIT as u32,
+            dstStageMask: VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT as u32
+                | VK_PIPELINE_STAGE_EARLY_FRAGMENT_TESTS_BIT as u32,
+            dstAccessMask: VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT as u32
+                | VK_ACCESS_DEPTH_STENCIL_ATTACHMENT_WRITE_BIT as u32,
+            dependencyFlags: 0,
+        };
+        // Headless frames are copied out right after the render pass, once the color writes
+        // and the transition to the final layout are done.
+        let readback_dependency: VkSubpassDependency = VkSubpassDependency {
+            srcSubpass: 0,
+            dstSubpass: VK_SUBPASS_EXTERNAL as u32,
             srcStageMask: VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT as u32,
-            srcAccessMask: 0,
-            dstStageMask: VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT as u32,
-            dstAccessMask: VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT as u32,
+            srcAccessMask: VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT as u32,
+            dstStageMask: VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+            dstAccessMask: VK_ACCESS_TRANSFER_READ_BIT as u32,
             dependencyFlags: 0,
         };
+        let mut dependencies: Vec<VkSubpassDependency> = vec![dependency];
+        if self.settings.headless {
+            dependencies.push(readback_dependency);
+        }
 
+        let mut attachments: Vec<VkAttachmentDescription> =
+            vec![color_attachment, depth_attachment];
+        if msaa_enabled {
+            attachments.push(color_attachment_resolve);
+        }
         let render_pass_info: VkRenderPassCreateInfo = VkRenderPassCreateInfo {
             sType: VK_STRUCTURE_TYPE_RENDER_PASS_CREATE_INFO,
-            attachmentCount: 1,
-            pAttachments: &color_attachment,
+            attachmentCount: attachments.len() as u32,
+            pAttachments: attachments.as_ptr(),
             subpassCount: 1,
             pSubpasses: &subpass,
-            dependencyCount: 1,
-            pDependencies: &dependency,
+            dependencyCount: dependencies.len() as u32,
+            pDependencies: dependencies.as_ptr(),
             pNext: std::ptr::null(),
             flags: 0,
         };
@@ -1198,32 +2314,340 @@ impl VulkanApi {
             &mut render_pass,
         );
         if result != VK_SUCCESS {
-            panic!("Failed to create render pass!");
+            return Err(VulkanError::vk("create render pass", result));
         }
 
         self.render_pass
             .set(render_pass)
             .expect("Render pass can not be initialized!");
+
+        Ok(())
+    }
+    // Layout the rendered image ends up in: ready to present, or to copy out when headless.
+    fn _final_color_layout(&self) -> VkImageLayout {
+        if self.settings.headless {
+            VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL
+        } else {
+            VK_IMAGE_LAYOUT_PRESENT_SRC_KHR
+        }
+    }
+    fn _create_descriptor_set_layout(&self) -> Result<(), VulkanError> {
+        let bindings: Vec<VkDescriptorSetLayoutBinding> =
+            self._get_pipeline_shaders().set_layout_bindings(0);
+
+        let layout_info: VkDescriptorSetLayoutCreateInfo = VkDescriptorSetLayoutCreateInfo {
+            sType: VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+            bindingCount: bindings.len() as u32,
+            pBindings: bindings.as_ptr(),
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let mut descriptor_set_layout: VkDescriptorSetLayout = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_descriptor_set_layout(
+            self._get_device(),
+            &layout_info,
+            std::ptr::null(),
+            &mut descriptor_set_layout,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create descriptor set layout", result));
+        }
+        self.descriptor_set_layout
+            .set(descriptor_set_layout)
+            .expect("Descriptor set layout can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan descriptor set layout created");
+        }
+
+        Ok(())
     }
-    fn _create_graphics_pipeline(&self) {
+    fn _create_pipeline_layout(&self) -> Result<(), VulkanError> {
+        let push_constant_ranges: Vec<VkPushConstantRange> =
+            self._get_pipeline_shaders().push_constant_ranges();
+        let pipeline_layout_info: VkPipelineLayoutCreateInfo = VkPipelineLayoutCreateInfo {
+            sType: VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO,
+            setLayoutCount: 1,
+            pSetLayouts: &self._get_descriptor_set_layout(),
+            pushConstantRangeCount: push_constant_ranges.len() as u32,
+            pPushConstantRanges: push_constant_ranges.as_ptr(),
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let mut pipeline_layout: VkPipelineLayout = unsafe { std::mem::zeroed() };
+        let result = vk_create_pipeline_layout(
+            self._get_device(),
+            &pipeline_layout_info,
+            std::ptr::null(),
+            &mut pipeline_layout,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create pipeline layout", result));
+        }
+        self.pipeline_layout
+            .set(pipeline_layout)
+            .expect("Pipeline layout can not be initialized!");
+
+        Ok(())
+    }
+
+    fn _create_graphics_pipeline(&self) -> Result<(), VulkanError> {
         if debug_mode() {
             println!("Creating graphics pipeline");
         }
 
-        let vert_shader_code: Vec<c_char> = self._read_file("src/shaders/shader.vert.spv");
-        let frag_shader_code: Vec<c_char> = self._read_file("src/shaders/shader.frag.spv");
+        let specialization: PipelineSpecialization = self.specialization.borrow().clone();
+        let graphics_pipeline: VkPipeline = self
+            .specialized_pipelines
+            .borrow_mut()
+            .get_or_build(&specialization, || {
+                self._build_graphics_pipeline(&self._get_pipeline_shaders(), &specialization)
+            })?;
+        self.graphics_pipeline.set(Some(graphics_pipeline));
+
+        Ok(())
+    }
+
+    fn _create_shader_watcher(&self) {
+        // Headless runs are over before anyone could edit a shader.
+        if !self.settings.shader_hot_reload || self.settings.headless {
+            return;
+        }
+        // With an override directory the SPIR-V files in it are what changes, not the sources.
+        let watched_dir: &str = self
+            .settings
+            .shader_override_dir
+            .as_deref()
+            .unwrap_or(SHADER_DIR);
+        self.shader_watcher
+            .set(FileWatcher::new(watched_dir, SHADER_POLL_INTERVAL))
+            .expect("Shader watcher can not be initialized!");
+    }
+
+    // Runs before the descriptor set layout is created, since it is derived from the shaders.
+    fn _load_shaders(&self) -> Result<(), VulkanError> {
+        let pipeline_shaders: PipelineShaders = self._read_registry_shaders()?;
+        if debug_mode() {
+            for layout_binding in &pipeline_shaders.bindings {
+                println!(
+                    "Shader binding {}: {} ({:?})",
+                    layout_binding.binding.binding,
+                    layout_binding.binding.name,
+                    layout_binding.binding.descriptor_type
+                );
+            }
+        }
+        self.pipeline_shaders.replace(Some(pipeline_shaders));
+
+        Ok(())
+    }
+
+    fn _read_registry_shaders(&self) -> Result<PipelineShaders, VulkanError> {
+        let load = |name: &str| {
+            self.shader_registry
+                .load(name)
+                .map_err(|error| VulkanError::setup("load shaders", &error.to_string()))
+        };
+
+        self._reflect_shaders(load(VERTEX_SHADER)?, load(FRAGMENT_SHADER)?)
+    }
+
+    fn _compile_shaders(&self) -> Result<PipelineShaders, VulkanError> {
+        let compile = |path: &str| {
+            self.shader_compiler
+                .compile(path, &[])
+                .map_err(|error| VulkanError::setup("compile shaders", &error.to_string()))
+        };
+        let vert_shader_code: Vec<u32> = compile(VERTEX_SHADER_PATH)?;
+        let frag_shader_code: Vec<u32> = compile(FRAGMENT_SHADER_PATH)?;
+
+        self._reflect_shaders(vert_shader_code, frag_shader_code)
+    }
+
+    fn _reflect_shaders(
+        &self,
+        vert_shader_code: Vec<u32>,
+        frag_shader_code: Vec<u32>,
+    ) -> Result<PipelineShaders, VulkanError> {
+        let pipeline_shaders: PipelineShaders =
+            PipelineShaders::new(vert_shader_code, frag_shader_code, ENTRY_POINT)
+                .map_err(|error| VulkanError::setup("reflect shaders", &error))?;
+        for layout_binding in &pipeline_shaders.bindings {
+            if !is_provided_binding(layout_binding) {
+                return Err(VulkanError::setup(
+                    "reflect shaders",
+                    &format!(
+                        "{} at set {} binding {} is a {:?} descriptor the renderer does not \
+                         provide",
+                        layout_binding.binding.name,
+                        layout_binding.binding.set,
+                        layout_binding.binding.binding,
+                        layout_binding.binding.descriptor_type
+                    ),
+                ));
+            }
+        }
+
+        Ok(pipeline_shaders)
+    }
+
+    // Called before every frame. Once a file in SHADER_DIR changes the shaders are recompiled and
+    // the pipeline is swapped; when they do not compile the current pipeline keeps drawing. With
+    // an override directory its SPIR-V files are read through the registry again instead. The
+    // descriptor sets and pipeline layout are kept, so shaders that change them need a restart.
+    fn _reload_changed_shaders(&self) {
+        let Some(shader_watcher) = self.shader_watcher.get() else {
+            return;
+        };
+        if !shader_watcher.poll() {
+            return;
+        }
+
+        let reloaded: Result<PipelineShaders, VulkanError> =
+            if self.settings.shader_override_dir.is_some() {
+                self._read_registry_shaders()
+            } else {
+                self._compile_shaders()
+            };
+        let pipeline_shaders: PipelineShaders = match reloaded {
+            Ok(pipeline_shaders) => pipeline_shaders,
+            Err(error) => {
+                eprintln!(
+                    "Shader reload failed, keeping the current pipeline\n{}",
+                    error
+                );
+                return;
+            }
+        };
+        if !pipeline_shaders.same_layout(&self._get_pipeline_shaders()) {
+            eprintln!(
+                "Shader reload failed, keeping the current pipeline\n\
+                 The descriptor bindings or push constants changed, restart to use them"
+            );
+            return;
+        }
+
+        // Frames in flight still use the current pipeline.
+        vk_device_wait_idle(self._get_device());
+        let specialization: PipelineSpecialization = self.specialization.borrow().clone();
+        match self._build_graphics_pipeline(&pipeline_shaders, &specialization) {
+            Ok(graphics_pipeline) => {
+                // Pipelines specialized from the old shaders are built again when used.
+                for old_pipeline in self.specialized_pipelines.borrow_mut().drain() {
+                    vk_destroy_pipeline(self._get_device(), old_pipeline, std::ptr::null());
+                }
+                self.specialized_pipelines
+                    .borrow_mut()
+                    .insert(specialization, graphics_pipeline);
+                self.graphics_pipeline.set(Some(graphics_pipeline));
+                self.pipeline_shaders.replace(Some(pipeline_shaders));
+                println!("Shaders reloaded");
+            }
+            Err(error) => {
+                eprintln!(
+                    "Shader reload failed, keeping the current pipeline\n{}",
+                    error
+                );
+            }
+        }
+    }
+
+    // Switches to the pipeline for these constants, which is built the first time they are used.
+    // The current pipeline is kept when the constants do not match the shaders or the new pipeline
+    // can not be built.
+    pub fn set_specialization(
+        &self,
+        specialization: PipelineSpecialization,
+    ) -> Result<(), VulkanError> {
+        let pipeline_shaders: Ref<PipelineShaders> = self._get_pipeline_shaders();
+        specialization
+            .check(&pipeline_shaders)
+            .map_err(|error| VulkanError::setup("specialize graphics pipeline", &error))?;
+
+        let graphics_pipeline: VkPipeline =
+            self.specialized_pipelines
+                .borrow_mut()
+                .get_or_build(&specialization, || {
+                    if debug_mode() {
+                        println!("Creating graphics pipeline for {:?}", specialization);
+                    }
+                    self._build_graphics_pipeline(&pipeline_shaders, &specialization)
+                })?;
+        // Frames in flight keep the pipeline they were recorded with, which stays in the cache.
+        self.graphics_pipeline.set(Some(graphics_pipeline));
+        self.specialization.replace(specialization);
+
+        Ok(())
+    }
+
+    fn _toggle_texturing(&self) {
+        let mut specialization: PipelineSpecialization = self.specialization.borrow().clone();
+        let textured: bool = matches!(
+            specialization.fragment.get(TEXTURED_CONSTANT_ID),
+            Some(SpecializationValue::Bool(true))
+        );
+        specialization.fragment.set(TEXTURED_CONSTANT_ID, !textured);
+
+        match self.set_specialization(specialization) {
+            Ok(()) => println!("Texturing {}", if textured { "off" } else { "on" }),
+            Err(error) => eprintln!("Failed to switch the pipeline\n{}", error),
+        }
+    }
+
+    // The shader modules are only needed while the pipeline is created.
+    fn _build_graphics_pipeline(
+        &self,
+        pipeline_shaders: &PipelineShaders,
+        specialization: &PipelineSpecialization,
+    ) -> Result<VkPipeline, VulkanError> {
+        // Only the attributes the vertex shader reads, each checked against its input type.
+        let attribute_descriptions: Vec<VkVertexInputAttributeDescription> = pipeline_shaders
+            .vertex_attributes::<MeshVertex>()
+            .map_err(|error| VulkanError::setup("create graphics pipeline", &error))?;
+        // Every constant has to be declared in its stage with the type of its value.
+        specialization
+            .check(pipeline_shaders)
+            .map_err(|error| VulkanError::setup("create graphics pipeline", &error))?;
+
+        let vert_shader_module: VkShaderModule =
+            self._create_shader_module(&pipeline_shaders.vertex_code)?;
+        let frag_shader_module: VkShaderModule = match self
+            ._create_shader_module(&pipeline_shaders.fragment_code)
+        {
+            Ok(frag_shader_module) => frag_shader_module,
+            Err(error) => {
+                vk_destroy_shader_module(self._get_device(), vert_shader_module, std::ptr::null());
+                return Err(error);
+            }
+        };
+
+        let vert_map_entries: Vec<VkSpecializationMapEntry> = specialization.vertex.map_entries();
+        let vert_specialization_data: Vec<u8> = specialization.vertex.data();
+        let vert_specialization_info: VkSpecializationInfo = VkSpecializationInfo {
+            mapEntryCount: vert_map_entries.len() as u32,
+            pMapEntries: vert_map_entries.as_ptr(),
+            dataSize: vert_specialization_data.len(),
+            pData: vert_specialization_data.as_ptr() as *const c_void,
+        };
 
-        let vert_shader_module: VkShaderModule = self._create_shader_module(&vert_shader_code);
-        let frag_shader_module: VkShaderModule = self._create_shader_module(&frag_shader_code);
+        let frag_map_entries: Vec<VkSpecializationMapEntry> = specialization.fragment.map_entries();
+        let frag_specialization_data: Vec<u8> = specialization.fragment.data();
+        let frag_specialization_info: VkSpecializationInfo = VkSpecializationInfo {
+            mapEntryCount: frag_map_entries.len() as u32,
+            pMapEntries: frag_map_entries.as_ptr(),
+            dataSize: frag_specialization_data.len(),
+            pData: frag_specialization_data.as_ptr() as *const c_void,
+        };
 
-        let queue_name = CString::new("main").expect("CString::new failed");
+        let queue_name = CString::new(ENTRY_POINT).expect("CString::new failed");
         let vert_shader_stage_info: VkPipelineShaderStageCreateInfo =
             VkPipelineShaderStageCreateInfo {
                 sType: VK_STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_CREATE_INFO,
                 stage: VK_SHADER_STAGE_VERTEX_BIT,
                 module: vert_shader_module,
                 pName: queue_name.as_ptr(),
-                pSpecializationInfo: std::ptr::null(),
+                pSpecializationInfo: &vert_specialization_info,
                 pNext: std::ptr::null(),
                 flags: 0,
             };
@@ -1234,7 +2658,7 @@ impl VulkanApi {
                 stage: VK_SHADER_STAGE_FRAGMENT_BIT,
                 module: frag_shader_module,
                 pName: queue_name.as_ptr(),
-                pSpecializationInfo: std::ptr::null(),
+                pSpecializationInfo: &frag_specialization_info,
                 pNext: std::ptr::null(),
                 flags: 0,
             };
@@ -1242,13 +2666,15 @@ impl VulkanApi {
         let shader_stages: Vec<VkPipelineShaderStageCreateInfo> =
             vec![vert_shader_stage_info, frag_shader_stage_info];
 
+        let binding_description: VkVertexInputBindingDescription =
+            MeshVertex::binding_description();
         let vertex_input_info: VkPipelineVertexInputStateCreateInfo =
             VkPipelineVertexInputStateCreateInfo {
                 sType: VK_STRUCTURE_TYPE_PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
-                vertexBindingDescriptionCount: 0,
-                pVertexBindingDescriptions: std::ptr::null(),
-                vertexAttributeDescriptionCount: 0,
-                pVertexAttributeDescriptions: std::ptr::null(),
+                vertexBindingDescriptionCount: 1,
+                pVertexBindingDescriptions: &binding_description,
+                vertexAttributeDescriptionCount: attribute_descriptions.len() as u32,
+                pVertexAttributeDescriptions: attribute_descriptions.as_ptr(),
                 pNext: std::ptr::null(),
                 flags: 0,
             };
@@ -1294,7 +2720,7 @@ impl VulkanApi {
                 polygonMode: VK_POLYGON_MODE_FILL,
                 lineWidth: 1.0,
                 cullMode: VK_CULL_MODE_BACK_BIT as u32,
-                frontFace: VK_FRONT_FACE_CLOCKWISE,
+                frontFace: VK_FRONT_FACE_COUNTER_CLOCKWISE,
                 depthBiasEnable: VK_FALSE,
                 depthBiasConstantFactor: 0.0,
                 depthBiasClamp: 0.0,
@@ -1306,9 +2732,14 @@ impl VulkanApi {
         let multisampling: VkPipelineMultisampleStateCreateInfo =
             VkPipelineMultisampleStateCreateInfo {
                 sType: VK_STRUCTURE_TYPE_PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
-                sampleShadingEnable: VK_FALSE,
-                rasterizationSamples: VK_SAMPLE_COUNT_1_BIT,
-                minSampleShading: 1.0,
+                sampleShadingEnable: if self.sample_rate_shading.get() {
+                    VK_TRUE
+                } else {
+                    VK_FALSE
+                },
+                rasterizationSamples: self._get_msaa_samples(),
+                // Shade at least a fifth of the samples individually to smooth texture edges.
+                minSampleShading: 0.2,
                 pSampleMask: std::ptr::null(),
                 alphaToCoverageEnable: VK_FALSE,
                 alphaToOneEnable: VK_FALSE,
@@ -1316,6 +2747,22 @@ impl VulkanApi {
                 flags: 0,
             };
 
+        let depth_stencil: VkPipelineDepthStencilStateCreateInfo =
+            VkPipelineDepthStencilStateCreateInfo {
+                sType: VK_STRUCTURE_TYPE_PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
+                depthTestEnable: VK_TRUE,
+                depthWriteEnable: VK_TRUE,
+                depthCompareOp: VK_COMPARE_OP_LESS,
+                depthBoundsTestEnable: VK_FALSE,
+                stencilTestEnable: VK_FALSE,
+                front: unsafe { std::mem::zeroed::<VkStencilOpState>() },
+                back: unsafe { std::mem::zeroed::<VkStencilOpState>() },
+                minDepthBounds: 0.0,
+                maxDepthBounds: 1.0,
+                pNext: std::ptr::null(),
+                flags: 0,
+            };
+
         let color_white_mask_bit_or: u32 = VK_COLOR_COMPONENT_R_BIT as u32
             | VK_COLOR_COMPONENT_G_BIT as u32
             | VK_COLOR_COMPONENT_B_BIT as u32
@@ -1356,30 +2803,6 @@ impl VulkanApi {
             flags: 0,
         };
 
-        let pipeline_layout_info: VkPipelineLayoutCreateInfo = VkPipelineLayoutCreateInfo {
-            sType: VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO,
-            setLayoutCount: 0,
-            pSetLayouts: std::ptr::null(),
-            pushConstantRangeCount: 0,
-            pPushConstantRanges: std::ptr::null(),
-            pNext: std::ptr::null(),
-            flags: 0,
-        };
-
-        let mut pipeline_layout: VkPipelineLayout = unsafe { std::mem::zeroed() };
-        let result = vk_create_pipeline_layout(
-            self._get_device(),
-            &pipeline_layout_info,
-            std::ptr::null(),
-            &mut pipeline_layout,
-        );
-        if result != VK_SUCCESS {
-            panic!("Failed to create pipeline layout!");
-        }
-        self.pipeline_layout
-            .set(pipeline_layout)
-            .expect("Pipeline layout can not be initialized!");
-
         let graphics_pipeline_info: VkGraphicsPipelineCreateInfo = VkGraphicsPipelineCreateInfo {
             sType: VK_STRUCTURE_TYPE_GRAPHICS_PIPELINE_CREATE_INFO,
             stageCount: shader_stages.len() as u32,
@@ -1389,7 +2812,7 @@ impl VulkanApi {
             pViewportState: &viewport_state,
             pRasterizationState: &rasterizer,
             pMultisampleState: &multisampling,
-            pDepthStencilState: std::ptr::null(),
+            pDepthStencilState: &depth_stencil,
             pColorBlendState: &color_blending,
             pDynamicState: &dynamic_state,
             layout: self._get_pipeline_layout(),
@@ -1411,22 +2834,22 @@ impl VulkanApi {
             std::ptr::null(),
             &mut graphics_pipeline,
         );
-        if result != VK_SUCCESS {
-            panic!("Failed to create graphics pipeline!");
-        }
-        self.graphics_pipeline
-            .set(graphics_pipeline)
-            .expect("Graphics pipeline can not be initialized!");
 
         vk_destroy_shader_module(self._get_device(), vert_shader_module, std::ptr::null());
         vk_destroy_shader_module(self._get_device(), frag_shader_module, std::ptr::null());
+
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create graphics pipeline", result));
+        }
+
+        Ok(graphics_pipeline)
     }
 
-    fn _create_shader_module(&self, code: &Vec<c_char>) -> VkShaderModule {
+    fn _create_shader_module(&self, code: &[u32]) -> Result<VkShaderModule, VulkanError> {
         let create_info: VkShaderModuleCreateInfo = VkShaderModuleCreateInfo {
             sType: VK_STRUCTURE_TYPE_SHADER_MODULE_CREATE_INFO,
-            codeSize: code.len() as usize,
-            pCode: code.as_ptr() as *const u32,
+            codeSize: std::mem::size_of_val(code),
+            pCode: code.as_ptr(),
             pNext: std::ptr::null(),
             flags: 0,
         };
@@ -1440,39 +2863,30 @@ impl VulkanApi {
         );
 
         if result != VK_SUCCESS {
-            panic!("Failed to create shader module!");
+            return Err(VulkanError::vk("create shader module", result));
         }
 
-        shader_module
+        Ok(shader_module)
     }
 
-    fn _read_file(&self, filename: &str) -> Vec<c_char> {
-        let mut file: File =
-            File::open(filename).expect(&format!("Failed to open file: {}", filename));
-        let mut content: Vec<u8> = Vec::new();
-        file.read_to_end(&mut content)
-            .expect(&format!("Failed to read file: {}", filename));
-
-        let mut result: Vec<c_char> = Vec::with_capacity(content.len());
-        for byte in content {
-            result.push(byte as c_char);
-        }
-
-        if debug_mode() {
-            println!("File {} read successfully", filename);
-        }
-
-        result
-    }
-    fn _create_framebuffers(&self) {
+    fn _create_framebuffers(&self) -> Result<(), VulkanError> {
         let mut swapchain_framebuffers: Vec<VkFramebuffer> =
             Vec::with_capacity(self._get_swapchain_image_views().len());
-        for swapchain_image_view in self._get_swapchain_image_views() {
-            let attachments: Vec<VkImageView> = vec![*swapchain_image_view];
+        for swapchain_image_view in self._get_swapchain_image_views().iter() {
+            // Attachment order has to match the render pass.
+            let attachments: Vec<VkImageView> = if self._msaa_enabled() {
+                vec![
+                    self._get_color_image_view(),
+                    self._get_depth_image_view(),
+                    *swapchain_image_view,
+                ]
+            } else {
+                vec![*swapchain_image_view, self._get_depth_image_view()]
+            };
             let framebuffer_info: VkFramebufferCreateInfo = VkFramebufferCreateInfo {
                 sType: VK_STRUCTURE_TYPE_FRAMEBUFFER_CREATE_INFO,
                 renderPass: self._get_render_pass(),
-                attachmentCount: 1,
+                attachmentCount: attachments.len() as u32,
                 pAttachments: attachments.as_ptr(),
                 width: self._get_swapchain_extent().width,
                 height: self._get_swapchain_extent().height,
@@ -1489,7 +2903,7 @@ impl VulkanApi {
                 &mut framebuffer,
             );
             if result != VK_SUCCESS {
-                panic!("Failed to create framebuffer!");
+                return Err(VulkanError::vk("create framebuffer", result));
             }
             swapchain_framebuffers.push(framebuffer);
         }
@@ -1498,10 +2912,11 @@ impl VulkanApi {
             println!("Vulkan swapchain framebuffers created");
         }
         self.swapchain_framebuffers
-            .set(swapchain_framebuffers)
-            .expect("Failed to set swapchain framebuffers");
+            .replace(Some(swapchain_framebuffers));
+
+        Ok(())
     }
-    fn _create_command_pool(&self) {
+    fn _create_command_pool(&self) -> Result<(), VulkanError> {
         let queue_family_indices: QueueFamilyIndices =
             self._find_queue_families(&self._get_physical_device());
 
@@ -1519,7 +2934,7 @@ impl VulkanApi {
             &mut command_pool,
         );
         if result != VK_SUCCESS {
-            panic!("Failed to create command pool!");
+            return Err(VulkanError::vk("create command pool", result));
         }
         self.command_pool
             .set(command_pool)
@@ -1527,11 +2942,45 @@ impl VulkanApi {
         if debug_mode() {
             println!("Vulkan command pool created");
         }
+
+        Ok(())
+    }
+    fn _create_transfer_command_pool(&self) -> Result<(), VulkanError> {
+        let queue_family_indices: QueueFamilyIndices =
+            self._find_queue_families(&self._get_physical_device());
+
+        let pool_info: VkCommandPoolCreateInfo = VkCommandPoolCreateInfo {
+            sType: VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
+            flags: VK_COMMAND_POOL_CREATE_TRANSIENT_BIT as u32,
+            queueFamilyIndex: queue_family_indices.get_transfer_family(),
+            pNext: std::ptr::null(),
+        };
+        let mut command_pool: VkCommandPool = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_command_pool(
+            self._get_device(),
+            &pool_info,
+            std::ptr::null(),
+            &mut command_pool,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create transfer command pool", result));
+        }
+        self.transfer_command_pool
+            .set(command_pool)
+            .expect("Transfer command pool can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan transfer command pool created");
+        }
+
+        Ok(())
     }
-    fn _create_command_buffers(&self) {
+    fn _begin_single_time_commands(
+        &self,
+        command_pool: VkCommandPool,
+    ) -> Result<VkCommandBuffer, VulkanError> {
         let alloc_info: VkCommandBufferAllocateInfo = VkCommandBufferAllocateInfo {
             sType: VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
-            commandPool: self._get_command_pool(),
+            commandPool: command_pool,
             level: VK_COMMAND_BUFFER_LEVEL_PRIMARY,
             commandBufferCount: 1,
             pNext: std::ptr::null(),
@@ -1541,72 +2990,1251 @@ impl VulkanApi {
         let result: VkResult =
             vk_allocate_command_buffers(self._get_device(), &alloc_info, &mut command_buffer);
         if result != VK_SUCCESS {
-            panic!("Failed to allocate command buffers!");
+            return Err(VulkanError::vk("allocate one-shot command buffer", result));
         }
-        if debug_mode() {
-            println!("Vulkan command buffer created");
-        }
-        self.command_buffer
-            .set(command_buffer)
-            .expect("Command buffer can not be initialized!");
-    }
-    fn _create_sync_objects(&self) {
-        let semaphore_info: VkSemaphoreCreateInfo = VkSemaphoreCreateInfo {
-            sType: VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+
+        let begin_info: VkCommandBufferBeginInfo = VkCommandBufferBeginInfo {
+            sType: VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO,
+            flags: VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT as u32,
+            pInheritanceInfo: std::ptr::null(),
             pNext: std::ptr::null(),
-            flags: 0,
         };
-
-        let fence_info: VkFenceCreateInfo = VkFenceCreateInfo {
-            sType: VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
-            flags: VK_FENCE_CREATE_SIGNALED_BIT as u32,
+        let result: VkResult = vk_begin_command_buffer(command_buffer, &begin_info);
+        if result != VK_SUCCESS {
+            vk_free_command_buffers(self._get_device(), command_pool, 1, &command_buffer);
+            return Err(VulkanError::vk("begin one-shot command buffer", result));
+        }
+
+        Ok(command_buffer)
+    }
+    fn _end_single_time_commands(
+        &self,
+        command_pool: VkCommandPool,
+        queue: VkQueue,
+        command_buffer: VkCommandBuffer,
+    ) -> Result<(), VulkanError> {
+        let mut stage: &'static str = "record one-shot command buffer";
+        let mut result: VkResult = vk_end_command_buffer(command_buffer);
+
+        if result == VK_SUCCESS {
+            let submit_info: VkSubmitInfo = VkSubmitInfo {
+                sType: VK_STRUCTURE_TYPE_SUBMIT_INFO,
+                waitSemaphoreCount: 0,
+                pWaitSemaphores: std::ptr::null(),
+                pWaitDstStageMask: std::ptr::null(),
+                commandBufferCount: 1,
+                pCommandBuffers: &command_buffer,
+                signalSemaphoreCount: 0,
+                pSignalSemaphores: std::ptr::null(),
+                pNext: std::ptr::null(),
+            };
+            stage = "submit one-shot command buffer";
+            result = vk_queue_submit(queue, 1, &submit_info, std::ptr::null_mut());
+        }
+        if result == VK_SUCCESS {
+            stage = "wait for one-shot command buffer";
+            result = vk_queue_wait_idle(queue);
+        }
+
+        vk_free_command_buffers(self._get_device(), command_pool, 1, &command_buffer);
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk(stage, result));
+        }
+
+        Ok(())
+    }
+    fn _find_memory_type(
+        &self,
+        type_filter: u32,
+        properties: VkMemoryPropertyFlags,
+    ) -> Result<u32, VulkanError> {
+        let mut memory_properties: VkPhysicalDeviceMemoryProperties = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_memory_properties(
+            self._get_physical_device(),
+            &mut memory_properties,
+        );
+
+        for index in 0..memory_properties.memoryTypeCount {
+            let property_flags = memory_properties.memoryTypes[index as usize].propertyFlags;
+            if type_filter & (1 << index) != 0 && property_flags & properties == properties {
+                return Ok(index);
+            }
+        }
+
+        Err(VulkanError::setup(
+            "find suitable memory type",
+            &format!(
+                "no memory type matches filter {:#b} with properties {:#x}",
+                type_filter, properties
+            ),
+        ))
+    }
+    fn _create_buffer(
+        &self,
+        size: VkDeviceSize,
+        usage: VkBufferUsageFlags,
+        properties: VkMemoryPropertyFlags,
+    ) -> Result<(VkBuffer, VkDeviceMemory), VulkanError> {
+        let buffer_info: VkBufferCreateInfo = VkBufferCreateInfo {
+            sType: VK_STRUCTURE_TYPE_BUFFER_CREATE_INFO,
+            size,
+            usage,
+            sharingMode: VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: std::ptr::null(),
             pNext: std::ptr::null(),
+            flags: 0,
         };
 
-        let mut image_available_semaphore: VkSemaphore = unsafe { std::mem::zeroed() };
-        let mut render_finished_semaphore: VkSemaphore = unsafe { std::mem::zeroed() };
-        let mut in_flight_fence: VkFence = unsafe { std::mem::zeroed() };
+        let mut buffer: VkBuffer = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_buffer(
+            self._get_device(),
+            &buffer_info,
+            std::ptr::null(),
+            &mut buffer,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create buffer", result));
+        }
+
+        let mut memory_requirements: VkMemoryRequirements = unsafe { std::mem::zeroed() };
+        vk_get_buffer_memory_requirements(self._get_device(), buffer, &mut memory_requirements);
 
-        let result_semaphore_image_available: VkResult = vk_create_semaphore(
+        let memory_type_index: u32 =
+            match self._find_memory_type(memory_requirements.memoryTypeBits, properties) {
+                Ok(memory_type_index) => memory_type_index,
+                Err(error) => {
+                    vk_destroy_buffer(self._get_device(), buffer, std::ptr::null());
+                    return Err(error);
+                }
+            };
+        let alloc_info: VkMemoryAllocateInfo = VkMemoryAllocateInfo {
+            sType: VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
+            allocationSize: memory_requirements.size,
+            memoryTypeIndex: memory_type_index,
+            pNext: std::ptr::null(),
+        };
+
+        let mut buffer_memory: VkDeviceMemory = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_allocate_memory(
             self._get_device(),
-            &semaphore_info,
+            &alloc_info,
             std::ptr::null(),
-            &mut image_available_semaphore,
+            &mut buffer_memory,
         );
-        let result_semaphore_render_finished: VkResult = vk_create_semaphore(
+        if result != VK_SUCCESS {
+            vk_destroy_buffer(self._get_device(), buffer, std::ptr::null());
+            return Err(VulkanError::vk("allocate buffer memory", result));
+        }
+
+        let result: VkResult = vk_bind_buffer_memory(self._get_device(), buffer, buffer_memory, 0);
+        if result != VK_SUCCESS {
+            vk_destroy_buffer(self._get_device(), buffer, std::ptr::null());
+            vk_free_memory(self._get_device(), buffer_memory, std::ptr::null());
+            return Err(VulkanError::vk("bind buffer memory", result));
+        }
+
+        Ok((buffer, buffer_memory))
+    }
+    fn _create_host_buffer<T: Copy>(
+        &self,
+        data: &[T],
+        usage: VkBufferUsageFlags,
+    ) -> Result<(VkBuffer, VkDeviceMemory), VulkanError> {
+        let buffer_size: VkDeviceSize = std::mem::size_of_val(data) as VkDeviceSize;
+        let (buffer, buffer_memory) = self._create_buffer(
+            buffer_size,
+            usage,
+            VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT as u32
+                | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT as u32,
+        )?;
+
+        let mut mapped: *mut c_void = std::ptr::null_mut();
+        let result: VkResult = vk_map_memory(
             self._get_device(),
-            &semaphore_info,
+            buffer_memory,
+            0,
+            buffer_size,
+            0,
+            &mut mapped,
+        );
+        if result != VK_SUCCESS {
+            vk_destroy_buffer(self._get_device(), buffer, std::ptr::null());
+            vk_free_memory(self._get_device(), buffer_memory, std::ptr::null());
+            return Err(VulkanError::vk("map buffer memory", result));
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), mapped as *mut T, data.len());
+        }
+        vk_unmap_memory(self._get_device(), buffer_memory);
+
+        Ok((buffer, buffer_memory))
+    }
+    // Copies data into a new device-local buffer through a staging buffer and the transfer queue.
+    pub fn upload_buffer<T: Copy>(
+        &self,
+        data: &[T],
+        usage: VkBufferUsageFlags,
+    ) -> Result<(VkBuffer, VkDeviceMemory), VulkanError> {
+        let buffer_size: VkDeviceSize = std::mem::size_of_val(data) as VkDeviceSize;
+        let (staging_buffer, staging_buffer_memory) =
+            self._create_host_buffer(data, VK_BUFFER_USAGE_TRANSFER_SRC_BIT as u32)?;
+
+        let result = self
+            ._create_buffer(
+                buffer_size,
+                usage | VK_BUFFER_USAGE_TRANSFER_DST_BIT as u32,
+                VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT as u32,
+            )
+            .and_then(|(buffer, buffer_memory)| {
+                match self._copy_buffer(staging_buffer, buffer, buffer_size, usage) {
+                    Ok(()) => Ok((buffer, buffer_memory)),
+                    Err(error) => {
+                        vk_destroy_buffer(self._get_device(), buffer, std::ptr::null());
+                        vk_free_memory(self._get_device(), buffer_memory, std::ptr::null());
+                        Err(error)
+                    }
+                }
+            });
+
+        vk_destroy_buffer(self._get_device(), staging_buffer, std::ptr::null());
+        vk_free_memory(self._get_device(), staging_buffer_memory, std::ptr::null());
+
+        result
+    }
+    fn _copy_buffer(
+        &self,
+        src_buffer: VkBuffer,
+        dst_buffer: VkBuffer,
+        size: VkDeviceSize,
+        usage: VkBufferUsageFlags,
+    ) -> Result<(), VulkanError> {
+        let indices: QueueFamilyIndices = self._find_queue_families(&self._get_physical_device());
+        let transfer_family: u32 = indices.get_transfer_family();
+        let graphics_family: u32 = indices.get_graphics_family();
+        let (dst_access_mask, dst_stage_mask) = buffer_usage_access(usage);
+
+        let command_buffer: VkCommandBuffer =
+            self._begin_single_time_commands(self._get_transfer_command_pool())?;
+        let copy_region: VkBufferCopy = VkBufferCopy {
+            srcOffset: 0,
+            dstOffset: 0,
+            size,
+        };
+        vk_cmd_copy_buffer(command_buffer, src_buffer, dst_buffer, 1, &copy_region);
+
+        if transfer_family != graphics_family {
+            // Release half of the ownership transfer, the graphics queue acquires it below.
+            let release_barrier: VkBufferMemoryBarrier = buffer_memory_barrier(
+                dst_buffer,
+                size,
+                VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+                0,
+                transfer_family,
+                graphics_family,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT as u32,
+                0,
+                0,
+                std::ptr::null(),
+                1,
+                &release_barrier,
+                0,
+                std::ptr::null(),
+            );
+        }
+        self._end_single_time_commands(
+            self._get_transfer_command_pool(),
+            self._get_transfer_queue(),
+            command_buffer,
+        )?;
+
+        if transfer_family != graphics_family {
+            let command_buffer: VkCommandBuffer =
+                self._begin_single_time_commands(self._get_command_pool())?;
+            let acquire_barrier: VkBufferMemoryBarrier = buffer_memory_barrier(
+                dst_buffer,
+                size,
+                0,
+                dst_access_mask,
+                transfer_family,
+                graphics_family,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT as u32,
+                dst_stage_mask,
+                0,
+                0,
+                std::ptr::null(),
+                1,
+                &acquire_barrier,
+                0,
+                std::ptr::null(),
+            );
+            self._end_single_time_commands(
+                self._get_command_pool(),
+                self._get_graphics_queue(),
+                command_buffer,
+            )?;
+        }
+
+        Ok(())
+    }
+    // Copies tightly packed mip levels, base level first, into an image created with TRANSFER_DST
+    // usage. Levels past the provided ones are blitted from the level above on the graphics queue,
+    // which also needs TRANSFER_SRC usage. The image ends up in SHADER_READ_ONLY_OPTIMAL layout,
+    // owned by the graphics queue family.
+    pub fn upload_image(
+        &self,
+        levels: &[ImageData],
+        image: VkImage,
+        mip_levels: u32,
+    ) -> Result<(), VulkanError> {
+        let pixels: Vec<u8> = levels
+            .iter()
+            .flat_map(|level| level.pixels.iter().copied())
+            .collect();
+        let (staging_buffer, staging_buffer_memory) =
+            self._create_host_buffer(&pixels, VK_BUFFER_USAGE_TRANSFER_SRC_BIT as u32)?;
+
+        let mut regions: Vec<VkBufferImageCopy> = Vec::with_capacity(levels.len());
+        let mut buffer_offset: VkDeviceSize = 0;
+        for (mip_level, level) in levels.iter().enumerate() {
+            regions.push(VkBufferImageCopy {
+                bufferOffset: buffer_offset,
+                bufferRowLength: 0,
+                bufferImageHeight: 0,
+                imageSubresource: color_subresource_layers(mip_level as u32),
+                imageOffset: VkOffset3D { x: 0, y: 0, z: 0 },
+                imageExtent: VkExtent3D {
+                    width: level.width,
+                    height: level.height,
+                    depth: 1,
+                },
+            });
+            buffer_offset += level.pixels.len() as VkDeviceSize;
+        }
+
+        let result: Result<(), VulkanError> = self
+            ._copy_buffer_to_image(staging_buffer, image, &regions, mip_levels)
+            .and_then(|_| {
+                self._prepare_image_for_sampling(
+                    image,
+                    levels[0].width,
+                    levels[0].height,
+                    levels.len() as u32,
+                    mip_levels,
+                )
+            });
+
+        vk_destroy_buffer(self._get_device(), staging_buffer, std::ptr::null());
+        vk_free_memory(self._get_device(), staging_buffer_memory, std::ptr::null());
+
+        result
+    }
+    // Leaves every mip level in TRANSFER_DST_OPTIMAL layout with the regions copied in. When the
+    // transfer family is separate, its half of the ownership transfer to graphics is recorded too.
+    fn _copy_buffer_to_image(
+        &self,
+        buffer: VkBuffer,
+        image: VkImage,
+        regions: &[VkBufferImageCopy],
+        mip_levels: u32,
+    ) -> Result<(), VulkanError> {
+        let indices: QueueFamilyIndices = self._find_queue_families(&self._get_physical_device());
+        let transfer_family: u32 = indices.get_transfer_family();
+        let graphics_family: u32 = indices.get_graphics_family();
+
+        let command_buffer: VkCommandBuffer =
+            self._begin_single_time_commands(self._get_transfer_command_pool())?;
+
+        let to_transfer_barrier: VkImageMemoryBarrier = image_memory_barrier(
+            image,
+            color_subresource_range(0, mip_levels),
+            VK_IMAGE_LAYOUT_UNDEFINED,
+            VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            0,
+            VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+        );
+        vk_cmd_pipeline_barrier(
+            command_buffer,
+            VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT as u32,
+            VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+            0,
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            1,
+            &to_transfer_barrier,
+        );
+
+        vk_cmd_copy_buffer_to_image(
+            command_buffer,
+            buffer,
+            image,
+            VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            regions.len() as u32,
+            regions.as_ptr(),
+        );
+
+        if transfer_family != graphics_family {
+            let release_barrier: VkImageMemoryBarrier = image_memory_barrier(
+                image,
+                color_subresource_range(0, mip_levels),
+                VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+                0,
+                transfer_family,
+                graphics_family,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT as u32,
+                0,
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                1,
+                &release_barrier,
+            );
+        }
+
+        self._end_single_time_commands(
+            self._get_transfer_command_pool(),
+            self._get_transfer_queue(),
+            command_buffer,
+        )
+    }
+    // Runs on the graphics queue: a transfer-only queue can neither blit nor reach the fragment
+    // shader stage, so mip generation and the final layout change both happen here.
+    fn _prepare_image_for_sampling(
+        &self,
+        image: VkImage,
+        width: u32,
+        height: u32,
+        uploaded_levels: u32,
+        mip_levels: u32,
+    ) -> Result<(), VulkanError> {
+        let indices: QueueFamilyIndices = self._find_queue_families(&self._get_physical_device());
+        let transfer_family: u32 = indices.get_transfer_family();
+        let graphics_family: u32 = indices.get_graphics_family();
+
+        let command_buffer: VkCommandBuffer =
+            self._begin_single_time_commands(self._get_command_pool())?;
+
+        if transfer_family != graphics_family {
+            let acquire_barrier: VkImageMemoryBarrier = image_memory_barrier(
+                image,
+                color_subresource_range(0, mip_levels),
+                VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                0,
+                VK_ACCESS_TRANSFER_READ_BIT as u32 | VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+                transfer_family,
+                graphics_family,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT as u32,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                0,
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                1,
+                &acquire_barrier,
+            );
+        }
+
+        if uploaded_levels < mip_levels {
+            self._generate_mipmaps(
+                command_buffer,
+                image,
+                width,
+                height,
+                uploaded_levels,
+                mip_levels,
+            );
+        } else {
+            let to_shader_barrier: VkImageMemoryBarrier = image_memory_barrier(
+                image,
+                color_subresource_range(0, mip_levels),
+                VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+                VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+                VK_ACCESS_SHADER_READ_BIT as u32,
+                VK_QUEUE_FAMILY_IGNORED as u32,
+                VK_QUEUE_FAMILY_IGNORED as u32,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT as u32,
+                0,
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                1,
+                &to_shader_barrier,
+            );
+        }
+
+        self._end_single_time_commands(
+            self._get_command_pool(),
+            self._get_graphics_queue(),
+            command_buffer,
+        )
+    }
+    // Fills levels from first_generated_level on by blitting each level into the next one, and
+    // moves every level to SHADER_READ_ONLY_OPTIMAL once nothing reads from it anymore.
+    fn _generate_mipmaps(
+        &self,
+        command_buffer: VkCommandBuffer,
+        image: VkImage,
+        width: u32,
+        height: u32,
+        first_generated_level: u32,
+        mip_levels: u32,
+    ) {
+        let mut mip_width: i32 = width as i32;
+        let mut mip_height: i32 = height as i32;
+
+        for mip_level in 1..mip_levels {
+            let next_width: i32 = (mip_width / 2).max(1);
+            let next_height: i32 = (mip_height / 2).max(1);
+
+            let to_source_barrier: VkImageMemoryBarrier = image_memory_barrier(
+                image,
+                color_subresource_range(mip_level - 1, 1),
+                VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+                VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+                VK_ACCESS_TRANSFER_READ_BIT as u32,
+                VK_QUEUE_FAMILY_IGNORED as u32,
+                VK_QUEUE_FAMILY_IGNORED as u32,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                0,
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                1,
+                &to_source_barrier,
+            );
+
+            if mip_level >= first_generated_level {
+                let blit: VkImageBlit = VkImageBlit {
+                    srcSubresource: color_subresource_layers(mip_level - 1),
+                    srcOffsets: [
+                        VkOffset3D { x: 0, y: 0, z: 0 },
+                        VkOffset3D {
+                            x: mip_width,
+                            y: mip_height,
+                            z: 1,
+                        },
+                    ],
+                    dstSubresource: color_subresource_layers(mip_level),
+                    dstOffsets: [
+                        VkOffset3D { x: 0, y: 0, z: 0 },
+                        VkOffset3D {
+                            x: next_width,
+                            y: next_height,
+                            z: 1,
+                        },
+                    ],
+                };
+                vk_cmd_blit_image(
+                    command_buffer,
+                    image,
+                    VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+                    image,
+                    VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                    1,
+                    &blit,
+                    VK_FILTER_LINEAR,
+                );
+            }
+
+            let to_shader_barrier: VkImageMemoryBarrier = image_memory_barrier(
+                image,
+                color_subresource_range(mip_level - 1, 1),
+                VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+                VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+                VK_ACCESS_TRANSFER_READ_BIT as u32,
+                VK_ACCESS_SHADER_READ_BIT as u32,
+                VK_QUEUE_FAMILY_IGNORED as u32,
+                VK_QUEUE_FAMILY_IGNORED as u32,
+            );
+            vk_cmd_pipeline_barrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+                VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT as u32,
+                0,
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                1,
+                &to_shader_barrier,
+            );
+
+            mip_width = next_width;
+            mip_height = next_height;
+        }
+
+        // The last level is only ever written, so it is still in TRANSFER_DST_OPTIMAL.
+        let last_level_barrier: VkImageMemoryBarrier = image_memory_barrier(
+            image,
+            color_subresource_range(mip_levels - 1, 1),
+            VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+            VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+            VK_ACCESS_SHADER_READ_BIT as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+        );
+        vk_cmd_pipeline_barrier(
+            command_buffer,
+            VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+            VK_PIPELINE_STAGE_FRAGMENT_SHADER_BIT as u32,
+            0,
+            0,
             std::ptr::null(),
-            &mut render_finished_semaphore,
+            0,
+            std::ptr::null(),
+            1,
+            &last_level_barrier,
         );
-        let result_fence: VkResult = vk_create_fence(
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn _create_image(
+        &self,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        num_samples: VkSampleCountFlagBits,
+        format: VkFormat,
+        tiling: VkImageTiling,
+        usage: VkImageUsageFlags,
+        properties: VkMemoryPropertyFlags,
+    ) -> Result<(VkImage, VkDeviceMemory), VulkanError> {
+        let image_info: VkImageCreateInfo = VkImageCreateInfo {
+            sType: VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+            imageType: VK_IMAGE_TYPE_2D,
+            format,
+            extent: VkExtent3D {
+                width,
+                height,
+                depth: 1,
+            },
+            mipLevels: mip_levels,
+            arrayLayers: 1,
+            samples: num_samples,
+            tiling,
+            usage,
+            sharingMode: VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: std::ptr::null(),
+            initialLayout: VK_IMAGE_LAYOUT_UNDEFINED,
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let mut image: VkImage = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_image(
             self._get_device(),
-            &fence_info,
+            &image_info,
             std::ptr::null(),
-            &mut in_flight_fence,
+            &mut image,
         );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create image", result));
+        }
+
+        let mut memory_requirements: VkMemoryRequirements = unsafe { std::mem::zeroed() };
+        vk_get_image_memory_requirements(self._get_device(), image, &mut memory_requirements);
+
+        let memory_type_index: u32 =
+            match self._find_memory_type(memory_requirements.memoryTypeBits, properties) {
+                Ok(memory_type_index) => memory_type_index,
+                Err(error) => {
+                    vk_destroy_image(self._get_device(), image, std::ptr::null());
+                    return Err(error);
+                }
+            };
+        let alloc_info: VkMemoryAllocateInfo = VkMemoryAllocateInfo {
+            sType: VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
+            allocationSize: memory_requirements.size,
+            memoryTypeIndex: memory_type_index,
+            pNext: std::ptr::null(),
+        };
+
+        let mut image_memory: VkDeviceMemory = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_allocate_memory(
+            self._get_device(),
+            &alloc_info,
+            std::ptr::null(),
+            &mut image_memory,
+        );
+        if result != VK_SUCCESS {
+            vk_destroy_image(self._get_device(), image, std::ptr::null());
+            return Err(VulkanError::vk("allocate image memory", result));
+        }
+
+        let result: VkResult = vk_bind_image_memory(self._get_device(), image, image_memory, 0);
+        if result != VK_SUCCESS {
+            vk_destroy_image(self._get_device(), image, std::ptr::null());
+            vk_free_memory(self._get_device(), image_memory, std::ptr::null());
+            return Err(VulkanError::vk("bind image memory", result));
+        }
+
+        Ok((image, image_memory))
+    }
+    fn _create_texture_sampler(&self, mip_levels: u32) -> Result<VkSampler, VulkanError> {
+        let mut properties: VkPhysicalDeviceProperties = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_properties(self._get_physical_device(), &mut properties);
+
+        let (anisotropy_enable, max_anisotropy) = if self.sampler_anisotropy.get() {
+            (VK_TRUE, properties.limits.maxSamplerAnisotropy)
+        } else {
+            (VK_FALSE, 1.0)
+        };
+
+        let sampler_info: VkSamplerCreateInfo = VkSamplerCreateInfo {
+            sType: VK_STRUCTURE_TYPE_SAMPLER_CREATE_INFO,
+            magFilter: VK_FILTER_LINEAR,
+            minFilter: VK_FILTER_LINEAR,
+            mipmapMode: VK_SAMPLER_MIPMAP_MODE_LINEAR,
+            addressModeU: VK_SAMPLER_ADDRESS_MODE_REPEAT,
+            addressModeV: VK_SAMPLER_ADDRESS_MODE_REPEAT,
+            addressModeW: VK_SAMPLER_ADDRESS_MODE_REPEAT,
+            mipLodBias: 0.0,
+            anisotropyEnable: anisotropy_enable,
+            maxAnisotropy: max_anisotropy,
+            compareEnable: VK_FALSE,
+            compareOp: VK_COMPARE_OP_ALWAYS,
+            minLod: 0.0,
+            maxLod: mip_levels as f32,
+            borderColor: VK_BORDER_COLOR_INT_OPAQUE_BLACK,
+            unnormalizedCoordinates: VK_FALSE,
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let mut sampler: VkSampler = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_sampler(
+            self._get_device(),
+            &sampler_info,
+            std::ptr::null(),
+            &mut sampler,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create texture sampler", result));
+        }
+
+        Ok(sampler)
+    }
+    // Blitting between mip levels needs linear filtering support for the texture format.
+    fn _supports_linear_blit(&self, format: VkFormat) -> bool {
+        let mut properties: VkFormatProperties = unsafe { std::mem::zeroed() };
+        vk_get_physical_device_format_properties(
+            self._get_physical_device(),
+            format,
+            &mut properties,
+        );
+
+        properties.optimalTilingFeatures & VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT as u32
+            != 0
+    }
+    fn _load_texture_levels(&self, path: &str) -> Result<(Vec<ImageData>, u32), VulkanError> {
+        let image_data: ImageData = if self.settings.ktx2_textures {
+            let (mut levels, level_count) = ktx2::load(path)?;
+            if level_count != 0 {
+                let mip_levels: u32 = levels.len() as u32;
+                return Ok((levels, mip_levels));
+            }
+            // Only the base level is stored, the mips are generated like for PNG and JPEG files.
+            levels.remove(0)
+        } else {
+            ImageData::load(path)?
+        };
+        let mip_levels: u32 = image_data.mip_level_count();
+        if self._supports_linear_blit(VK_FORMAT_R8G8B8A8_SRGB) {
+            return Ok((vec![image_data], mip_levels));
+        }
+
+        if debug_mode() {
+            println!(
+                "Linear blit not supported, building mip levels of {} on the CPU",
+                path
+            );
+        }
+        Ok((image_data.mip_chain(), mip_levels))
+    }
+    fn _load_texture(&self, path: &str) -> Result<Texture, VulkanError> {
+        let (levels, mip_levels) = self._load_texture_levels(path)?;
+        let (width, height) = (levels[0].width, levels[0].height);
+
+        let (image, image_memory) = self._create_image(
+            width,
+            height,
+            mip_levels,
+            VK_SAMPLE_COUNT_1_BIT,
+            VK_FORMAT_R8G8B8A8_SRGB,
+            VK_IMAGE_TILING_OPTIMAL,
+            VK_IMAGE_USAGE_TRANSFER_SRC_BIT as u32
+                | VK_IMAGE_USAGE_TRANSFER_DST_BIT as u32
+                | VK_IMAGE_USAGE_SAMPLED_BIT as u32,
+            VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT as u32,
+        )?;
+
+        let image_view: Result<VkImageView, VulkanError> =
+            self.upload_image(&levels, image, mip_levels).and_then(|_| {
+                self._create_image_view(
+                    image,
+                    VK_FORMAT_R8G8B8A8_SRGB,
+                    VK_IMAGE_ASPECT_COLOR_BIT as u32,
+                    mip_levels,
+                )
+            });
+        let image_view: VkImageView = match image_view {
+            Ok(image_view) => image_view,
+            Err(error) => {
+                vk_destroy_image(self._get_device(), image, std::ptr::null());
+                vk_free_memory(self._get_device(), image_memory, std::ptr::null());
+                return Err(error);
+            }
+        };
+
+        let sampler: VkSampler = match self._create_texture_sampler(mip_levels) {
+            Ok(sampler) => sampler,
+            Err(error) => {
+                vk_destroy_image_view(self._get_device(), image_view, std::ptr::null());
+                vk_destroy_image(self._get_device(), image, std::ptr::null());
+                vk_free_memory(self._get_device(), image_memory, std::ptr::null());
+                return Err(error);
+            }
+        };
+
+        if debug_mode() {
+            println!(
+                "Texture {} loaded ({}x{}, {} mip levels)",
+                path, width, height, mip_levels
+            );
+        }
+
+        Ok(Texture {
+            image,
+            image_memory,
+            image_view,
+            sampler,
+        })
+    }
+    fn _load_model(&self) -> Result<(), VulkanError> {
+        let model: Mesh = mesh::load(&self.settings.model_path)?;
+        if debug_mode() {
+            println!(
+                "Model {} loaded ({} vertices, {} triangles, {} materials)",
+                self.settings.model_path,
+                model.vertices.len(),
+                model.face_materials.len(),
+                model.materials.len()
+            );
+        }
+        self.model
+            .set(model)
+            .expect("Model can not be initialized!");
+
+        Ok(())
+    }
+    fn _create_texture(&self) -> Result<(), VulkanError> {
+        // The whole model is drawn with a single texture, the first one its materials name.
+        let model_texture: Option<String> = self
+            ._get_model()
+            .materials
+            .iter()
+            .find_map(|material| material.diffuse_texture.clone());
+        let path: String = if self.settings.ktx2_textures {
+            String::from(KTX2_TEXTURE_PATH)
+        } else {
+            model_texture.unwrap_or_else(|| String::from(TEXTURE_PATH))
+        };
+        let texture: Texture = self._load_texture(&path)?;
+        self.texture
+            .set(texture)
+            .expect("Texture can not be initialized!");
+
+        Ok(())
+    }
+    fn _create_vertex_buffer(&self) -> Result<(), VulkanError> {
+        let (vertex_buffer, vertex_buffer_memory) = self.upload_buffer(
+            &self._get_model().vertices,
+            VK_BUFFER_USAGE_VERTEX_BUFFER_BIT as u32,
+        )?;
+
+        self.vertex_buffer
+            .set(vertex_buffer)
+            .expect("Vertex buffer can not be initialized!");
+        self.vertex_buffer_memory
+            .set(vertex_buffer_memory)
+            .expect("Vertex buffer memory can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan vertex buffer created");
+        }
+
+        Ok(())
+    }
+    fn _create_index_buffer(&self) -> Result<(), VulkanError> {
+        let (index_buffer, index_buffer_memory) = self.upload_buffer(
+            &self._get_model().indices,
+            VK_BUFFER_USAGE_INDEX_BUFFER_BIT as u32,
+        )?;
+
+        self.index_buffer
+            .set(index_buffer)
+            .expect("Index buffer can not be initialized!");
+        self.index_buffer_memory
+            .set(index_buffer_memory)
+            .expect("Index buffer memory can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan index buffer created");
+        }
+
+        Ok(())
+    }
+    fn _create_uniform_buffers(&self) -> Result<(), VulkanError> {
+        let buffer_size: VkDeviceSize = std::mem::size_of::<UniformBufferObject>() as VkDeviceSize;
+
+        let mut uniform_buffers: Vec<VkBuffer> = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        let mut uniform_buffers_memory: Vec<VkDeviceMemory> =
+            Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        let mut uniform_buffers_mapped: Vec<*mut c_void> = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+
+        for _ in 0..MAX_FRAMES_IN_FLIGHT {
+            let (uniform_buffer, uniform_buffer_memory) = self._create_buffer(
+                buffer_size,
+                VK_BUFFER_USAGE_UNIFORM_BUFFER_BIT as u32,
+                VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT as u32
+                    | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT as u32,
+            )?;
+
+            // Stays mapped for the lifetime of the buffer, it is written every frame.
+            let mut mapped: *mut c_void = std::ptr::null_mut();
+            let result: VkResult = vk_map_memory(
+                self._get_device(),
+                uniform_buffer_memory,
+                0,
+                buffer_size,
+                0,
+                &mut mapped,
+            );
+            if result != VK_SUCCESS {
+                vk_destroy_buffer(self._get_device(), uniform_buffer, std::ptr::null());
+                vk_free_memory(self._get_device(), uniform_buffer_memory, std::ptr::null());
+                return Err(VulkanError::vk("map uniform buffer memory", result));
+            }
+
+            uniform_buffers.push(uniform_buffer);
+            uniform_buffers_memory.push(uniform_buffer_memory);
+            uniform_buffers_mapped.push(mapped);
+        }
+
+        self.uniform_buffers
+            .set(uniform_buffers)
+            .expect("Uniform buffers can not be initialized!");
+        self.uniform_buffers_memory
+            .set(uniform_buffers_memory)
+            .expect("Uniform buffers memory can not be initialized!");
+        self.uniform_buffers_mapped
+            .set(uniform_buffers_mapped)
+            .expect("Uniform buffers mapped can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan uniform buffers created");
+        }
+
+        Ok(())
+    }
+    fn _create_descriptor_pool(&self) -> Result<(), VulkanError> {
+        let pool_sizes: Vec<VkDescriptorPoolSize> = self
+            ._get_pipeline_shaders()
+            .descriptor_pool_sizes(MAX_FRAMES_IN_FLIGHT as u32);
+
+        let pool_info: VkDescriptorPoolCreateInfo = VkDescriptorPoolCreateInfo {
+            sType: VK_STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO,
+            maxSets: MAX_FRAMES_IN_FLIGHT as u32,
+            poolSizeCount: pool_sizes.len() as u32,
+            pPoolSizes: pool_sizes.as_ptr(),
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let mut descriptor_pool: VkDescriptorPool = unsafe { std::mem::zeroed() };
+        let result: VkResult = vk_create_descriptor_pool(
+            self._get_device(),
+            &pool_info,
+            std::ptr::null(),
+            &mut descriptor_pool,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("create descriptor pool", result));
+        }
+        self.descriptor_pool
+            .set(descriptor_pool)
+            .expect("Descriptor pool can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan descriptor pool created");
+        }
+
+        Ok(())
+    }
+    fn _create_descriptor_sets(&self) -> Result<(), VulkanError> {
+        let layouts: Vec<VkDescriptorSetLayout> =
+            vec![self._get_descriptor_set_layout(); MAX_FRAMES_IN_FLIGHT];
+        let alloc_info: VkDescriptorSetAllocateInfo = VkDescriptorSetAllocateInfo {
+            sType: VK_STRUCTURE_TYPE_DESCRIPTOR_SET_ALLOCATE_INFO,
+            descriptorPool: self._get_descriptor_pool(),
+            descriptorSetCount: MAX_FRAMES_IN_FLIGHT as u32,
+            pSetLayouts: layouts.as_ptr(),
+            pNext: std::ptr::null(),
+        };
+
+        let mut descriptor_sets: Vec<VkDescriptorSet> =
+            vec![std::ptr::null_mut(); MAX_FRAMES_IN_FLIGHT];
+        let result: VkResult = vk_allocate_descriptor_sets(
+            self._get_device(),
+            &alloc_info,
+            descriptor_sets.as_mut_ptr(),
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("allocate descriptor sets", result));
+        }
+
+        for (frame, descriptor_set) in descriptor_sets.iter().enumerate() {
+            let buffer_info: VkDescriptorBufferInfo = VkDescriptorBufferInfo {
+                buffer: self._get_uniform_buffer(frame),
+                offset: 0,
+                range: std::mem::size_of::<UniformBufferObject>() as VkDeviceSize,
+            };
+
+            let image_info: VkDescriptorImageInfo = self._get_texture().descriptor_image_info();
+
+            // Bindings the shaders do not use are not in the layout and must not be written.
+            let descriptor_writes: Vec<VkWriteDescriptorSet> = self
+                ._get_pipeline_shaders()
+                .bindings
+                .iter()
+                .map(|layout_binding| VkWriteDescriptorSet {
+                    sType: VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
+                    dstSet: *descriptor_set,
+                    dstBinding: layout_binding.binding.binding,
+                    dstArrayElement: 0,
+                    descriptorType: reflect::descriptor_type(
+                        layout_binding.binding.descriptor_type,
+                    ),
+                    descriptorCount: 1,
+                    pBufferInfo: if layout_binding.binding.binding == UNIFORM_BUFFER_BINDING {
+                        &buffer_info
+                    } else {
+                        std::ptr::null()
+                    },
+                    pImageInfo: if layout_binding.binding.binding == TEXTURE_BINDING {
+                        &image_info
+                    } else {
+                        std::ptr::null()
+                    },
+                    pTexelBufferView: std::ptr::null(),
+                    pNext: std::ptr::null(),
+                })
+                .collect();
+            vk_update_descriptor_sets(
+                self._get_device(),
+                descriptor_writes.len() as u32,
+                descriptor_writes.as_ptr(),
+                0,
+                std::ptr::null(),
+            );
+        }
+
+        self.descriptor_sets
+            .set(descriptor_sets)
+            .expect("Descriptor sets can not be initialized!");
+        if debug_mode() {
+            println!("Vulkan descriptor sets created");
+        }
+
+        Ok(())
+    }
+    fn _update_uniform_buffer(&self, current_frame: usize) {
+        let time: f32 = if self.settings.headless {
+            self.frames_rendered.get() as f32 * HEADLESS_FRAME_TIME
+        } else {
+            self.start_time.elapsed().as_secs_f32()
+        };
+        let extent: VkExtent2D = self._get_swapchain_extent();
+
+        let ubo: UniformBufferObject = UniformBufferObject {
+            model: math::rotation(time * 90.0_f32.to_radians(), [0.0, 0.0, 1.0]),
+            view: math::look_at([2.0, 2.0, 2.0], [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            proj: math::perspective(
+                45.0_f32.to_radians(),
+                extent.width as f32 / extent.height as f32,
+                0.1,
+                10.0,
+            ),
+        };
+
+        unsafe {
+            std::ptr::write_unaligned(
+                self._get_uniform_buffer_mapped(current_frame) as *mut UniformBufferObject,
+                ubo,
+            );
+        }
+    }
+    fn _create_readback_buffer(&self) -> Result<(), VulkanError> {
+        let extent: VkExtent2D = self._get_swapchain_extent();
+        let size: VkDeviceSize = extent.width as VkDeviceSize * extent.height as VkDeviceSize * 4;
+        let (readback_buffer, readback_buffer_memory) = self._create_buffer(
+            size,
+            VK_BUFFER_USAGE_TRANSFER_DST_BIT as u32,
+            VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT as u32
+                | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT as u32,
+        )?;
+
+        self.readback_buffer
+            .set(readback_buffer)
+            .expect("Readback buffer can not be initialized!");
+        self.readback_buffer_memory
+            .set(readback_buffer_memory)
+            .expect("Readback buffer memory can not be initialized!");
+
+        Ok(())
+    }
+    fn _create_command_buffers(&self) -> Result<(), VulkanError> {
+        let alloc_info: VkCommandBufferAllocateInfo = VkCommandBufferAllocateInfo {
+            sType: VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
+            commandPool: self._get_command_pool(),
+            level: VK_COMMAND_BUFFER_LEVEL_PRIMARY,
+            commandBufferCount: MAX_FRAMES_IN_FLIGHT as u32,
+            pNext: std::ptr::null(),
+        };
+
+        let mut command_buffers: Vec<VkCommandBuffer> =
+            vec![std::ptr::null_mut(); MAX_FRAMES_IN_FLIGHT];
+        let result: VkResult = vk_allocate_command_buffers(
+            self._get_device(),
+            &alloc_info,
+            command_buffers.as_mut_ptr(),
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("allocate command buffers", result));
+        }
+        if debug_mode() {
+            println!("Vulkan command buffers created");
+        }
+        self.command_buffers
+            .set(command_buffers)
+            .expect("Command buffers can not be initialized!");
+
+        Ok(())
+    }
+    fn _create_sync_objects(&self) -> Result<(), VulkanError> {
+        let semaphore_info: VkSemaphoreCreateInfo = VkSemaphoreCreateInfo {
+            sType: VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+            pNext: std::ptr::null(),
+            flags: 0,
+        };
+
+        let fence_info: VkFenceCreateInfo = VkFenceCreateInfo {
+            sType: VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            flags: VK_FENCE_CREATE_SIGNALED_BIT as u32,
+            pNext: std::ptr::null(),
+        };
+
+        let mut image_available_semaphores: Vec<VkSemaphore> =
+            Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        let mut render_finished_semaphores: Vec<VkSemaphore> =
+            Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        let mut in_flight_fences: Vec<VkFence> = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+
+        for _ in 0..MAX_FRAMES_IN_FLIGHT {
+            let mut image_available_semaphore: VkSemaphore = unsafe { std::mem::zeroed() };
+            let mut render_finished_semaphore: VkSemaphore = unsafe { std::mem::zeroed() };
+            let mut in_flight_fence: VkFence = unsafe { std::mem::zeroed() };
+
+            let result_semaphore_image_available: VkResult = vk_create_semaphore(
+                self._get_device(),
+                &semaphore_info,
+                std::ptr::null(),
+                &mut image_available_semaphore,
+            );
+            let result_semaphore_render_finished: VkResult = vk_create_semaphore(
+                self._get_device(),
+                &semaphore_info,
+                std::ptr::null(),
+                &mut render_finished_semaphore,
+            );
+            let result_fence: VkResult = vk_create_fence(
+                self._get_device(),
+                &fence_info,
+                std::ptr::null(),
+                &mut in_flight_fence,
+            );
+
+            for result in [
+                result_semaphore_image_available,
+                result_semaphore_render_finished,
+                result_fence,
+            ] {
+                if result != VK_SUCCESS {
+                    return Err(VulkanError::vk("create synchronization objects", result));
+                }
+            }
+
+            image_available_semaphores.push(image_available_semaphore);
+            render_finished_semaphores.push(render_finished_semaphore);
+            in_flight_fences.push(in_flight_fence);
+        }
 
-        if result_semaphore_image_available != VK_SUCCESS
-            || result_semaphore_render_finished != VK_SUCCESS
-            || result_fence != VK_SUCCESS
-        {
-            panic!("Failed to create synchronization objects!");
-        }
-        self.image_available_semaphore
-            .set(image_available_semaphore)
-            .expect("Image available semaphore can not be initialized!");
-        self.render_finished_semaphore
-            .set(render_finished_semaphore)
-            .expect("Render finished semaphore can not be initialized!");
-        self.in_flight_fence
-            .set(in_flight_fence)
-            .expect("In flight fence can not be initialized!");
+        self.image_available_semaphores
+            .set(image_available_semaphores)
+            .expect("Image available semaphores can not be initialized!");
+        self.render_finished_semaphores
+            .set(render_finished_semaphores)
+            .expect("Render finished semaphores can not be initialized!");
+        self.in_flight_fences
+            .set(in_flight_fences)
+            .expect("In flight fences can not be initialized!");
         if debug_mode() {
             println!("Vulkan synchronization objects created");
         }
+
+        Ok(())
     }
 
-    fn _record_command_buffer(&self, command_buffer: VkCommandBuffer, image_index: u32) {
+    // With a screenshot buffer the drawn image is also copied into it, see capture_screenshot.
+    fn _record_command_buffer(
+        &self,
+        command_buffer: VkCommandBuffer,
+        image_index: u32,
+        current_frame: usize,
+        screenshot_buffer: Option<VkBuffer>,
+    ) -> Result<(), VulkanError> {
         let begin_info: VkCommandBufferBeginInfo = VkCommandBufferBeginInfo {
             sType: VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO,
             flags: 0,
@@ -1616,14 +4244,22 @@ impl VulkanApi {
 
         let result: VkResult = vk_begin_command_buffer(command_buffer, &begin_info);
         if result != VK_SUCCESS {
-            panic!("Failed to begin recording command buffer!");
+            return Err(VulkanError::vk("begin recording command buffer", result));
         }
 
-        let clear_color: VkClearValue = VkClearValue {
-            color: VkClearColorValue {
-                float32: [0.0, 0.0, 0.0, 1.0],
+        let clear_values: Vec<VkClearValue> = vec![
+            VkClearValue {
+                color: VkClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
             },
-        };
+            VkClearValue {
+                depthStencil: VkClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
         let render_area = VkRect2D {
             offset: VkOffset2D { x: 0, y: 0 },
             extent: self._get_swapchain_extent(),
@@ -1633,8 +4269,8 @@ impl VulkanApi {
             renderPass: self._get_render_pass(),
             framebuffer: self._get_swapchain_framebuffers()[image_index as usize],
             renderArea: render_area,
-            clearValueCount: 1,
-            pClearValues: &clear_color,
+            clearValueCount: clear_values.len() as u32,
+            pClearValues: clear_values.as_ptr(),
             pNext: std::ptr::null(),
         };
 
@@ -1664,12 +4300,375 @@ impl VulkanApi {
             extent: self._get_swapchain_extent(),
         };
         vk_cmd_set_scissor(command_buffer, 0, 1, &scissor);
-        vk_cmd_draw(command_buffer, 3, 1, 0, 0);
+
+        let vertex_buffers: Vec<VkBuffer> = vec![self._get_vertex_buffer()];
+        let offsets: Vec<VkDeviceSize> = vec![0];
+        vk_cmd_bind_vertex_buffers(
+            command_buffer,
+            0,
+            1,
+            vertex_buffers.as_ptr(),
+            offsets.as_ptr(),
+        );
+        vk_cmd_bind_index_buffer(
+            command_buffer,
+            self._get_index_buffer(),
+            0,
+            VK_INDEX_TYPE_UINT32,
+        );
+        vk_cmd_bind_descriptor_sets(
+            command_buffer,
+            VK_PIPELINE_BIND_POINT_GRAPHICS,
+            self._get_pipeline_layout(),
+            0,
+            1,
+            &self._get_descriptor_set(current_frame),
+            0,
+            std::ptr::null(),
+        );
+        vk_cmd_draw_indexed(
+            command_buffer,
+            self._get_model().indices.len() as u32,
+            1,
+            0,
+            0,
+            0,
+        );
         vk_cmd_end_render_pass(command_buffer);
 
+        if self.settings.headless {
+            self._record_image_copy(
+                command_buffer,
+                self._get_swapchain_images()[0],
+                self._get_readback_buffer(),
+            );
+        }
+        if let Some(buffer) = screenshot_buffer {
+            self._record_screenshot_copy(
+                command_buffer,
+                self._get_swapchain_images()[image_index as usize],
+                buffer,
+            );
+        }
+
         let result: VkResult = vk_end_command_buffer(command_buffer);
         if result != VK_SUCCESS {
-            panic!("Failed to record command buffer!");
+            return Err(VulkanError::vk("record command buffer", result));
+        }
+
+        Ok(())
+    }
+
+    // Copies a swapchain-sized color image in TRANSFER_SRC_OPTIMAL into a host-visible buffer.
+    // Headless, the render pass leaves the image in that layout and its outgoing dependency
+    // orders the copy after the color writes.
+    fn _record_image_copy(
+        &self,
+        command_buffer: VkCommandBuffer,
+        image: VkImage,
+        buffer: VkBuffer,
+    ) {
+        let extent: VkExtent2D = self._get_swapchain_extent();
+        let region: VkBufferImageCopy = VkBufferImageCopy {
+            bufferOffset: 0,
+            bufferRowLength: 0,
+            bufferImageHeight: 0,
+            imageSubresource: color_subresource_layers(0),
+            imageOffset: VkOffset3D { x: 0, y: 0, z: 0 },
+            imageExtent: VkExtent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            },
+        };
+        vk_cmd_copy_image_to_buffer(
+            command_buffer,
+            image,
+            VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+            buffer,
+            1,
+            &region,
+        );
+
+        let host_barrier: VkBufferMemoryBarrier = buffer_memory_barrier(
+            buffer,
+            extent.width as VkDeviceSize * extent.height as VkDeviceSize * 4,
+            VK_ACCESS_TRANSFER_WRITE_BIT as u32,
+            VK_ACCESS_HOST_READ_BIT as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+        );
+        vk_cmd_pipeline_barrier(
+            command_buffer,
+            VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+            VK_PIPELINE_STAGE_HOST_BIT as u32,
+            0,
+            0,
+            std::ptr::null(),
+            1,
+            &host_barrier,
+            0,
+            std::ptr::null(),
+        );
+    }
+
+    fn _read_image_memory(&self, memory: VkDeviceMemory) -> Result<ImageData, VulkanError> {
+        let extent: VkExtent2D = self._get_swapchain_extent();
+        let size: usize = extent.width as usize * extent.height as usize * 4;
+
+        let mut mapped: *mut c_void = std::ptr::null_mut();
+        let result: VkResult = vk_map_memory(
+            self._get_device(),
+            memory,
+            0,
+            size as VkDeviceSize,
+            0,
+            &mut mapped,
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("map readback buffer memory", result));
+        }
+        let pixels: Vec<u8> =
+            unsafe { std::slice::from_raw_parts(mapped as *const u8, size) }.to_vec();
+        vk_unmap_memory(self._get_device(), memory);
+
+        Ok(ImageData {
+            width: extent.width,
+            height: extent.height,
+            pixels,
+        })
+    }
+
+    // Headless counterpart of draw_frame. The readback buffer is shared by all frames, so each
+    // frame is waited for and read back before the next one is recorded.
+    pub fn render_offscreen_frame(&self) -> Result<ImageData, VulkanError> {
+        let current_frame: usize = self.current_frame.get();
+
+        vk_reset_fences(
+            self._get_device(),
+            1,
+            &self._get_in_flight_fence(current_frame),
+        );
+
+        self._update_uniform_buffer(current_frame);
+
+        vk_reset_command_buffer(self._get_command_buffer(current_frame), 0);
+        self._record_command_buffer(
+            self._get_command_buffer(current_frame),
+            0,
+            current_frame,
+            None,
+        )?;
+
+        let submit_info = VkSubmitInfo {
+            sType: VK_STRUCTURE_TYPE_SUBMIT_INFO,
+            waitSemaphoreCount: 0,
+            pWaitSemaphores: std::ptr::null(),
+            pWaitDstStageMask: std::ptr::null(),
+            commandBufferCount: 1,
+            pCommandBuffers: &self._get_command_buffer(current_frame),
+            signalSemaphoreCount: 0,
+            pSignalSemaphores: std::ptr::null(),
+            pNext: std::ptr::null(),
+        };
+        let result: VkResult = vk_queue_submit(
+            self._get_graphics_queue(),
+            1,
+            &submit_info,
+            self._get_in_flight_fence(current_frame),
+        );
+        if result != VK_SUCCESS {
+            return Err(VulkanError::vk("submit offscreen command buffer", result));
+        }
+        vk_wait_for_fences(
+            self._get_device(),
+            1,
+            &self._get_in_flight_fence(current_frame),
+            VK_TRUE,
+            u64::MAX,
+        );
+
+        let frame: ImageData = self._read_image_memory(self._get_readback_buffer_memory())?;
+
+        self.frames_rendered.set(self.frames_rendered.get() + 1);
+        self.current_frame
+            .set((current_frame + 1) % MAX_FRAMES_IN_FLIGHT);
+
+        Ok(frame)
+    }
+
+    fn _draw_offscreen_frame(&self) -> Result<(), VulkanError> {
+        let path: String = format!(
+            "{}/frame_{:04}.png",
+            HEADLESS_OUTPUT_DIR,
+            self.frames_rendered.get()
+        );
+        let frame: ImageData = self.render_offscreen_frame()?;
+
+        fs::create_dir_all(HEADLESS_OUTPUT_DIR).map_err(|error| {
+            VulkanError::setup(
+                "create output directory",
+                &format!("{}: {}", HEADLESS_OUTPUT_DIR, error),
+            )
+        })?;
+        frame.save_png(&path)?;
+        if debug_mode() {
+            println!("Frame written to {}", path);
+        }
+
+        Ok(())
+    }
+
+    // Saves the next frame drawn to the window as a PNG file. The copy is recorded into that
+    // frame's command buffer, so it reads the image before it is handed to the presentation
+    // engine.
+    pub fn capture_screenshot(&self, path: &str) -> Result<(), VulkanError> {
+        if self.settings.headless {
+            return Err(VulkanError::setup(
+                "capture screenshot",
+                &format!(
+                    "headless frames are written to {} already",
+                    HEADLESS_OUTPUT_DIR
+                ),
+            ));
+        }
+        if !self.swapchain_transfer_src.get() {
+            return Err(VulkanError::setup(
+                "capture screenshot",
+                "the surface does not allow copying from swapchain images",
+            ));
+        }
+        self._screenshot_swaps_red_and_blue()?;
+
+        self.screenshot_path.replace(Some(path.to_string()));
+
+        Ok(())
+    }
+
+    fn _screenshot_swaps_red_and_blue(&self) -> Result<bool, VulkanError> {
+        match self._get_swapchain_image_format() {
+            VK_FORMAT_B8G8R8A8_SRGB | VK_FORMAT_B8G8R8A8_UNORM => Ok(true),
+            VK_FORMAT_R8G8B8A8_SRGB | VK_FORMAT_R8G8B8A8_UNORM => Ok(false),
+            format => Err(VulkanError::setup(
+                "capture screenshot",
+                &format!("unsupported swapchain format {:?}", format),
+            )),
+        }
+    }
+
+    fn _create_screenshot_buffer(&self) -> Result<(VkBuffer, VkDeviceMemory), VulkanError> {
+        let extent: VkExtent2D = self._get_swapchain_extent();
+        self._create_buffer(
+            extent.width as VkDeviceSize * extent.height as VkDeviceSize * 4,
+            VK_BUFFER_USAGE_TRANSFER_DST_BIT as u32,
+            VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT as u32
+                | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT as u32,
+        )
+    }
+
+    // Moves the image out of its presentable layout for the copy and back again afterwards.
+    // Presentation waits for the whole command buffer, so it sees the image back in its layout.
+    fn _record_screenshot_copy(
+        &self,
+        command_buffer: VkCommandBuffer,
+        image: VkImage,
+        buffer: VkBuffer,
+    ) {
+        let to_transfer_barrier: VkImageMemoryBarrier = image_memory_barrier(
+            image,
+            color_subresource_range(0, 1),
+            self._final_color_layout(),
+            VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+            VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT as u32,
+            VK_ACCESS_TRANSFER_READ_BIT as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+        );
+        vk_cmd_pipeline_barrier(
+            command_buffer,
+            VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT as u32,
+            VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+            0,
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            1,
+            &to_transfer_barrier,
+        );
+
+        self._record_image_copy(command_buffer, image, buffer);
+
+        let to_present_barrier: VkImageMemoryBarrier = image_memory_barrier(
+            image,
+            color_subresource_range(0, 1),
+            VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+            self._final_color_layout(),
+            VK_ACCESS_TRANSFER_READ_BIT as u32,
+            0,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+            VK_QUEUE_FAMILY_IGNORED as u32,
+        );
+        vk_cmd_pipeline_barrier(
+            command_buffer,
+            VK_PIPELINE_STAGE_TRANSFER_BIT as u32,
+            VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT as u32,
+            0,
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            1,
+            &to_present_barrier,
+        );
+    }
+
+    // Waits for the frame that copied the image, then writes the buffer out and frees it.
+    fn _save_screenshot(
+        &self,
+        path: &str,
+        buffer: VkBuffer,
+        buffer_memory: VkDeviceMemory,
+        current_frame: usize,
+    ) -> Result<(), VulkanError> {
+        vk_wait_for_fences(
+            self._get_device(),
+            1,
+            &self._get_in_flight_fence(current_frame),
+            VK_TRUE,
+            u64::MAX,
+        );
+        let result: Result<ImageData, VulkanError> = self._read_image_memory(buffer_memory);
+        vk_destroy_buffer(self._get_device(), buffer, std::ptr::null());
+        vk_free_memory(self._get_device(), buffer_memory, std::ptr::null());
+
+        let mut screenshot: ImageData = result?;
+        if self._screenshot_swaps_red_and_blue()? {
+            screenshot.swap_red_and_blue();
+        }
+        screenshot.save_png(path)?;
+
+        Ok(())
+    }
+
+    fn _request_screenshot(&self) {
+        let timestamp: u128 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or(0);
+        let path: String = format!("{}/screenshot_{}.png", SCREENSHOT_DIR, timestamp);
+
+        // A failed screenshot is reported but does not stop the app.
+        let result: Result<(), VulkanError> = fs::create_dir_all(SCREENSHOT_DIR)
+            .map_err(|error| {
+                VulkanError::setup(
+                    "create screenshot directory",
+                    &format!("{}: {}", SCREENSHOT_DIR, error),
+                )
+            })
+            .and_then(|_| self.capture_screenshot(&path));
+        if let Err(error) = result {
+            eprintln!("Screenshot failed: {}", error);
         }
     }
 }
@@ -1687,10 +4686,20 @@ impl GraphicApi for VulkanApi {
             println!("Validation layers enabled");
         }
 
+        if self.settings.headless {
+            if debug_mode() {
+                println!(
+                    "Headless mode, frames are written to {}",
+                    HEADLESS_OUTPUT_DIR
+                );
+            }
+            return Window::Headless;
+        }
+
         glfw_init();
 
         glfw_window_hint(GLFW_CLIENT_API as isize, GLFW_NO_API as isize);
-        glfw_window_hint(GLFW_RESIZABLE as isize, GLFW_FALSE as isize);
+        glfw_window_hint(GLFW_RESIZABLE as isize, GLFW_TRUE as isize);
 
         let window = glfw_create_window(
             self.width as i32,
@@ -1700,6 +4709,13 @@ impl GraphicApi for VulkanApi {
             std::ptr::null_mut(),
         );
 
+        // VulkanApi lives behind a Box in Graphics, so its address is stable for the callback.
+        glfw_set_window_user_pointer(window, self as *const VulkanApi as *mut c_void);
+        let framebuffer_size_callback: GLFWframebuffersizefun = Some(framebuffer_resize_callback);
+        glfw_set_framebuffer_size_callback(window, framebuffer_size_callback);
+        let key_callback_fn: GLFWkeyfun = Some(key_callback);
+        glfw_set_key_callback(window, key_callback_fn);
+
         self.window
             .set(window)
             .expect("Glfw window can not be initialized");
@@ -1707,23 +4723,46 @@ impl GraphicApi for VulkanApi {
         Window::Vulkan(window)
     }
 
-    fn init_api(&self) {
+    fn init_api(&self) -> Result<(), GraphicError> {
         if debug_mode() {
             println!("Vulkan API initialized");
         }
-        self._create_instance();
-        self._setup_debug_messenger();
-        self._create_surface();
-        self._pick_physical_device();
-        self._create_logical_device();
-        self._create_swap_chain();
-        self._create_image_views();
-        self._create_render_pass();
-        self._create_graphics_pipeline();
-        self._create_framebuffers();
-        self._create_command_pool();
-        self._create_command_buffers();
-        self._create_sync_objects();
+        self._create_instance()?;
+        self._setup_debug_messenger()?;
+        if !self.settings.headless {
+            self._create_surface()?;
+        }
+        self._pick_physical_device()?;
+        self._create_logical_device()?;
+        if self.settings.headless {
+            self._create_offscreen_target()?;
+            self._create_readback_buffer()?;
+        } else {
+            self._create_swap_chain()?;
+        }
+        self._create_image_views()?;
+        self._create_render_pass()?;
+        self._load_shaders()?;
+        self._create_descriptor_set_layout()?;
+        self._create_pipeline_layout()?;
+        self._create_graphics_pipeline()?;
+        self._create_shader_watcher();
+        self._create_color_resources()?;
+        self._create_depth_resources()?;
+        self._create_framebuffers()?;
+        self._create_command_pool()?;
+        self._create_transfer_command_pool()?;
+        self._load_model()?;
+        self._create_texture()?;
+        self._create_vertex_buffer()?;
+        self._create_index_buffer()?;
+        self._create_uniform_buffers()?;
+        self._create_descriptor_pool()?;
+        self._create_descriptor_sets()?;
+        self._create_command_buffers()?;
+        self._create_sync_objects()?;
+
+        Ok(())
     }
 
     fn cleanup(&self) {
@@ -1731,80 +4770,157 @@ impl GraphicApi for VulkanApi {
             println!("Vulkan cleanup");
         }
 
-        vk_destroy_semaphore(
-            self._get_device(),
-            self._get_render_finished_semaphore(),
-            std::ptr::null(),
-        );
-        vk_destroy_semaphore(
-            self._get_device(),
-            self._get_image_available_semaphore(),
-            std::ptr::null(),
-        );
-        vk_destroy_fence(
-            self._get_device(),
-            self._get_in_flight_fence(),
-            std::ptr::null(),
-        );
+        // init_api may have stopped half way, so only destroy what was created.
+        if let Some(device) = self.device.get() {
+            let device: VkDevice = *device;
 
-        vk_destroy_command_pool(
-            self._get_device(),
-            self._get_command_pool(),
-            std::ptr::null(),
-        );
+            if let Some(semaphores) = self.render_finished_semaphores.get() {
+                for semaphore in semaphores {
+                    vk_destroy_semaphore(device, *semaphore, std::ptr::null());
+                }
+            }
+            if let Some(semaphores) = self.image_available_semaphores.get() {
+                for semaphore in semaphores {
+                    vk_destroy_semaphore(device, *semaphore, std::ptr::null());
+                }
+            }
+            if let Some(fences) = self.in_flight_fences.get() {
+                for fence in fences {
+                    vk_destroy_fence(device, *fence, std::ptr::null());
+                }
+            }
 
-        for swapchain_framebuffer in self._get_swapchain_framebuffers() {
-            vk_destroy_framebuffer(self._get_device(), *swapchain_framebuffer, std::ptr::null());
-        }
+            if let Some(command_pool) = self.command_pool.get() {
+                vk_destroy_command_pool(device, *command_pool, std::ptr::null());
+            }
+            if let Some(transfer_command_pool) = self.transfer_command_pool.get() {
+                vk_destroy_command_pool(device, *transfer_command_pool, std::ptr::null());
+            }
 
-        vk_destroy_pipeline(
-            self._get_device(),
-            self._get_graphics_pipeline(),
-            std::ptr::null(),
-        );
+            self._cleanup_swap_chain();
 
-        vk_destroy_pipeline_layout(
-            self._get_device(),
-            self._get_pipeline_layout(),
-            std::ptr::null(),
-        );
+            // Unlike swapchain images, the offscreen image is owned by the app.
+            if let Some(offscreen_image_memory) = self.offscreen_image_memory.take() {
+                if let Some(images) = self.swapchain_images.take() {
+                    for image in images {
+                        vk_destroy_image(device, image, std::ptr::null());
+                    }
+                }
+                vk_free_memory(device, offscreen_image_memory, std::ptr::null());
+            }
+            if let Some(readback_buffer) = self.readback_buffer.get() {
+                vk_destroy_buffer(device, *readback_buffer, std::ptr::null());
+            }
+            if let Some(readback_buffer_memory) = self.readback_buffer_memory.get() {
+                vk_free_memory(device, *readback_buffer_memory, std::ptr::null());
+            }
 
-        vk_destroy_render_pass(
-            self._get_device(),
-            self._get_render_pass(),
-            std::ptr::null(),
-        );
+            if let Some(uniform_buffers) = self.uniform_buffers.get() {
+                for uniform_buffer in uniform_buffers {
+                    vk_destroy_buffer(device, *uniform_buffer, std::ptr::null());
+                }
+            }
+            if let Some(uniform_buffers_memory) = self.uniform_buffers_memory.get() {
+                for uniform_buffer_memory in uniform_buffers_memory {
+                    vk_free_memory(device, *uniform_buffer_memory, std::ptr::null());
+                }
+            }
 
-        for swapchain_image_view in self._get_swapchain_image_views() {
-            vk_destroy_image_view(self._get_device(), *swapchain_image_view, std::ptr::null());
-        }
+            if let Some(descriptor_pool) = self.descriptor_pool.get() {
+                vk_destroy_descriptor_pool(device, *descriptor_pool, std::ptr::null());
+            }
+            if let Some(descriptor_set_layout) = self.descriptor_set_layout.get() {
+                vk_destroy_descriptor_set_layout(device, *descriptor_set_layout, std::ptr::null());
+            }
 
-        vk_destroy_swapchain_khr(self._get_device(), self._get_swapchain(), std::ptr::null());
+            if let Some(texture) = self.texture.get() {
+                texture.destroy(device);
+            }
 
-        vk_destroy_device(self._get_device(), std::ptr::null());
+            if let Some(index_buffer) = self.index_buffer.get() {
+                vk_destroy_buffer(device, *index_buffer, std::ptr::null());
+            }
+            if let Some(index_buffer_memory) = self.index_buffer_memory.get() {
+                vk_free_memory(device, *index_buffer_memory, std::ptr::null());
+            }
+            if let Some(vertex_buffer) = self.vertex_buffer.get() {
+                vk_destroy_buffer(device, *vertex_buffer, std::ptr::null());
+            }
+            if let Some(vertex_buffer_memory) = self.vertex_buffer_memory.get() {
+                vk_free_memory(device, *vertex_buffer_memory, std::ptr::null());
+            }
 
-        vk_destroy_surface_khr(self._get_instance(), self._get_surface(), std::ptr::null());
+            // graphics_pipeline is one of the specialized pipelines.
+            self.graphics_pipeline.take();
+            for graphics_pipeline in self.specialized_pipelines.borrow_mut().drain() {
+                vk_destroy_pipeline(device, graphics_pipeline, std::ptr::null());
+            }
 
-        if self._enable_validation_layers() {
-            self.destroy_debug_utils_messenger_ext(
-                &self._get_instance(),
-                &self._get_debug_messenger(),
-                std::ptr::null(),
-            );
+            if let Some(pipeline_layout) = self.pipeline_layout.get() {
+                vk_destroy_pipeline_layout(device, *pipeline_layout, std::ptr::null());
+            }
+
+            if let Some(render_pass) = self.render_pass.get() {
+                vk_destroy_render_pass(device, *render_pass, std::ptr::null());
+            }
+
+            if let Some(swapchain) = self.swapchain.take() {
+                vk_destroy_swapchain_khr(device, swapchain, std::ptr::null());
+            }
+
+            vk_destroy_device(device, std::ptr::null());
+        }
+
+        if let Some(instance) = self.instance.get() {
+            if let Some(surface) = self.surface.get() {
+                vk_destroy_surface_khr(*instance, *surface, std::ptr::null());
+            }
+
+            if self._enable_validation_layers() {
+                if let Some(debug_messenger) = self.debug_messenger.get() {
+                    self.destroy_debug_utils_messenger_ext(
+                        instance,
+                        debug_messenger,
+                        std::ptr::null(),
+                    );
+                }
+            }
+
+            vk_destroy_instance(*instance, std::ptr::null());
         }
 
-        vk_destroy_instance(self._get_instance(), std::ptr::null());
-        glfw_destroy_window(self._get_window());
+        if self.settings.headless {
+            return;
+        }
+        if let Some(window) = self.window.get() {
+            glfw_destroy_window(*window);
+        }
         glfw_terminate();
     }
 
     fn should_close(&self) -> bool {
+        // Headless runs have no window to close, so they default to a single frame.
+        let frame_limit: Option<u32> = if self.settings.headless {
+            Some(self.settings.frame_limit.unwrap_or(1))
+        } else {
+            self.settings.frame_limit
+        };
+        if frame_limit.is_some_and(|frame_limit| self.frames_rendered.get() >= frame_limit) {
+            return true;
+        }
+        if self.settings.headless {
+            return false;
+        }
+
         let should_close: i32 = glfw_window_should_close(self._get_window());
 
         should_close != 0
     }
 
     fn pool_events(&self) {
+        if self.settings.headless {
+            return;
+        }
         glfw_poll_events();
     }
 
@@ -1812,39 +4928,101 @@ impl GraphicApi for VulkanApi {
         vk_wait_for_fences(
             self._get_device(),
             1,
-            &self._get_in_flight_fence(),
+            &self._get_in_flight_fence(self.current_frame.get()),
             VK_TRUE,
             std::u64::MAX,
         );
-        vk_reset_fences(self._get_device(), 1, &self._get_in_flight_fence());
     }
 
-    fn draw_frame(&self) {
+    fn draw_frame(&self) -> Result<(), GraphicError> {
+        self._reload_changed_shaders();
+        if self.texture_toggle_requested.replace(false) {
+            self._toggle_texturing();
+        }
+
+        if self.settings.headless {
+            self._draw_offscreen_frame()?;
+            return Ok(());
+        }
+
+        let current_frame: usize = self.current_frame.get();
         let mut image_index: u32 = 0;
 
-        vk_acquire_next_image_khr(
+        let result: VkResult = vk_acquire_next_image_khr(
             self._get_device(),
             self._get_swapchain(),
             std::u64::MAX,
-            self._get_image_available_semaphore(),
+            self._get_image_available_semaphore(current_frame),
             std::ptr::null_mut(),
             &mut image_index,
         );
+        if result == VK_ERROR_OUT_OF_DATE_KHR {
+            self._recreate_swap_chain()?;
+            return Ok(());
+        } else if result != VK_SUCCESS && result != VK_SUBOPTIMAL_KHR {
+            return Err(VulkanError::vk("acquire swap chain image", result).into());
+        }
+
+        // The acquired image may still be rendered to by an older frame using another fence.
+        let image_in_flight: VkFence = self.images_in_flight.borrow()[image_index as usize];
+        if !image_in_flight.is_null() {
+            vk_wait_for_fences(
+                self._get_device(),
+                1,
+                &image_in_flight,
+                VK_TRUE,
+                std::u64::MAX,
+            );
+        }
+        self.images_in_flight.borrow_mut()[image_index as usize] =
+            self._get_in_flight_fence(current_frame);
+
+        // Only reset the fence once work is going to be submitted, otherwise the next
+        // wait_events would wait forever on a fence nobody signals.
+        vk_reset_fences(
+            self._get_device(),
+            1,
+            &self._get_in_flight_fence(current_frame),
+        );
+
+        self._update_uniform_buffer(current_frame);
+
+        if self.screenshot_requested.replace(false) {
+            self._request_screenshot();
+        }
+        // The image is copied by this frame's commands, while the app still owns it.
+        let screenshot: Option<(String, VkBuffer, VkDeviceMemory)> = self
+            .screenshot_path
+            .take()
+            .and_then(|path| match self._create_screenshot_buffer() {
+                Ok((buffer, buffer_memory)) => Some((path, buffer, buffer_memory)),
+                Err(error) => {
+                    eprintln!("Screenshot failed: {}", error);
+                    None
+                }
+            });
 
-        vk_reset_command_buffer(self._get_command_buffer(), 0);
-        self._record_command_buffer(self._get_command_buffer(), image_index);
+        vk_reset_command_buffer(self._get_command_buffer(current_frame), 0);
+        self._record_command_buffer(
+            self._get_command_buffer(current_frame),
+            image_index,
+            current_frame,
+            screenshot.as_ref().map(|(_, buffer, _)| *buffer),
+        )?;
 
-        let wait_semaphores: Vec<VkSemaphore> = vec![self._get_image_available_semaphore()];
+        let wait_semaphores: Vec<VkSemaphore> =
+            vec![self._get_image_available_semaphore(current_frame)];
         let wait_stages: Vec<VkPipelineStageFlags> =
             vec![VK_PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT as u32];
-        let signal_semaphores: Vec<VkSemaphore> = vec![self._get_render_finished_semaphore()];
+        let signal_semaphores: Vec<VkSemaphore> =
+            vec![self._get_render_finished_semaphore(current_frame)];
         let submit_info = VkSubmitInfo {
             sType: VK_STRUCTURE_TYPE_SUBMIT_INFO,
             waitSemaphoreCount: 1,
             pWaitSemaphores: wait_semaphores.as_ptr(),
             pWaitDstStageMask: wait_stages.as_ptr(),
             commandBufferCount: 1,
-            pCommandBuffers: &self._get_command_buffer(),
+            pCommandBuffers: &self._get_command_buffer(current_frame),
             signalSemaphoreCount: 1,
             pSignalSemaphores: signal_semaphores.as_ptr(),
             pNext: std::ptr::null(),
@@ -1854,10 +5032,10 @@ impl GraphicApi for VulkanApi {
             self._get_graphics_queue(),
             1,
             &submit_info,
-            self._get_in_flight_fence(),
+            self._get_in_flight_fence(current_frame),
         );
         if result != VK_SUCCESS {
-            panic!("Failed to submit draw command buffer!");
+            return Err(VulkanError::vk("submit draw command buffer", result).into());
         }
 
         let swapchains = vec![self._get_swapchain()];
@@ -1872,7 +5050,30 @@ impl GraphicApi for VulkanApi {
             pNext: std::ptr::null(),
         };
 
-        vk_queue_present_khr(self._get_present_queue(), &preset_info);
+        let result: VkResult = vk_queue_present_khr(self._get_present_queue(), &preset_info);
+        // Read back before a resize can recreate the swapchain with another extent.
+        if let Some((path, buffer, buffer_memory)) = screenshot {
+            match self._save_screenshot(&path, buffer, buffer_memory, current_frame) {
+                Ok(()) => println!("Screenshot saved to {}", path),
+                Err(error) => eprintln!("Screenshot failed: {}", error),
+            }
+        }
+
+        if result == VK_ERROR_OUT_OF_DATE_KHR
+            || result == VK_SUBOPTIMAL_KHR
+            || self.framebuffer_resized.get()
+        {
+            self.framebuffer_resized.set(false);
+            self._recreate_swap_chain()?;
+        } else if result != VK_SUCCESS {
+            return Err(VulkanError::vk("present swap chain image", result).into());
+        }
+
+        self.frames_rendered.set(self.frames_rendered.get() + 1);
+        self.current_frame
+            .set((current_frame + 1) % MAX_FRAMES_IN_FLIGHT);
+
+        Ok(())
     }
 
     fn wait_device_idle(&self) {
@@ -1,52 +1,128 @@
-mod graphics;
-use graphics::{Graphics, GraphicsType};
+pub mod cli;
+mod config;
+pub mod graphics;
+pub use config::Config;
+use graphics::{GraphicError, Graphics};
 
 pub struct App {
     graphic_api: Graphics,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let width: usize = 800;
-        let height: usize = 600;
-        let api_type: GraphicsType = App::select_graphics_api(GraphicsType::Vulkan);
-        let graphic_api: Graphics = Graphics::new(width, height, api_type);
+    pub fn new(config: Config) -> Self {
+        let graphic_api: Graphics =
+            Graphics::new(config.width, config.height, config.backend, config.settings);
 
         Self { graphic_api }
     }
 
-    pub fn execute(&self) {
+    pub fn execute(&self) -> Result<(), GraphicError> {
         self.graphic_api.init_window();
-        self.graphic_api.init_api();
-        self._main_loop();
+        let result: Result<(), GraphicError> =
+            self.graphic_api.init_api().and_then(|_| self._main_loop());
         self.graphic_api.cleanup();
-    }
-
-    fn select_graphics_api(selected_api: GraphicsType) -> GraphicsType {
-        let mut api: GraphicsType = GraphicsType::Vulkan;
-        if selected_api == GraphicsType::OpenGL {
-            api = GraphicsType::OpenGL;
-        }
 
-        return api;
+        result
     }
 
-    fn _main_loop(&self) {
-        
+    fn _main_loop(&self) -> Result<(), GraphicError> {
+        let mut result: Result<(), GraphicError> = Ok(());
+
         loop {
             if self.graphic_api.should_close() {
                 break;
             }
             self.graphic_api.pool_events();
 
-            self._draw_frame();
+            result = self._draw_frame();
+            if result.is_err() {
+                break;
+            }
         }
 
         self.graphic_api.wait_device_idle();
+
+        result
     }
 
-    fn _draw_frame(&self) {
+    fn _draw_frame(&self) -> Result<(), GraphicError> {
         self.graphic_api.wait_events();
-        self.graphic_api.draw_frame();
+        self.graphic_api.draw_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::graphics::null::{Call, CallLog, NullApi};
+    use super::graphics::{GraphicsSettings, GraphicsType};
+    use super::*;
+
+    fn null_app(frame_limit: Option<u32>) -> (App, CallLog) {
+        let api: NullApi = NullApi::new(GraphicsSettings {
+            frame_limit,
+            ..GraphicsSettings::default()
+        });
+        let calls: CallLog = api.calls();
+        let app: App = App {
+            graphic_api: Graphics::with_api(Box::new(api)),
+        };
+
+        (app, calls)
+    }
+
+    #[test]
+    fn execute_drives_the_backend_until_it_should_close() {
+        let (app, calls) = null_app(Some(2));
+        assert!(app.execute().is_ok());
+
+        let mut expected: Vec<Call> = vec![Call::InitWindow, Call::InitApi];
+        for _ in 0..2 {
+            expected.extend([
+                Call::ShouldClose,
+                Call::PoolEvents,
+                Call::WaitEvents,
+                Call::DrawFrame,
+            ]);
+        }
+        expected.extend([Call::ShouldClose, Call::WaitDeviceIdle, Call::Cleanup]);
+        assert_eq!(*calls.borrow(), expected);
+    }
+
+    #[test]
+    fn execute_draws_nothing_when_closed_from_the_start() {
+        let (app, calls) = null_app(Some(0));
+        assert!(app.execute().is_ok());
+
+        assert_eq!(
+            *calls.borrow(),
+            vec![
+                Call::InitWindow,
+                Call::InitApi,
+                Call::ShouldClose,
+                Call::WaitDeviceIdle,
+                Call::Cleanup,
+            ]
+        );
+    }
+
+    #[test]
+    fn config_selects_the_backend() {
+        let app: App = App::new(Config {
+            backend: GraphicsType::Null,
+            ..Config::default()
+        });
+        assert!(app.execute().is_ok());
+    }
+
+    #[test]
+    fn null_backend_needs_no_window_or_gpu() {
+        let graphics: Graphics =
+            Graphics::new(800, 600, GraphicsType::Null, GraphicsSettings::default());
+        graphics.init_window();
+        assert!(graphics.init_api().is_ok());
+        assert!(!graphics.should_close());
+        assert!(graphics.draw_frame().is_ok());
+        assert!(graphics.should_close());
+        graphics.cleanup();
     }
 }
@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::app::graphics::load::LoadError;
+
+#[derive(Debug)]
+pub enum GlError {
+    // The driver does not provide an entry point the backend needs.
+    MissingFunction(&'static str),
+    Compile {
+        path: String,
+        log: String,
+    },
+    Link {
+        log: String,
+    },
+    Setup {
+        stage: &'static str,
+        message: String,
+    },
+    Load(LoadError),
+}
+
+impl From<LoadError> for GlError {
+    fn from(error: LoadError) -> Self {
+        GlError::Load(error)
+    }
+}
+
+impl GlError {
+    pub fn setup(stage: &'static str, message: &str) -> Self {
+        GlError::Setup {
+            stage,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlError::MissingFunction(name) => {
+                write!(f, "Failed to load OpenGL function {}", name)
+            }
+            GlError::Compile { path, log } => write!(f, "Failed to compile {}: {}", path, log),
+            GlError::Link { log } => write!(f, "Failed to link shader program: {}", log),
+            GlError::Setup { stage, message } => {
+                write!(f, "Failed to {}: {}", stage, message)
+            }
+            GlError::Load(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for GlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GlError::Load(error) => Some(error),
+            _ => None,
+        }
+    }
+}
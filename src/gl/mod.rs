@@ -0,0 +1,349 @@
+mod error;
+#[allow(dead_code)]
+mod ffi;
+
+use std::ffi::c_void;
+use std::sync::OnceLock;
+
+pub use error::GlError;
+
+pub use ffi::{
+    GLbitfield, GLboolean, GLchar, GLdouble, GLenum, GLfloat, GLint, GLintptr, GLsizei, GLsizeiptr,
+    GLubyte, GLuint, GL_COLOR_BUFFER_BIT, GL_COMPILE_STATUS, GL_DEPTH_BUFFER_BIT, GL_DEPTH_TEST,
+    GL_DYNAMIC_STORAGE_BIT, GL_FALSE, GL_FLOAT, GL_FRAGMENT_SHADER, GL_FRAMEBUFFER_SRGB,
+    GL_INFO_LOG_LENGTH, GL_LESS, GL_LINEAR, GL_LINEAR_MIPMAP_LINEAR, GL_LINK_STATUS, GL_RENDERER,
+    GL_REPEAT, GL_RGBA, GL_SRGB8_ALPHA8, GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER,
+    GL_TEXTURE_MIN_FILTER, GL_TEXTURE_WRAP_S, GL_TEXTURE_WRAP_T, GL_TRIANGLES, GL_UNIFORM_BUFFER,
+    GL_UNSIGNED_BYTE, GL_UNSIGNED_INT, GL_UPPER_LEFT, GL_VERSION, GL_VERTEX_SHADER, GL_ZERO_TO_ONE,
+};
+
+// OpenGL entry points are not exported by a library we can link against; they belong to the
+// driver behind the current context and are looked up by name once it exists.
+macro_rules! gl_functions {
+    ($($name:ident: $type:ident),* $(,)?) => {
+        #[allow(non_snake_case)]
+        struct Functions {
+            $($name: ffi::$type,)*
+        }
+
+        impl Functions {
+            fn load(get_proc_address: &dyn Fn(&str) -> *const c_void) -> Result<Self, GlError> {
+                Ok(Self {
+                    $($name: {
+                        let address: *const c_void = get_proc_address(stringify!($name));
+                        if address.is_null() {
+                            return Err(GlError::MissingFunction(stringify!($name)));
+                        }
+                        unsafe { std::mem::transmute::<*const c_void, ffi::$type>(address) }
+                    },)*
+                })
+            }
+        }
+    };
+}
+
+gl_functions! {
+    glClipControl: PFNGLCLIPCONTROLPROC,
+    glEnable: PFNGLENABLEPROC,
+    glViewport: PFNGLVIEWPORTPROC,
+    glClearColor: PFNGLCLEARCOLORPROC,
+    glClearDepth: PFNGLCLEARDEPTHPROC,
+    glClear: PFNGLCLEARPROC,
+    glDepthFunc: PFNGLDEPTHFUNCPROC,
+    glGetString: PFNGLGETSTRINGPROC,
+    glCreateShader: PFNGLCREATESHADERPROC,
+    glShaderSource: PFNGLSHADERSOURCEPROC,
+    glCompileShader: PFNGLCOMPILESHADERPROC,
+    glGetShaderiv: PFNGLGETSHADERIVPROC,
+    glGetShaderInfoLog: PFNGLGETSHADERINFOLOGPROC,
+    glDeleteShader: PFNGLDELETESHADERPROC,
+    glCreateProgram: PFNGLCREATEPROGRAMPROC,
+    glAttachShader: PFNGLATTACHSHADERPROC,
+    glLinkProgram: PFNGLLINKPROGRAMPROC,
+    glGetProgramiv: PFNGLGETPROGRAMIVPROC,
+    glGetProgramInfoLog: PFNGLGETPROGRAMINFOLOGPROC,
+    glDeleteProgram: PFNGLDELETEPROGRAMPROC,
+    glUseProgram: PFNGLUSEPROGRAMPROC,
+    glCreateBuffers: PFNGLCREATEBUFFERSPROC,
+    glNamedBufferStorage: PFNGLNAMEDBUFFERSTORAGEPROC,
+    glNamedBufferSubData: PFNGLNAMEDBUFFERSUBDATAPROC,
+    glDeleteBuffers: PFNGLDELETEBUFFERSPROC,
+    glBindBufferBase: PFNGLBINDBUFFERBASEPROC,
+    glCreateVertexArrays: PFNGLCREATEVERTEXARRAYSPROC,
+    glVertexArrayVertexBuffer: PFNGLVERTEXARRAYVERTEXBUFFERPROC,
+    glVertexArrayElementBuffer: PFNGLVERTEXARRAYELEMENTBUFFERPROC,
+    glEnableVertexArrayAttrib: PFNGLENABLEVERTEXARRAYATTRIBPROC,
+    glVertexArrayAttribFormat: PFNGLVERTEXARRAYATTRIBFORMATPROC,
+    glVertexArrayAttribBinding: PFNGLVERTEXARRAYATTRIBBINDINGPROC,
+    glBindVertexArray: PFNGLBINDVERTEXARRAYPROC,
+    glDeleteVertexArrays: PFNGLDELETEVERTEXARRAYSPROC,
+    glCreateTextures: PFNGLCREATETEXTURESPROC,
+    glTextureStorage2D: PFNGLTEXTURESTORAGE2DPROC,
+    glTextureSubImage2D: PFNGLTEXTURESUBIMAGE2DPROC,
+    glGenerateTextureMipmap: PFNGLGENERATETEXTUREMIPMAPPROC,
+    glTextureParameteri: PFNGLTEXTUREPARAMETERIPROC,
+    glBindTextureUnit: PFNGLBINDTEXTUREUNITPROC,
+    glDeleteTextures: PFNGLDELETETEXTURESPROC,
+    glDrawElements: PFNGLDRAWELEMENTSPROC,
+    glFinish: PFNGLFINISHPROC,
+}
+
+static FUNCTIONS: OnceLock<Functions> = OnceLock::new();
+
+// Needs a current context. Loading again, e.g. for a second window, keeps the first table.
+pub fn gl_load_functions(get_proc_address: &dyn Fn(&str) -> *const c_void) -> Result<(), GlError> {
+    if FUNCTIONS.get().is_none() {
+        let _ = FUNCTIONS.set(Functions::load(get_proc_address)?);
+    }
+
+    Ok(())
+}
+
+// Every entry is Some: loading fails on the first function the driver does not have.
+fn functions() -> &'static Functions {
+    FUNCTIONS.get().expect("OpenGL functions are not loaded")
+}
+
+pub fn gl_clip_control(origin: GLenum, depth: GLenum) {
+    unsafe { functions().glClipControl.unwrap()(origin, depth) }
+}
+
+pub fn gl_enable(cap: GLenum) {
+    unsafe { functions().glEnable.unwrap()(cap) }
+}
+
+pub fn gl_viewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+    unsafe { functions().glViewport.unwrap()(x, y, width, height) }
+}
+
+pub fn gl_clear_color(red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat) {
+    unsafe { functions().glClearColor.unwrap()(red, green, blue, alpha) }
+}
+
+pub fn gl_clear_depth(depth: GLdouble) {
+    unsafe { functions().glClearDepth.unwrap()(depth) }
+}
+
+pub fn gl_clear(mask: GLbitfield) {
+    unsafe { functions().glClear.unwrap()(mask) }
+}
+
+pub fn gl_depth_func(func: GLenum) {
+    unsafe { functions().glDepthFunc.unwrap()(func) }
+}
+
+pub fn gl_get_string(name: GLenum) -> *const GLubyte {
+    unsafe { functions().glGetString.unwrap()(name) }
+}
+
+pub fn gl_create_shader(type_: GLenum) -> GLuint {
+    unsafe { functions().glCreateShader.unwrap()(type_) }
+}
+
+pub fn gl_shader_source(
+    shader: GLuint,
+    count: GLsizei,
+    string: *const *const GLchar,
+    length: *const GLint,
+) {
+    unsafe { functions().glShaderSource.unwrap()(shader, count, string, length) }
+}
+
+pub fn gl_compile_shader(shader: GLuint) {
+    unsafe { functions().glCompileShader.unwrap()(shader) }
+}
+
+pub fn gl_get_shaderiv(shader: GLuint, pname: GLenum, params: *mut GLint) {
+    unsafe { functions().glGetShaderiv.unwrap()(shader, pname, params) }
+}
+
+pub fn gl_get_shader_info_log(
+    shader: GLuint,
+    buf_size: GLsizei,
+    length: *mut GLsizei,
+    info_log: *mut GLchar,
+) {
+    unsafe { functions().glGetShaderInfoLog.unwrap()(shader, buf_size, length, info_log) }
+}
+
+pub fn gl_delete_shader(shader: GLuint) {
+    unsafe { functions().glDeleteShader.unwrap()(shader) }
+}
+
+pub fn gl_create_program() -> GLuint {
+    unsafe { functions().glCreateProgram.unwrap()() }
+}
+
+pub fn gl_attach_shader(program: GLuint, shader: GLuint) {
+    unsafe { functions().glAttachShader.unwrap()(program, shader) }
+}
+
+pub fn gl_link_program(program: GLuint) {
+    unsafe { functions().glLinkProgram.unwrap()(program) }
+}
+
+pub fn gl_get_programiv(program: GLuint, pname: GLenum, params: *mut GLint) {
+    unsafe { functions().glGetProgramiv.unwrap()(program, pname, params) }
+}
+
+pub fn gl_get_program_info_log(
+    program: GLuint,
+    buf_size: GLsizei,
+    length: *mut GLsizei,
+    info_log: *mut GLchar,
+) {
+    unsafe { functions().glGetProgramInfoLog.unwrap()(program, buf_size, length, info_log) }
+}
+
+pub fn gl_delete_program(program: GLuint) {
+    unsafe { functions().glDeleteProgram.unwrap()(program) }
+}
+
+pub fn gl_use_program(program: GLuint) {
+    unsafe { functions().glUseProgram.unwrap()(program) }
+}
+
+pub fn gl_create_buffers(n: GLsizei, buffers: *mut GLuint) {
+    unsafe { functions().glCreateBuffers.unwrap()(n, buffers) }
+}
+
+pub fn gl_named_buffer_storage(
+    buffer: GLuint,
+    size: GLsizeiptr,
+    data: *const c_void,
+    flags: GLbitfield,
+) {
+    unsafe { functions().glNamedBufferStorage.unwrap()(buffer, size, data, flags) }
+}
+
+pub fn gl_named_buffer_sub_data(
+    buffer: GLuint,
+    offset: GLintptr,
+    size: GLsizeiptr,
+    data: *const c_void,
+) {
+    unsafe { functions().glNamedBufferSubData.unwrap()(buffer, offset, size, data) }
+}
+
+pub fn gl_delete_buffers(n: GLsizei, buffers: *const GLuint) {
+    unsafe { functions().glDeleteBuffers.unwrap()(n, buffers) }
+}
+
+pub fn gl_bind_buffer_base(target: GLenum, index: GLuint, buffer: GLuint) {
+    unsafe { functions().glBindBufferBase.unwrap()(target, index, buffer) }
+}
+
+pub fn gl_create_vertex_arrays(n: GLsizei, arrays: *mut GLuint) {
+    unsafe { functions().glCreateVertexArrays.unwrap()(n, arrays) }
+}
+
+pub fn gl_vertex_array_vertex_buffer(
+    vaobj: GLuint,
+    bindingindex: GLuint,
+    buffer: GLuint,
+    offset: GLintptr,
+    stride: GLsizei,
+) {
+    unsafe {
+        functions().glVertexArrayVertexBuffer.unwrap()(vaobj, bindingindex, buffer, offset, stride)
+    }
+}
+
+pub fn gl_vertex_array_element_buffer(vaobj: GLuint, buffer: GLuint) {
+    unsafe { functions().glVertexArrayElementBuffer.unwrap()(vaobj, buffer) }
+}
+
+pub fn gl_enable_vertex_array_attrib(vaobj: GLuint, index: GLuint) {
+    unsafe { functions().glEnableVertexArrayAttrib.unwrap()(vaobj, index) }
+}
+
+pub fn gl_vertex_array_attrib_format(
+    vaobj: GLuint,
+    attribindex: GLuint,
+    size: GLint,
+    type_: GLenum,
+    normalized: GLboolean,
+    relativeoffset: GLuint,
+) {
+    unsafe {
+        functions().glVertexArrayAttribFormat.unwrap()(
+            vaobj,
+            attribindex,
+            size,
+            type_,
+            normalized,
+            relativeoffset,
+        )
+    }
+}
+
+pub fn gl_vertex_array_attrib_binding(vaobj: GLuint, attribindex: GLuint, bindingindex: GLuint) {
+    unsafe { functions().glVertexArrayAttribBinding.unwrap()(vaobj, attribindex, bindingindex) }
+}
+
+pub fn gl_bind_vertex_array(array: GLuint) {
+    unsafe { functions().glBindVertexArray.unwrap()(array) }
+}
+
+pub fn gl_delete_vertex_arrays(n: GLsizei, arrays: *const GLuint) {
+    unsafe { functions().glDeleteVertexArrays.unwrap()(n, arrays) }
+}
+
+pub fn gl_create_textures(target: GLenum, n: GLsizei, textures: *mut GLuint) {
+    unsafe { functions().glCreateTextures.unwrap()(target, n, textures) }
+}
+
+pub fn gl_texture_storage_2d(
+    texture: GLuint,
+    levels: GLsizei,
+    internalformat: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+) {
+    unsafe {
+        functions().glTextureStorage2D.unwrap()(texture, levels, internalformat, width, height)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn gl_texture_sub_image_2d(
+    texture: GLuint,
+    level: GLint,
+    xoffset: GLint,
+    yoffset: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    type_: GLenum,
+    pixels: *const c_void,
+) {
+    unsafe {
+        functions().glTextureSubImage2D.unwrap()(
+            texture, level, xoffset, yoffset, width, height, format, type_, pixels,
+        )
+    }
+}
+
+pub fn gl_generate_texture_mipmap(texture: GLuint) {
+    unsafe { functions().glGenerateTextureMipmap.unwrap()(texture) }
+}
+
+pub fn gl_texture_parameteri(texture: GLuint, pname: GLenum, param: GLint) {
+    unsafe { functions().glTextureParameteri.unwrap()(texture, pname, param) }
+}
+
+pub fn gl_bind_texture_unit(unit: GLuint, texture: GLuint) {
+    unsafe { functions().glBindTextureUnit.unwrap()(unit, texture) }
+}
+
+pub fn gl_delete_textures(n: GLsizei, textures: *const GLuint) {
+    unsafe { functions().glDeleteTextures.unwrap()(n, textures) }
+}
+
+pub fn gl_draw_elements(mode: GLenum, count: GLsizei, type_: GLenum, indices: *const c_void) {
+    unsafe { functions().glDrawElements.unwrap()(mode, count, type_, indices) }
+}
+
+pub fn gl_finish() {
+    unsafe { functions().glFinish.unwrap()() }
+}
@@ -36,4 +36,16 @@ unsafe extern "C" {
         width: *mut c_int,
         height: *mut c_int
     ) -> c_void;
+    pub unsafe fn glfwWaitEvents();
+    pub unsafe fn glfwSetWindowUserPointer(window: *mut GLFWwindow, pointer: *mut c_void);
+    pub unsafe fn glfwGetWindowUserPointer(window: *mut GLFWwindow) -> *mut c_void;
+    pub unsafe fn glfwSetFramebufferSizeCallback(
+        window: *mut GLFWwindow,
+        callback: GLFWframebuffersizefun,
+    ) -> GLFWframebuffersizefun;
+    pub unsafe fn glfwSetKeyCallback(window: *mut GLFWwindow, callback: GLFWkeyfun) -> GLFWkeyfun;
+    pub unsafe fn glfwMakeContextCurrent(window: *mut GLFWwindow);
+    pub unsafe fn glfwGetProcAddress(procname: *const c_char) -> *const c_void;
+    pub unsafe fn glfwSwapBuffers(window: *mut GLFWwindow);
+    pub unsafe fn glfwSwapInterval(interval: c_int);
 }
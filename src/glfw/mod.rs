@@ -5,7 +5,12 @@ use crate::vulkan::{VkAllocationCallbacks, VkInstance, VkResult, VkSurfaceKHR};
 #[allow(dead_code)]
 mod ffi;
 
-pub use ffi::{GLFWmonitor, GLFWwindow, GLFW_CLIENT_API, GLFW_FALSE, GLFW_NO_API, GLFW_RESIZABLE};
+pub use ffi::{
+    GLFWframebuffersizefun, GLFWkeyfun, GLFWmonitor, GLFWwindow, GLFW_CLIENT_API,
+    GLFW_CONTEXT_VERSION_MAJOR, GLFW_CONTEXT_VERSION_MINOR, GLFW_FALSE, GLFW_KEY_F12, GLFW_KEY_T,
+    GLFW_NO_API, GLFW_OPENGL_API, GLFW_OPENGL_CORE_PROFILE, GLFW_OPENGL_FORWARD_COMPAT,
+    GLFW_OPENGL_PROFILE, GLFW_PRESS, GLFW_RESIZABLE, GLFW_SAMPLES, GLFW_SRGB_CAPABLE, GLFW_TRUE,
+};
 
 pub fn glfw_init() {
     unsafe { ffi::glfwInit() }
@@ -67,3 +72,43 @@ pub fn glfw_get_framebuffer_size(
 ) -> c_void {
     unsafe { ffi::glfwGetFramebufferSize(window, width, height) }
 }
+
+pub fn glfw_wait_events() {
+    unsafe { ffi::glfwWaitEvents() }
+}
+
+pub fn glfw_set_window_user_pointer(window: *mut GLFWwindow, pointer: *mut c_void) {
+    unsafe { ffi::glfwSetWindowUserPointer(window, pointer) }
+}
+
+pub fn glfw_get_window_user_pointer(window: *mut GLFWwindow) -> *mut c_void {
+    unsafe { ffi::glfwGetWindowUserPointer(window) }
+}
+
+pub fn glfw_set_framebuffer_size_callback(
+    window: *mut GLFWwindow,
+    callback: GLFWframebuffersizefun,
+) -> GLFWframebuffersizefun {
+    unsafe { ffi::glfwSetFramebufferSizeCallback(window, callback) }
+}
+
+pub fn glfw_set_key_callback(window: *mut GLFWwindow, callback: GLFWkeyfun) -> GLFWkeyfun {
+    unsafe { ffi::glfwSetKeyCallback(window, callback) }
+}
+
+pub fn glfw_make_context_current(window: *mut GLFWwindow) {
+    unsafe { ffi::glfwMakeContextCurrent(window) }
+}
+
+pub fn glfw_get_proc_address(procname: &str) -> *const c_void {
+    let c_procname: CString = CString::new(procname).expect("CString::new failed");
+    unsafe { ffi::glfwGetProcAddress(c_procname.as_ptr()) }
+}
+
+pub fn glfw_swap_buffers(window: *mut GLFWwindow) {
+    unsafe { ffi::glfwSwapBuffers(window) }
+}
+
+pub fn glfw_swap_interval(interval: c_int) {
+    unsafe { ffi::glfwSwapInterval(interval) }
+}
@@ -1,11 +1,42 @@
 mod app;
 mod utils;
+mod gl;
 mod glfw;
+mod shaderc;
+mod stb;
 mod vulkan;
 
-use app::App;
+use std::process::ExitCode;
 
-fn main() {
-    let app: App = crate::App::new();
-    app.execute();
+use app::cli::{self, Command};
+use app::{App, Config};
+
+fn main() -> ExitCode {
+    let config: Config = match Config::load() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let config: Config = match cli::parse(std::env::args().skip(1), config) {
+        Ok(Command::Run(config)) => config,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let app: App = crate::App::new(config);
+    match app.execute() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
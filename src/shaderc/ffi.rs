@@ -0,0 +1,54 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use std::ffi::{c_char, c_void};
+
+include!(concat!(env!("OUT_DIR"), "/bindings_shaderc.rs"));
+
+unsafe extern "C" {
+    pub unsafe fn shaderc_compiler_initialize() -> shaderc_compiler_t;
+    pub unsafe fn shaderc_compiler_release(compiler: shaderc_compiler_t);
+    pub unsafe fn shaderc_compile_options_initialize() -> shaderc_compile_options_t;
+    pub unsafe fn shaderc_compile_options_release(options: shaderc_compile_options_t);
+    pub unsafe fn shaderc_compile_options_add_macro_definition(
+        options: shaderc_compile_options_t,
+        name: *const c_char,
+        name_length: usize,
+        value: *const c_char,
+        value_length: usize,
+    );
+    pub unsafe fn shaderc_compile_options_set_source_language(
+        options: shaderc_compile_options_t,
+        lang: shaderc_source_language,
+    );
+    pub unsafe fn shaderc_compile_options_set_target_env(
+        options: shaderc_compile_options_t,
+        target: shaderc_target_env,
+        version: u32,
+    );
+    pub unsafe fn shaderc_compile_options_set_include_callbacks(
+        options: shaderc_compile_options_t,
+        resolver: shaderc_include_resolve_fn,
+        result_releaser: shaderc_include_result_release_fn,
+        user_data: *mut c_void,
+    );
+    pub unsafe fn shaderc_compile_into_spv(
+        compiler: shaderc_compiler_t,
+        source_text: *const c_char,
+        source_text_size: usize,
+        shader_kind: shaderc_shader_kind,
+        input_file_name: *const c_char,
+        entry_point_name: *const c_char,
+        additional_options: shaderc_compile_options_t,
+    ) -> shaderc_compilation_result_t;
+    pub unsafe fn shaderc_result_release(result: shaderc_compilation_result_t);
+    pub unsafe fn shaderc_result_get_length(result: shaderc_compilation_result_t) -> usize;
+    pub unsafe fn shaderc_result_get_bytes(result: shaderc_compilation_result_t) -> *const c_char;
+    pub unsafe fn shaderc_result_get_compilation_status(
+        result: shaderc_compilation_result_t,
+    ) -> shaderc_compilation_status;
+    pub unsafe fn shaderc_result_get_error_message(
+        result: shaderc_compilation_result_t,
+    ) -> *const c_char;
+}
@@ -0,0 +1,130 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+
+#[allow(dead_code)]
+mod ffi;
+
+pub use ffi::{
+    shaderc_compilation_result_t, shaderc_compilation_status, shaderc_compile_options_t,
+    shaderc_compiler_t, shaderc_env_version, shaderc_include_resolve_fn, shaderc_include_result,
+    shaderc_include_result_release_fn, shaderc_include_type, shaderc_shader_kind,
+    shaderc_source_language, shaderc_target_env,
+};
+
+pub fn shaderc_compiler_initialize() -> shaderc_compiler_t {
+    unsafe { ffi::shaderc_compiler_initialize() }
+}
+
+pub fn shaderc_compiler_release(compiler: shaderc_compiler_t) {
+    unsafe { ffi::shaderc_compiler_release(compiler) }
+}
+
+pub fn shaderc_compile_options_initialize() -> shaderc_compile_options_t {
+    unsafe { ffi::shaderc_compile_options_initialize() }
+}
+
+pub fn shaderc_compile_options_release(options: shaderc_compile_options_t) {
+    unsafe { ffi::shaderc_compile_options_release(options) }
+}
+
+// An empty value defines the macro without a body, like `#define NAME`.
+pub fn shaderc_compile_options_add_macro_definition(
+    options: shaderc_compile_options_t,
+    name: &str,
+    value: &str,
+) {
+    unsafe {
+        ffi::shaderc_compile_options_add_macro_definition(
+            options,
+            name.as_ptr() as *const c_char,
+            name.len(),
+            value.as_ptr() as *const c_char,
+            value.len(),
+        )
+    }
+}
+
+pub fn shaderc_compile_options_set_source_language(
+    options: shaderc_compile_options_t,
+    lang: shaderc_source_language,
+) {
+    unsafe { ffi::shaderc_compile_options_set_source_language(options, lang) }
+}
+
+pub fn shaderc_compile_options_set_target_env(
+    options: shaderc_compile_options_t,
+    target: shaderc_target_env,
+    version: shaderc_env_version,
+) {
+    unsafe { ffi::shaderc_compile_options_set_target_env(options, target, version as u32) }
+}
+
+pub fn shaderc_compile_options_set_include_callbacks(
+    options: shaderc_compile_options_t,
+    resolver: shaderc_include_resolve_fn,
+    result_releaser: shaderc_include_result_release_fn,
+    user_data: *mut c_void,
+) {
+    unsafe {
+        ffi::shaderc_compile_options_set_include_callbacks(
+            options,
+            resolver,
+            result_releaser,
+            user_data,
+        )
+    }
+}
+
+pub fn shaderc_compile_into_spv(
+    compiler: shaderc_compiler_t,
+    source_text: &str,
+    shader_kind: shaderc_shader_kind,
+    input_file_name: &str,
+    entry_point_name: &str,
+    additional_options: shaderc_compile_options_t,
+) -> shaderc_compilation_result_t {
+    let c_input_file_name: CString = CString::new(input_file_name).expect("CString::new failed");
+    let c_entry_point_name: CString = CString::new(entry_point_name).expect("CString::new failed");
+    unsafe {
+        ffi::shaderc_compile_into_spv(
+            compiler,
+            source_text.as_ptr() as *const c_char,
+            source_text.len(),
+            shader_kind,
+            c_input_file_name.as_ptr(),
+            c_entry_point_name.as_ptr(),
+            additional_options,
+        )
+    }
+}
+
+pub fn shaderc_result_release(result: shaderc_compilation_result_t) {
+    unsafe { ffi::shaderc_result_release(result) }
+}
+
+pub fn shaderc_result_get_compilation_status(
+    result: shaderc_compilation_result_t,
+) -> shaderc_compilation_status {
+    unsafe { ffi::shaderc_result_get_compilation_status(result) }
+}
+
+// A copy of the compiled SPIR-V; the result still has to be released.
+pub fn shaderc_result_get_bytes(result: shaderc_compilation_result_t) -> Vec<u8> {
+    let length: usize = unsafe { ffi::shaderc_result_get_length(result) };
+    let bytes: *const c_char = unsafe { ffi::shaderc_result_get_bytes(result) };
+    if bytes.is_null() || length == 0 {
+        return Vec::new();
+    }
+
+    unsafe { std::slice::from_raw_parts(bytes as *const u8, length) }.to_vec()
+}
+
+pub fn shaderc_result_get_error_message(result: shaderc_compilation_result_t) -> String {
+    let message: *const c_char = unsafe { ffi::shaderc_result_get_error_message(result) };
+    if message.is_null() {
+        return String::new();
+    }
+
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
@@ -1,9 +1,21 @@
 #version 450
 
+layout(binding = 1) uniform sampler2D texSampler;
+
 layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec2 fragTexCoord;
 
 layout(location = 0) out vec4 outColor;
 
+// Specialized by the Vulkan renderer, T switches between the two pipelines. VULKAN is only
+// defined when compiling to SPIR-V: OpenGL compiles this file as is and has no constant_id.
+#ifdef VULKAN
+layout(constant_id = 1) const bool TEXTURED = true;
+#else
+const bool TEXTURED = true;
+#endif
+
 void main() {
-    outColor = vec4(fragColor, 1.0);
-}
\ No newline at end of file
+    vec3 texel = TEXTURED ? texture(texSampler, fragTexCoord).rgb : vec3(1.0);
+    outColor = vec4(fragColor * texel, 1.0);
+}
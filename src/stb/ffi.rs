@@ -0,0 +1,27 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use std::ffi::{c_char, c_int, c_void};
+
+include!(concat!(env!("OUT_DIR"), "/bindings_stb.rs"));
+
+unsafe extern "C" {
+    pub unsafe fn stbi_load(
+        filename: *const c_char,
+        x: *mut c_int,
+        y: *mut c_int,
+        channels_in_file: *mut c_int,
+        desired_channels: c_int,
+    ) -> *mut u8;
+    pub unsafe fn stbi_image_free(retval_from_stbi_load: *mut c_void);
+    pub unsafe fn stbi_failure_reason() -> *const c_char;
+    pub unsafe fn stbi_write_png(
+        filename: *const c_char,
+        w: c_int,
+        h: c_int,
+        comp: c_int,
+        data: *const c_void,
+        stride_in_bytes: c_int,
+    ) -> c_int;
+}
@@ -0,0 +1,61 @@
+use std::ffi::{c_int, c_void, CStr, CString};
+
+#[allow(dead_code)]
+mod ffi;
+
+pub use ffi::STBI_rgb_alpha;
+
+pub fn stbi_load(
+    filename: &str,
+    x: *mut c_int,
+    y: *mut c_int,
+    channels_in_file: *mut c_int,
+    desired_channels: c_int,
+) -> *mut u8 {
+    let c_filename: CString = CString::new(filename).expect("CString::new failed");
+    unsafe {
+        ffi::stbi_load(
+            c_filename.as_ptr(),
+            x,
+            y,
+            channels_in_file,
+            desired_channels,
+        )
+    }
+}
+
+pub fn stbi_image_free(retval_from_stbi_load: *mut u8) {
+    unsafe { ffi::stbi_image_free(retval_from_stbi_load as *mut c_void) }
+}
+
+pub fn stbi_failure_reason() -> String {
+    let reason = unsafe { ffi::stbi_failure_reason() };
+    if reason.is_null() {
+        return String::from("unknown error");
+    }
+
+    unsafe { CStr::from_ptr(reason) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub fn stbi_write_png(
+    filename: &str,
+    w: c_int,
+    h: c_int,
+    comp: c_int,
+    data: &[u8],
+    stride_in_bytes: c_int,
+) -> c_int {
+    let c_filename: CString = CString::new(filename).expect("CString::new failed");
+    unsafe {
+        ffi::stbi_write_png(
+            c_filename.as_ptr(),
+            w,
+            h,
+            comp,
+            data.as_ptr() as *const c_void,
+            stride_in_bytes,
+        )
+    }
+}
@@ -0,0 +1,333 @@
+use std::fmt;
+
+// Parsed JSON document. Objects keep their keys in file order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl JsonValue {
+    pub fn parse(source: &str) -> Result<JsonValue, JsonError> {
+        let mut parser: Parser = Parser {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+        parser.skip_whitespace();
+        let value: JsonValue = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unexpected data after the document"));
+        }
+
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            JsonValue::Number(value) if *value >= 0.0 && value.fract() == 0.0 => {
+                Some(*value as usize)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+// Deeply nested documents would otherwise overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> JsonError {
+        let consumed: &[char] = &self.chars[..self.position.min(self.chars.len())];
+        let line: usize = consumed.iter().filter(|c| **c == '\n').count() + 1;
+        let column: usize = consumed.iter().rev().take_while(|c| **c != '\n').count() + 1;
+
+        JsonError {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c: Option<char> = self.peek();
+        if c.is_some() {
+            self.position += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        for expected in literal.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error(&format!("invalid literal, expected '{}'", literal)));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("document is nested too deeply"));
+        }
+
+        match self.peek() {
+            Some('{') => self.parse_object(depth),
+            Some('[') => self.parse_array(depth),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some('t') => self.expect_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.expect_literal("false", JsonValue::Bool(false)),
+            Some('n') => self.expect_literal("null", JsonValue::Null),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some(c) => Err(self.error(&format!("unexpected character '{}'", c))),
+            None => Err(self.error("unexpected end of document")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        self.position += 1;
+        let mut members: Vec<(String, JsonValue)> = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key: String = self.parse_string()?;
+
+            self.skip_whitespace();
+            if self.next() != Some(':') {
+                return Err(self.error("expected ':' after an object key"));
+            }
+            self.skip_whitespace();
+            members.push((key, self.parse_value(depth + 1)?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(members)),
+                _ => return Err(self.error("expected ',' or '}' in an object")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        self.position += 1;
+        let mut values: Vec<JsonValue> = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            self.skip_whitespace();
+            values.push(self.parse_value(depth + 1)?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(values)),
+                _ => return Err(self.error("expected ',' or ']' in an array")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.position += 1;
+        let mut value: String = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped: char = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in a string"));
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let mut code: u32 = 0;
+        for _ in 0..4 {
+            let digit: u32 = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid \\u escape"))?;
+            code = code * 16 + digit;
+        }
+
+        Ok(code)
+    }
+
+    // Characters outside the basic plane arrive as a UTF-16 surrogate pair of escapes.
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high: u32 = self.parse_hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid \\u escape"));
+        }
+
+        if self.next() != Some('\\') || self.next() != Some('u') {
+            return Err(self.error("unpaired surrogate in \\u escape"));
+        }
+        let low: u32 = self.parse_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("unpaired surrogate in \\u escape"));
+        }
+
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            .ok_or_else(|| self.error("invalid \\u escape"))
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, JsonError> {
+        let start: usize = self.position;
+        while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        // Rust accepts forms JSON does not, like "+1" or ".5", so the leading part is checked.
+        let digits: &str = text.strip_prefix('-').unwrap_or(&text);
+        let valid_start: bool = digits.starts_with(|c: char| c.is_ascii_digit())
+            && !(digits.starts_with('0') && digits[1..].starts_with(|c: char| c.is_ascii_digit()));
+        match text.parse::<f64>() {
+            Ok(value) if valid_start => Ok(JsonValue::Number(value)),
+            _ => {
+                self.position = start;
+                Err(self.error(&format!("invalid number '{}'", text)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_documents() {
+        let value: JsonValue =
+            JsonValue::parse(r#"{"a": [1, -2.5e1, true, null], "b": {"c": "dé\n"}}"#).unwrap();
+
+        let a: &[JsonValue] = value.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a[0].as_usize(), Some(1));
+        assert_eq!(a[1].as_f64(), Some(-25.0));
+        assert_eq!(a[2].as_bool(), Some(true));
+        assert_eq!(a[3], JsonValue::Null);
+        assert_eq!(
+            value.get("b").unwrap().get("c").unwrap().as_str(),
+            Some("dé\n")
+        );
+        assert_eq!(JsonValue::parse(r#""😀""#).unwrap().as_str(), Some("😀"));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error: JsonError = JsonValue::parse("{\n  \"a\": 01\n}").err().unwrap();
+        assert_eq!((error.line, error.column), (2, 8));
+        assert_eq!(error.to_string(), "line 2, column 8: invalid number '01'");
+
+        let error: JsonError = JsonValue::parse("[1, 2").err().unwrap();
+        assert_eq!(error.message, "expected ',' or ']' in an array");
+        assert!(JsonValue::parse("{} x").is_err());
+    }
+}
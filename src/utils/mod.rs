@@ -1,3 +1,7 @@
+pub mod json;
+pub mod toml;
+pub mod watcher;
+
 #[cfg(debug_assertions)]
 pub fn debug_mode() -> bool {
     true
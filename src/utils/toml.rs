@@ -0,0 +1,418 @@
+use std::fmt;
+
+// The part of TOML that configuration files need: [tables] of bare keys with strings, integers,
+// booleans and arrays. Dotted keys, inline tables, floats and dates are rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    Array(Vec<TomlValue>),
+}
+
+// A key with its value, in file order. Keys before the first [table] have an empty table name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TomlEntry {
+    pub table: String,
+    pub key: String,
+    pub value: TomlValue,
+    pub line: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TomlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl TomlEntry {
+    // "table.key", or just the key at the top level.
+    pub fn path(&self) -> String {
+        if self.table.is_empty() {
+            self.key.clone()
+        } else {
+            format!("{}.{}", self.table, self.key)
+        }
+    }
+}
+
+impl TomlValue {
+    // Parses a single value, as written on the right of a `key =`.
+    pub fn parse(source: &str) -> Result<TomlValue, TomlError> {
+        let mut parser: Parser = Parser::new(source);
+        parser.skip_whitespace();
+        let value: TomlValue = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected data after the value"));
+        }
+
+        Ok(value)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TomlValue::String(_) => "a string",
+            TomlValue::Integer(_) => "an integer",
+            TomlValue::Bool(_) => "a boolean",
+            TomlValue::Array(_) => "an array",
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Vec<TomlEntry>, TomlError> {
+    let mut parser: Parser = Parser::new(source);
+    let mut entries: Vec<TomlEntry> = Vec::new();
+    let mut tables: Vec<String> = Vec::new();
+    let mut table: String = String::new();
+
+    loop {
+        parser.skip_blank_lines();
+        let line: usize = parser.line();
+        match parser.peek() {
+            None => return Ok(entries),
+            Some('[') => {
+                parser.position += 1;
+                parser.skip_whitespace();
+                let name: String = parser.parse_key()?;
+                parser.skip_whitespace();
+                if parser.next() != Some(']') {
+                    return Err(parser.error("expected ']' after the table name"));
+                }
+                if tables.contains(&name) {
+                    return Err(parser.error(&format!("table [{}] is defined twice", name)));
+                }
+                tables.push(name.clone());
+                table = name;
+            }
+            Some(_) => {
+                let key: String = parser.parse_key()?;
+                parser.skip_whitespace();
+                if parser.next() != Some('=') {
+                    return Err(parser.error(&format!("expected '=' after the key \"{}\"", key)));
+                }
+                parser.skip_whitespace();
+                let value: TomlValue = parser.parse_value()?;
+
+                let entry: TomlEntry = TomlEntry {
+                    table: table.clone(),
+                    key,
+                    value,
+                    line,
+                };
+                if entries
+                    .iter()
+                    .any(|other| other.table == entry.table && other.key == entry.key)
+                {
+                    return Err(parser.error(&format!("{} is defined twice", entry.path())));
+                }
+                entries.push(entry);
+            }
+        }
+
+        parser.skip_whitespace();
+        parser.skip_comment();
+        match parser.next() {
+            None | Some('\n') => {}
+            Some('\r') if parser.next() == Some('\n') => {}
+            _ => return Err(parser.error("expected the end of the line")),
+        }
+    }
+}
+
+// Each level of nested arrays recurses once, so the depth is capped like in the JSON parser.
+const MAX_DEPTH: usize = 128;
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self {
+            chars: source.chars().collect(),
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn line(&self) -> usize {
+        let consumed: &[char] = &self.chars[..self.position.min(self.chars.len())];
+        consumed.iter().filter(|c| **c == '\n').count() + 1
+    }
+
+    fn error(&self, message: &str) -> TomlError {
+        let consumed: &[char] = &self.chars[..self.position.min(self.chars.len())];
+        let column: usize = consumed.iter().rev().take_while(|c| **c != '\n').count() + 1;
+
+        TomlError {
+            line: self.line(),
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c: Option<char> = self.peek();
+        if c.is_some() {
+            self.position += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n' | '\r')) {
+                self.position += 1;
+            }
+        }
+    }
+
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => self.position += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String, TomlError> {
+        let start: usize = self.position;
+        while matches!(
+            self.peek(),
+            Some('A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '-')
+        ) {
+            self.position += 1;
+        }
+        if self.position == start {
+            return Err(self.error("expected a key"));
+        }
+
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    fn parse_value(&mut self) -> Result<TomlValue, TomlError> {
+        match self.peek() {
+            Some('"') => Ok(TomlValue::String(self.parse_basic_string()?)),
+            Some('\'') => Ok(TomlValue::String(self.parse_literal_string()?)),
+            Some('[') => self.parse_array(),
+            Some('t' | 'f') => self.parse_bool(),
+            Some('+' | '-' | '0'..='9') => self.parse_integer(),
+            Some(c) => Err(self.error(&format!("unexpected character '{}'", c))),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_bool(&mut self) -> Result<TomlValue, TomlError> {
+        let start: usize = self.position;
+        while matches!(self.peek(), Some('a'..='z')) {
+            self.position += 1;
+        }
+
+        let word: String = self.chars[start..self.position].iter().collect();
+        match word.as_str() {
+            "true" => Ok(TomlValue::Bool(true)),
+            "false" => Ok(TomlValue::Bool(false)),
+            _ => {
+                self.position = start;
+                Err(self.error(&format!("invalid value '{}'", word)))
+            }
+        }
+    }
+
+    fn parse_integer(&mut self) -> Result<TomlValue, TomlError> {
+        let start: usize = self.position;
+        while matches!(
+            self.peek(),
+            Some('+' | '-' | '_' | '.' | 'e' | 'E' | '0'..='9')
+        ) {
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        // Underscores may only separate digits, as in 1_000.
+        let digits: &str = text.trim_start_matches(['+', '-']);
+        let misplaced: bool = digits.starts_with('_')
+            || digits.ends_with('_')
+            || digits.contains("__")
+            || (digits.starts_with('0') && digits.len() > 1);
+        match text.replace('_', "").parse::<i64>() {
+            Ok(value) if !misplaced => Ok(TomlValue::Integer(value)),
+            _ => {
+                self.position = start;
+                Err(self.error(&format!("invalid integer '{}'", text)))
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<TomlValue, TomlError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("arrays are nested too deeply"));
+        }
+        self.depth += 1;
+        self.position += 1;
+        let mut values: Vec<TomlValue> = Vec::new();
+
+        // Arrays may span lines and end with a trailing comma.
+        loop {
+            self.skip_blank_lines();
+            if self.peek() == Some(']') {
+                self.position += 1;
+                break;
+            }
+            values.push(self.parse_value()?);
+
+            self.skip_blank_lines();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => return Err(self.error("expected ',' or ']' in an array")),
+            }
+        }
+        self.depth -= 1;
+
+        Ok(TomlValue::Array(values))
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, TomlError> {
+        self.position += 1;
+        let mut value: String = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped: char = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape(4)?,
+                        Some('U') => self.parse_unicode_escape(8)?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    value.push(escaped);
+                }
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) if (c as u32) < 0x20 && c != '\t' => {
+                    return Err(self.error("control character in a string"));
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    // Literal strings have no escapes: everything up to the closing quote is kept as is.
+    fn parse_literal_string(&mut self) -> Result<String, TomlError> {
+        self.position += 1;
+        let mut value: String = String::new();
+
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(value),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn parse_unicode_escape(&mut self, length: usize) -> Result<char, TomlError> {
+        let mut code: u32 = 0;
+        for _ in 0..length {
+            let digit: u32 = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tables_and_values() {
+        let entries: Vec<TomlEntry> = parse(
+            "# comment\n\
+             title = 'raw \\n'\n\
+             \n\
+             [window]\n\
+             width = 1_280 # trailing comment\n\
+             vsync = true\n\
+             layers = [\n  \"a\\tb\",\n  \"\\u00e9\",\n]\n",
+        )
+        .unwrap();
+
+        let paths: Vec<String> = entries.iter().map(TomlEntry::path).collect();
+        assert_eq!(
+            paths,
+            ["title", "window.width", "window.vsync", "window.layers"]
+        );
+        assert_eq!(entries[0].value, TomlValue::String(String::from("raw \\n")));
+        assert_eq!(entries[1].value, TomlValue::Integer(1280));
+        assert_eq!(entries[1].line, 5);
+        assert_eq!(entries[2].value, TomlValue::Bool(true));
+        assert_eq!(
+            entries[3].value,
+            TomlValue::Array(vec![
+                TomlValue::String(String::from("a\tb")),
+                TomlValue::String(String::from("é")),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_single_values() {
+        assert_eq!(TomlValue::parse("-3"), Ok(TomlValue::Integer(-3)));
+        assert_eq!(TomlValue::parse(" false "), Ok(TomlValue::Bool(false)));
+        assert!(TomlValue::parse("nvidia").is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error: TomlError = parse("[window]\nwidth = 01\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2, column 9: invalid integer '01'");
+
+        let error: TomlError = parse("[a]\nx = 1\nx = 2\n").err().unwrap();
+        assert_eq!(error.message, "a.x is defined twice");
+        assert_eq!(
+            parse("x = 1 y = 2").err().unwrap().message,
+            "expected the end of the line"
+        );
+        assert!(parse("x = 1.5").is_err());
+        assert!(parse("[a]\n[a]").is_err());
+    }
+}
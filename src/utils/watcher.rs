@@ -0,0 +1,93 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// Watches a directory tree by comparing modification times, so it needs no OS notification API.
+// Polling is rate limited and cheap enough to be done once per frame.
+#[derive(Debug)]
+pub struct FileWatcher {
+    dir: PathBuf,
+    interval: Duration,
+    last_poll: Cell<Instant>,
+    snapshot: RefCell<Vec<(PathBuf, SystemTime)>>,
+}
+
+impl FileWatcher {
+    pub fn new(dir: &str, interval: Duration) -> Self {
+        let dir: PathBuf = PathBuf::from(dir);
+        let snapshot: Vec<(PathBuf, SystemTime)> = scan(&dir);
+
+        Self {
+            dir,
+            interval,
+            last_poll: Cell::new(Instant::now()),
+            snapshot: RefCell::new(snapshot),
+        }
+    }
+
+    // True once for every round of changes since the last poll: files written, added or removed.
+    pub fn poll(&self) -> bool {
+        if self.last_poll.get().elapsed() < self.interval {
+            return false;
+        }
+        self.last_poll.set(Instant::now());
+
+        let snapshot: Vec<(PathBuf, SystemTime)> = scan(&self.dir);
+        if snapshot == *self.snapshot.borrow() {
+            return false;
+        }
+        self.snapshot.replace(snapshot);
+
+        true
+    }
+}
+
+// Files that can not be read are left out, they show up as changed once they can be.
+fn scan(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let mut files: Vec<(PathBuf, SystemTime)> = Vec::new();
+    let mut dirs: Vec<PathBuf> = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if let Ok(modified) = metadata.modified() {
+                files.push((entry.path(), modified));
+            }
+        }
+    }
+    files.sort();
+
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_each_change_once() {
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("oito-caneco-watcher-{}", std::process::id()));
+        fs::create_dir_all(dir.join("include")).unwrap();
+        fs::write(dir.join("shader.frag"), "void main() {}").unwrap();
+
+        let watcher: FileWatcher = FileWatcher::new(dir.to_str().unwrap(), Duration::ZERO);
+        assert!(!watcher.poll());
+
+        fs::write(dir.join("include").join("common.glsl"), "").unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        fs::remove_file(dir.join("shader.frag")).unwrap();
+        assert!(watcher.poll());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
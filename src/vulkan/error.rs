@@ -0,0 +1,152 @@
+use std::fmt;
+
+use crate::app::graphics::load::LoadError;
+
+use super::VkResult;
+
+#[derive(Debug)]
+pub enum VulkanError {
+    Vk {
+        stage: &'static str,
+        result: VkResult,
+    },
+    Setup {
+        stage: &'static str,
+        message: String,
+    },
+    Load(LoadError),
+}
+
+impl From<LoadError> for VulkanError {
+    fn from(error: LoadError) -> Self {
+        VulkanError::Load(error)
+    }
+}
+
+impl VulkanError {
+    pub fn vk(stage: &'static str, result: VkResult) -> Self {
+        VulkanError::Vk { stage, result }
+    }
+
+    pub fn setup(stage: &'static str, message: &str) -> Self {
+        VulkanError::Setup {
+            stage,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for VulkanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VulkanError::Vk { stage, result } => write!(
+                f,
+                "Failed to {}: {:?} ({})",
+                stage,
+                result,
+                vk_result_description(*result)
+            ),
+            VulkanError::Setup { stage, message } => {
+                write!(f, "Failed to {}: {}", stage, message)
+            }
+            VulkanError::Load(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for VulkanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VulkanError::Load(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// Descriptions follow the "Return Codes" section of the Vulkan specification.
+#[allow(unreachable_patterns)]
+pub fn vk_result_description(result: VkResult) -> &'static str {
+    match result {
+        VkResult::VK_SUCCESS => "Command successfully completed",
+        VkResult::VK_NOT_READY => "A fence or query has not yet completed",
+        VkResult::VK_TIMEOUT => "A wait operation has not completed in the specified time",
+        VkResult::VK_EVENT_SET => "An event is signaled",
+        VkResult::VK_EVENT_RESET => "An event is unsignaled",
+        VkResult::VK_INCOMPLETE => "A return array was too small for the result",
+        VkResult::VK_ERROR_OUT_OF_HOST_MEMORY => "A host memory allocation has failed",
+        VkResult::VK_ERROR_OUT_OF_DEVICE_MEMORY => "A device memory allocation has failed",
+        VkResult::VK_ERROR_INITIALIZATION_FAILED => {
+            "Initialization of an object could not be completed for implementation-specific reasons"
+        }
+        VkResult::VK_ERROR_DEVICE_LOST => "The logical or physical device has been lost",
+        VkResult::VK_ERROR_MEMORY_MAP_FAILED => "Mapping of a memory object has failed",
+        VkResult::VK_ERROR_LAYER_NOT_PRESENT => {
+            "A requested layer is not present or could not be loaded"
+        }
+        VkResult::VK_ERROR_EXTENSION_NOT_PRESENT => "A requested extension is not supported",
+        VkResult::VK_ERROR_FEATURE_NOT_PRESENT => "A requested feature is not supported",
+        VkResult::VK_ERROR_INCOMPATIBLE_DRIVER => {
+            "The requested version of Vulkan is not supported by the driver"
+        }
+        VkResult::VK_ERROR_TOO_MANY_OBJECTS => {
+            "Too many objects of the type have already been created"
+        }
+        VkResult::VK_ERROR_FORMAT_NOT_SUPPORTED => {
+            "A requested format is not supported on this device"
+        }
+        VkResult::VK_ERROR_FRAGMENTED_POOL => {
+            "A pool allocation has failed due to fragmentation of the pool's memory"
+        }
+        VkResult::VK_ERROR_UNKNOWN => "An unknown error has occurred",
+        VkResult::VK_ERROR_OUT_OF_POOL_MEMORY => "A pool memory allocation has failed",
+        VkResult::VK_ERROR_INVALID_EXTERNAL_HANDLE => {
+            "An external handle is not a valid handle of the specified type"
+        }
+        VkResult::VK_ERROR_FRAGMENTATION => {
+            "A descriptor pool creation has failed due to fragmentation"
+        }
+        VkResult::VK_ERROR_INVALID_OPAQUE_CAPTURE_ADDRESS => {
+            "A buffer creation or memory allocation failed because the requested address is not available"
+        }
+        VkResult::VK_PIPELINE_COMPILE_REQUIRED => {
+            "A requested pipeline creation would have required compilation"
+        }
+        VkResult::VK_ERROR_SURFACE_LOST_KHR => "A surface is no longer available",
+        VkResult::VK_ERROR_NATIVE_WINDOW_IN_USE_KHR => {
+            "The requested window is already in use by Vulkan or another API"
+        }
+        VkResult::VK_SUBOPTIMAL_KHR => {
+            "A swapchain no longer matches the surface properties exactly"
+        }
+        VkResult::VK_ERROR_OUT_OF_DATE_KHR => {
+            "A surface has changed and is no longer compatible with the swapchain"
+        }
+        VkResult::VK_ERROR_INCOMPATIBLE_DISPLAY_KHR => {
+            "The display used by a swapchain does not use the same presentable image layout"
+        }
+        VkResult::VK_ERROR_VALIDATION_FAILED_EXT => "A command failed because of invalid usage",
+        VkResult::VK_ERROR_INVALID_SHADER_NV => "One or more shaders failed to compile or link",
+        VkResult::VK_ERROR_INVALID_DRM_FORMAT_MODIFIER_PLANE_LAYOUT_EXT => {
+            "The DRM format modifier plane layout is invalid"
+        }
+        VkResult::VK_ERROR_FULL_SCREEN_EXCLUSIVE_MODE_LOST_EXT => {
+            "The swapchain lost exclusive full-screen access"
+        }
+        VkResult::VK_THREAD_IDLE_KHR => {
+            "A deferred operation is not complete but there is no work for this thread"
+        }
+        VkResult::VK_THREAD_DONE_KHR => {
+            "A deferred operation is not complete but there is no work remaining to assign"
+        }
+        VkResult::VK_OPERATION_DEFERRED_KHR => {
+            "A deferred operation was requested and at least some of the work was deferred"
+        }
+        VkResult::VK_OPERATION_NOT_DEFERRED_KHR => {
+            "A deferred operation was requested and no operations were deferred"
+        }
+        VkResult::VK_ERROR_COMPRESSION_EXHAUSTED_EXT => {
+            "An image creation failed because internal resources required for compression are exhausted"
+        }
+        _ => "Unknown VkResult",
+    }
+}
@@ -1,40 +1,61 @@
+mod error;
 #[allow(dead_code)]
 mod ffi;
 
 use std::ffi::c_void;
 
+pub use error::VulkanError;
+
 pub use ffi::{
     PFN_vkCreateDebugUtilsMessengerEXT, PFN_vkDebugUtilsMessengerCallbackEXT,
-    PFN_vkDestroyDebugUtilsMessengerEXT, VkAccessFlagBits, VkAllocationCallbacks,
+    PFN_vkDestroyDebugUtilsMessengerEXT, VkAccessFlagBits, VkAccessFlags, VkAllocationCallbacks,
     VkApplicationInfo, VkAttachmentDescription, VkAttachmentLoadOp, VkAttachmentReference,
-    VkAttachmentStoreOp, VkBlendFactor, VkBlendOp, VkBool32, VkClearColorValue, VkClearValue,
+    VkAttachmentStoreOp, VkBlendFactor, VkBlendOp, VkBool32, VkBorderColor, VkBuffer, VkBufferCopy,
+    VkBufferCreateInfo, VkBufferImageCopy, VkBufferMemoryBarrier, VkBufferUsageFlagBits,
+    VkBufferUsageFlags, VkClearColorValue, VkClearDepthStencilValue, VkClearValue,
     VkColorComponentFlagBits, VkColorSpaceKHR, VkCommandBuffer, VkCommandBufferAllocateInfo,
-    VkCommandBufferBeginInfo, VkCommandBufferLevel, VkCommandBufferResetFlags, VkCommandPool,
-    VkCommandPoolCreateFlagBits, VkCommandPoolCreateInfo, VkComponentMapping, VkComponentSwizzle,
-    VkCompositeAlphaFlagBitsKHR, VkCullModeFlagBits, VkDebugUtilsMessageSeverityFlagBitsEXT,
-    VkDebugUtilsMessageSeverityFlagsEXT, VkDebugUtilsMessageTypeFlagBitsEXT,
-    VkDebugUtilsMessageTypeFlagsEXT, VkDebugUtilsMessengerCallbackDataEXT,
-    VkDebugUtilsMessengerCreateInfoEXT, VkDebugUtilsMessengerEXT, VkDevice, VkDeviceCreateInfo,
-    VkDeviceQueueCreateInfo, VkDynamicState, VkExtensionProperties, VkExtent2D, VkFence,
-    VkFenceCreateFlagBits, VkFenceCreateInfo, VkFormat, VkFramebuffer, VkFramebufferCreateInfo,
-    VkFrontFace, VkGraphicsPipelineCreateInfo, VkImage, VkImageAspectFlagBits, VkImageLayout,
-    VkImageSubresourceRange, VkImageUsageFlagBits, VkImageView, VkImageViewCreateInfo,
-    VkImageViewType, VkInstance, VkInstanceCreateFlags, VkInstanceCreateInfo, VkLayerProperties,
-    VkLogicOp, VkOffset2D, VkPhysicalDevice, VkPhysicalDeviceFeatures, VkPhysicalDeviceProperties,
-    VkPipeline, VkPipelineBindPoint, VkPipelineCache, VkPipelineColorBlendAttachmentState,
-    VkPipelineColorBlendStateCreateInfo, VkPipelineDynamicStateCreateInfo,
-    VkPipelineInputAssemblyStateCreateInfo, VkPipelineLayout, VkPipelineLayoutCreateInfo,
-    VkPipelineMultisampleStateCreateInfo, VkPipelineRasterizationStateCreateInfo,
-    VkPipelineShaderStageCreateInfo, VkPipelineStageFlagBits, VkPipelineStageFlags,
-    VkPipelineVertexInputStateCreateInfo, VkPipelineViewportStateCreateInfo, VkPolygonMode,
-    VkPresentInfoKHR, VkPresentModeKHR, VkPrimitiveTopology, VkQueue, VkQueueFamilyProperties,
-    VkQueueFlagBits, VkRect2D, VkRenderPass, VkRenderPassBeginInfo, VkRenderPassCreateInfo,
-    VkResult, VkSampleCountFlagBits, VkSemaphore, VkSemaphoreCreateInfo, VkShaderModule,
-    VkShaderModuleCreateInfo, VkShaderStageFlagBits, VkSharingMode, VkStructureType, VkSubmitInfo,
-    VkSubpassContents, VkSubpassDependency, VkSubpassDescription, VkSurfaceCapabilitiesKHR,
-    VkSurfaceFormatKHR, VkSurfaceKHR, VkSwapchainCreateInfoKHR, VkSwapchainKHR, VkViewport,
+    VkCommandBufferBeginInfo, VkCommandBufferLevel, VkCommandBufferResetFlags,
+    VkCommandBufferUsageFlagBits, VkCommandPool, VkCommandPoolCreateFlagBits,
+    VkCommandPoolCreateInfo, VkCompareOp, VkComponentMapping, VkComponentSwizzle,
+    VkCompositeAlphaFlagBitsKHR, VkCopyDescriptorSet, VkCullModeFlagBits,
+    VkDebugUtilsMessageSeverityFlagBitsEXT, VkDebugUtilsMessageSeverityFlagsEXT,
+    VkDebugUtilsMessageTypeFlagBitsEXT, VkDebugUtilsMessageTypeFlagsEXT,
+    VkDebugUtilsMessengerCallbackDataEXT, VkDebugUtilsMessengerCreateInfoEXT,
+    VkDebugUtilsMessengerEXT, VkDependencyFlags, VkDescriptorBufferInfo, VkDescriptorImageInfo,
+    VkDescriptorPool, VkDescriptorPoolCreateInfo, VkDescriptorPoolSize, VkDescriptorSet,
+    VkDescriptorSetAllocateInfo, VkDescriptorSetLayout, VkDescriptorSetLayoutBinding,
+    VkDescriptorSetLayoutCreateInfo, VkDescriptorType, VkDevice, VkDeviceCreateInfo,
+    VkDeviceMemory, VkDeviceQueueCreateInfo, VkDeviceSize, VkDynamicState, VkExtensionProperties,
+    VkExtent2D, VkExtent3D, VkFence, VkFenceCreateFlagBits, VkFenceCreateInfo, VkFilter, VkFormat,
+    VkFormatFeatureFlagBits, VkFormatFeatureFlags, VkFormatProperties, VkFramebuffer,
+    VkFramebufferCreateInfo, VkFrontFace, VkGraphicsPipelineCreateInfo, VkImage,
+    VkImageAspectFlagBits, VkImageAspectFlags, VkImageBlit, VkImageCreateInfo, VkImageLayout,
+    VkImageMemoryBarrier, VkImageSubresourceLayers, VkImageSubresourceRange, VkImageTiling,
+    VkImageType, VkImageUsageFlagBits, VkImageUsageFlags, VkImageView, VkImageViewCreateInfo,
+    VkImageViewType, VkIndexType, VkInstance, VkInstanceCreateFlags, VkInstanceCreateInfo,
+    VkLayerProperties, VkLogicOp, VkMemoryAllocateInfo, VkMemoryBarrier, VkMemoryHeapFlagBits,
+    VkMemoryMapFlags, VkMemoryPropertyFlagBits, VkMemoryPropertyFlags, VkMemoryRequirements,
+    VkOffset2D, VkOffset3D, VkPhysicalDevice, VkPhysicalDeviceFeatures,
+    VkPhysicalDeviceMemoryProperties, VkPhysicalDeviceProperties, VkPhysicalDeviceType, VkPipeline,
+    VkPipelineBindPoint, VkPipelineCache, VkPipelineColorBlendAttachmentState,
+    VkPipelineColorBlendStateCreateInfo, VkPipelineDepthStencilStateCreateInfo,
+    VkPipelineDynamicStateCreateInfo, VkPipelineInputAssemblyStateCreateInfo, VkPipelineLayout,
+    VkPipelineLayoutCreateInfo, VkPipelineMultisampleStateCreateInfo,
+    VkPipelineRasterizationStateCreateInfo, VkPipelineShaderStageCreateInfo,
+    VkPipelineStageFlagBits, VkPipelineStageFlags, VkPipelineVertexInputStateCreateInfo,
+    VkPipelineViewportStateCreateInfo, VkPolygonMode, VkPresentInfoKHR, VkPresentModeKHR,
+    VkPrimitiveTopology, VkPushConstantRange, VkQueue, VkQueueFamilyProperties, VkQueueFlagBits,
+    VkRect2D, VkRenderPass, VkRenderPassBeginInfo, VkRenderPassCreateInfo, VkResult,
+    VkSampleCountFlagBits, VkSampler, VkSamplerAddressMode, VkSamplerCreateInfo,
+    VkSamplerMipmapMode, VkSemaphore, VkSemaphoreCreateInfo, VkShaderModule,
+    VkShaderModuleCreateInfo, VkShaderStageFlagBits, VkSharingMode, VkSpecializationInfo,
+    VkSpecializationMapEntry, VkStencilOpState, VkStructureType, VkSubmitInfo, VkSubpassContents,
+    VkSubpassDependency, VkSubpassDescription, VkSurfaceCapabilitiesKHR, VkSurfaceFormatKHR,
+    VkSurfaceKHR, VkSwapchainCreateInfoKHR, VkSwapchainKHR, VkVertexInputAttributeDescription,
+    VkVertexInputBindingDescription, VkVertexInputRate, VkViewport, VkWriteDescriptorSet,
     VK_API_VERSION_1_0, VK_EXT_DEBUG_UTILS_EXTENSION_NAME, VK_FALSE,
-    VK_KHR_SWAPCHAIN_EXTENSION_NAME, VK_MAKE_API_VERSION, VK_SUBPASS_EXTERNAL, VK_TRUE,
+    VK_KHR_SWAPCHAIN_EXTENSION_NAME, VK_MAKE_API_VERSION, VK_QUEUE_FAMILY_IGNORED,
+    VK_SUBPASS_EXTERNAL, VK_TRUE,
 };
 
 pub fn vk_enumerate_instance_layer_properties(
@@ -543,24 +564,6 @@ pub fn vk_cmd_set_scissor(
     unsafe { ffi::vkCmdSetScissor(command_buffer, first_scissor, scissor_count, p_scissors) }
 }
 
-pub fn vk_cmd_draw(
-    command_buffer: VkCommandBuffer,
-    vertex_count: u32,
-    instance_count: u32,
-    first_vertex: u32,
-    first_instance: u32,
-) -> c_void {
-    unsafe {
-        ffi::vkCmdDraw(
-            command_buffer,
-            vertex_count,
-            instance_count,
-            first_vertex,
-            first_instance,
-        )
-    }
-}
-
 pub fn vk_cmd_end_render_pass(command_buffer: VkCommandBuffer) -> c_void {
     unsafe { ffi::vkCmdEndRenderPass(command_buffer) }
 }
@@ -581,3 +584,401 @@ pub fn vk_queue_submit(
 pub fn vk_queue_present_khr(queue: VkQueue, p_present_info: *const VkPresentInfoKHR) -> VkResult {
     unsafe { ffi::vkQueuePresentKHR(queue, p_present_info) }
 }
+
+pub fn vk_create_buffer(
+    device: VkDevice,
+    p_create_info: *const VkBufferCreateInfo,
+    p_allocator: *const VkAllocationCallbacks,
+    p_buffer: *mut VkBuffer,
+) -> VkResult {
+    unsafe { ffi::vkCreateBuffer(device, p_create_info, p_allocator, p_buffer) }
+}
+
+pub fn vk_destroy_buffer(
+    device: VkDevice,
+    buffer: VkBuffer,
+    p_allocator: *const VkAllocationCallbacks,
+) -> c_void {
+    unsafe { ffi::vkDestroyBuffer(device, buffer, p_allocator) }
+}
+
+pub fn vk_get_buffer_memory_requirements(
+    device: VkDevice,
+    buffer: VkBuffer,
+    p_memory_requirements: *mut VkMemoryRequirements,
+) -> c_void {
+    unsafe { ffi::vkGetBufferMemoryRequirements(device, buffer, p_memory_requirements) }
+}
+
+pub fn vk_get_physical_device_memory_properties(
+    physical_device: VkPhysicalDevice,
+    p_memory_properties: *mut VkPhysicalDeviceMemoryProperties,
+) -> c_void {
+    unsafe { ffi::vkGetPhysicalDeviceMemoryProperties(physical_device, p_memory_properties) }
+}
+
+pub fn vk_allocate_memory(
+    device: VkDevice,
+    p_allocate_info: *const VkMemoryAllocateInfo,
+    p_allocator: *const VkAllocationCallbacks,
+    p_memory: *mut VkDeviceMemory,
+) -> VkResult {
+    unsafe { ffi::vkAllocateMemory(device, p_allocate_info, p_allocator, p_memory) }
+}
+
+pub fn vk_free_memory(
+    device: VkDevice,
+    memory: VkDeviceMemory,
+    p_allocator: *const VkAllocationCallbacks,
+) -> c_void {
+    unsafe { ffi::vkFreeMemory(device, memory, p_allocator) }
+}
+
+pub fn vk_bind_buffer_memory(
+    device: VkDevice,
+    buffer: VkBuffer,
+    memory: VkDeviceMemory,
+    memory_offset: VkDeviceSize,
+) -> VkResult {
+    unsafe { ffi::vkBindBufferMemory(device, buffer, memory, memory_offset) }
+}
+
+pub fn vk_map_memory(
+    device: VkDevice,
+    memory: VkDeviceMemory,
+    offset: VkDeviceSize,
+    size: VkDeviceSize,
+    flags: VkMemoryMapFlags,
+    pp_data: *mut *mut c_void,
+) -> VkResult {
+    unsafe { ffi::vkMapMemory(device, memory, offset, size, flags, pp_data) }
+}
+
+pub fn vk_unmap_memory(device: VkDevice, memory: VkDeviceMemory) -> c_void {
+    unsafe { ffi::vkUnmapMemory(device, memory) }
+}
+
+pub fn vk_cmd_bind_vertex_buffers(
+    command_buffer: VkCommandBuffer,
+    first_binding: u32,
+    binding_count: u32,
+    p_buffers: *const VkBuffer,
+    p_offsets: *const VkDeviceSize,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdBindVertexBuffers(
+            command_buffer,
+            first_binding,
+            binding_count,
+            p_buffers,
+            p_offsets,
+        )
+    }
+}
+
+pub fn vk_cmd_bind_index_buffer(
+    command_buffer: VkCommandBuffer,
+    buffer: VkBuffer,
+    offset: VkDeviceSize,
+    index_type: VkIndexType,
+) -> c_void {
+    unsafe { ffi::vkCmdBindIndexBuffer(command_buffer, buffer, offset, index_type) }
+}
+
+pub fn vk_free_command_buffers(
+    device: VkDevice,
+    command_pool: VkCommandPool,
+    command_buffer_count: u32,
+    p_command_buffers: *const VkCommandBuffer,
+) -> c_void {
+    unsafe {
+        ffi::vkFreeCommandBuffers(
+            device,
+            command_pool,
+            command_buffer_count,
+            p_command_buffers,
+        )
+    }
+}
+
+pub fn vk_queue_wait_idle(queue: VkQueue) -> VkResult {
+    unsafe { ffi::vkQueueWaitIdle(queue) }
+}
+
+pub fn vk_cmd_copy_buffer(
+    command_buffer: VkCommandBuffer,
+    src_buffer: VkBuffer,
+    dst_buffer: VkBuffer,
+    region_count: u32,
+    p_regions: *const VkBufferCopy,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdCopyBuffer(
+            command_buffer,
+            src_buffer,
+            dst_buffer,
+            region_count,
+            p_regions,
+        )
+    }
+}
+
+pub fn vk_cmd_copy_buffer_to_image(
+    command_buffer: VkCommandBuffer,
+    src_buffer: VkBuffer,
+    dst_image: VkImage,
+    dst_image_layout: VkImageLayout,
+    region_count: u32,
+    p_regions: *const VkBufferImageCopy,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdCopyBufferToImage(
+            command_buffer,
+            src_buffer,
+            dst_image,
+            dst_image_layout,
+            region_count,
+            p_regions,
+        )
+    }
+}
+
+pub fn vk_cmd_copy_image_to_buffer(
+    command_buffer: VkCommandBuffer,
+    src_image: VkImage,
+    src_image_layout: VkImageLayout,
+    dst_buffer: VkBuffer,
+    region_count: u32,
+    p_regions: *const VkBufferImageCopy,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdCopyImageToBuffer(
+            command_buffer,
+            src_image,
+            src_image_layout,
+            dst_buffer,
+            region_count,
+            p_regions,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vk_cmd_blit_image(
+    command_buffer: VkCommandBuffer,
+    src_image: VkImage,
+    src_image_layout: VkImageLayout,
+    dst_image: VkImage,
+    dst_image_layout: VkImageLayout,
+    region_count: u32,
+    p_regions: *const VkImageBlit,
+    filter: VkFilter,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdBlitImage(
+            command_buffer,
+            src_image,
+            src_image_layout,
+            dst_image,
+            dst_image_layout,
+            region_count,
+            p_regions,
+            filter,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vk_cmd_pipeline_barrier(
+    command_buffer: VkCommandBuffer,
+    src_stage_mask: VkPipelineStageFlags,
+    dst_stage_mask: VkPipelineStageFlags,
+    dependency_flags: VkDependencyFlags,
+    memory_barrier_count: u32,
+    p_memory_barriers: *const VkMemoryBarrier,
+    buffer_memory_barrier_count: u32,
+    p_buffer_memory_barriers: *const VkBufferMemoryBarrier,
+    image_memory_barrier_count: u32,
+    p_image_memory_barriers: *const VkImageMemoryBarrier,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdPipelineBarrier(
+            command_buffer,
+            src_stage_mask,
+            dst_stage_mask,
+            dependency_flags,
+            memory_barrier_count,
+            p_memory_barriers,
+            buffer_memory_barrier_count,
+            p_buffer_memory_barriers,
+            image_memory_barrier_count,
+            p_image_memory_barriers,
+        )
+    }
+}
+
+pub fn vk_create_descriptor_set_layout(
+    device: VkDevice,
+    p_create_info: *const VkDescriptorSetLayoutCreateInfo,
+    p_allocator: *const VkAllocationCallbacks,
+    p_set_layout: *mut VkDescriptorSetLayout,
+) -> VkResult {
+    unsafe { ffi::vkCreateDescriptorSetLayout(device, p_create_info, p_allocator, p_set_layout) }
+}
+
+pub fn vk_destroy_descriptor_set_layout(
+    device: VkDevice,
+    descriptor_set_layout: VkDescriptorSetLayout,
+    p_allocator: *const VkAllocationCallbacks,
+) -> c_void {
+    unsafe { ffi::vkDestroyDescriptorSetLayout(device, descriptor_set_layout, p_allocator) }
+}
+
+pub fn vk_create_descriptor_pool(
+    device: VkDevice,
+    p_create_info: *const VkDescriptorPoolCreateInfo,
+    p_allocator: *const VkAllocationCallbacks,
+    p_descriptor_pool: *mut VkDescriptorPool,
+) -> VkResult {
+    unsafe { ffi::vkCreateDescriptorPool(device, p_create_info, p_allocator, p_descriptor_pool) }
+}
+
+pub fn vk_destroy_descriptor_pool(
+    device: VkDevice,
+    descriptor_pool: VkDescriptorPool,
+    p_allocator: *const VkAllocationCallbacks,
+) -> c_void {
+    unsafe { ffi::vkDestroyDescriptorPool(device, descriptor_pool, p_allocator) }
+}
+
+pub fn vk_allocate_descriptor_sets(
+    device: VkDevice,
+    p_allocate_info: *const VkDescriptorSetAllocateInfo,
+    p_descriptor_sets: *mut VkDescriptorSet,
+) -> VkResult {
+    unsafe { ffi::vkAllocateDescriptorSets(device, p_allocate_info, p_descriptor_sets) }
+}
+
+pub fn vk_update_descriptor_sets(
+    device: VkDevice,
+    descriptor_write_count: u32,
+    p_descriptor_writes: *const VkWriteDescriptorSet,
+    descriptor_copy_count: u32,
+    p_descriptor_copies: *const VkCopyDescriptorSet,
+) -> c_void {
+    unsafe {
+        ffi::vkUpdateDescriptorSets(
+            device,
+            descriptor_write_count,
+            p_descriptor_writes,
+            descriptor_copy_count,
+            p_descriptor_copies,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vk_cmd_bind_descriptor_sets(
+    command_buffer: VkCommandBuffer,
+    pipeline_bind_point: VkPipelineBindPoint,
+    layout: VkPipelineLayout,
+    first_set: u32,
+    descriptor_set_count: u32,
+    p_descriptor_sets: *const VkDescriptorSet,
+    dynamic_offset_count: u32,
+    p_dynamic_offsets: *const u32,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdBindDescriptorSets(
+            command_buffer,
+            pipeline_bind_point,
+            layout,
+            first_set,
+            descriptor_set_count,
+            p_descriptor_sets,
+            dynamic_offset_count,
+            p_dynamic_offsets,
+        )
+    }
+}
+
+pub fn vk_create_image(
+    device: VkDevice,
+    p_create_info: *const VkImageCreateInfo,
+    p_allocator: *const VkAllocationCallbacks,
+    p_image: *mut VkImage,
+) -> VkResult {
+    unsafe { ffi::vkCreateImage(device, p_create_info, p_allocator, p_image) }
+}
+
+pub fn vk_destroy_image(
+    device: VkDevice,
+    image: VkImage,
+    p_allocator: *const VkAllocationCallbacks,
+) -> c_void {
+    unsafe { ffi::vkDestroyImage(device, image, p_allocator) }
+}
+
+pub fn vk_get_image_memory_requirements(
+    device: VkDevice,
+    image: VkImage,
+    p_memory_requirements: *mut VkMemoryRequirements,
+) -> c_void {
+    unsafe { ffi::vkGetImageMemoryRequirements(device, image, p_memory_requirements) }
+}
+
+pub fn vk_bind_image_memory(
+    device: VkDevice,
+    image: VkImage,
+    memory: VkDeviceMemory,
+    memory_offset: VkDeviceSize,
+) -> VkResult {
+    unsafe { ffi::vkBindImageMemory(device, image, memory, memory_offset) }
+}
+
+pub fn vk_create_sampler(
+    device: VkDevice,
+    p_create_info: *const VkSamplerCreateInfo,
+    p_allocator: *const VkAllocationCallbacks,
+    p_sampler: *mut VkSampler,
+) -> VkResult {
+    unsafe { ffi::vkCreateSampler(device, p_create_info, p_allocator, p_sampler) }
+}
+
+pub fn vk_destroy_sampler(
+    device: VkDevice,
+    sampler: VkSampler,
+    p_allocator: *const VkAllocationCallbacks,
+) -> c_void {
+    unsafe { ffi::vkDestroySampler(device, sampler, p_allocator) }
+}
+
+pub fn vk_get_physical_device_format_properties(
+    physical_device: VkPhysicalDevice,
+    format: VkFormat,
+    p_format_properties: *mut VkFormatProperties,
+) -> c_void {
+    unsafe {
+        ffi::vkGetPhysicalDeviceFormatProperties(physical_device, format, p_format_properties)
+    }
+}
+
+pub fn vk_cmd_draw_indexed(
+    command_buffer: VkCommandBuffer,
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    vertex_offset: i32,
+    first_instance: u32,
+) -> c_void {
+    unsafe {
+        ffi::vkCmdDrawIndexed(
+            command_buffer,
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        )
+    }
+}
@@ -0,0 +1,9 @@
+#version 450
+
+#include <broken.glsl>
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = vec4(tint(), 1.0);
+}
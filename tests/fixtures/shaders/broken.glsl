@@ -0,0 +1,5 @@
+// The error has to be reported on line 4 of this file, not of broken.frag.
+
+vec3 tint() {
+    return colour;
+}
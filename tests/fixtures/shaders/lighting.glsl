@@ -0,0 +1,5 @@
+const vec3 LIGHT_DIRECTION = vec3(0.4, 0.6, 0.7);
+
+float lambert(vec3 normal) {
+    return AMBIENT + (1.0 - AMBIENT) * max(dot(normal, normalize(LIGHT_DIRECTION)), 0.0);
+}
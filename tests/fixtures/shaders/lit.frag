@@ -0,0 +1,12 @@
+#version 450
+
+// AMBIENT has to be defined by the compiler.
+#include "lighting.glsl"
+
+layout(location = 0) in vec3 fragNormal;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = vec4(vec3(lambert(normalize(fragNormal))), 1.0);
+}